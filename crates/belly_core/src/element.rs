@@ -20,7 +20,7 @@ impl Plugin for ElementsPlugin {
         app.init_resource::<ElementIdIndex>();
         app.add_systems(
             PostUpdate,
-            invalidate_elements
+            (invalidate_elements, invalidate_structure)
                 .in_set(InvalidateElements)
                 .before(UiSystem::Layout),
         );
@@ -101,6 +101,8 @@ pub struct Element {
     pub(crate) id: Option<Tag>,
     pub classes: HashSet<Tag>,
     pub state: HashSet<Tag>,
+    /// Plain params the element was built with, matched by `[name=value]` selectors
+    pub attributes: HashMap<Tag, String>,
     pub styles: HashMap<Tag, PropertyValue>,
 }
 
@@ -130,6 +132,45 @@ impl Element {
     }
 }
 
+/// Position of the element among its siblings, used by the
+/// structural selectors like `:first-child` or `:nth-child(2n)`
+#[derive(Default, Clone, Copy, Debug, PartialEq)]
+pub struct ElementPosition {
+    /// Index of the element among the non-virtual element siblings
+    pub index: usize,
+    /// Number of the non-virtual element siblings, including the element itself
+    pub siblings: usize,
+    /// Number of the element children (including text and other virtual elements)
+    pub children: usize,
+}
+
+impl ElementPosition {
+    pub fn resolve(
+        entity: Entity,
+        siblings: Option<&Children>,
+        children: Option<&Children>,
+        is_element: impl Fn(Entity) -> bool,
+    ) -> ElementPosition {
+        let mut position = ElementPosition {
+            index: 0,
+            siblings: 1,
+            children: children.map(|c| c.len()).unwrap_or(0),
+        };
+        if let Some(siblings) = siblings {
+            position.siblings = 0;
+            for sibling in siblings.iter().copied() {
+                if sibling == entity {
+                    position.index = position.siblings;
+                    position.siblings += 1;
+                } else if is_element(sibling) {
+                    position.siblings += 1;
+                }
+            }
+        }
+        position
+    }
+}
+
 #[derive(Resource, Deref, DerefMut, Default)]
 pub struct ElementIdIndex(HashMap<Tag, Entity>);
 
@@ -172,6 +213,7 @@ pub struct Elements<'w, 's> {
     pub(crate) commands: ElementCommands<'w, 's>,
     pub(crate) elements: Query<'w, 's, ElementsQuery, ()>,
     pub(crate) children: Query<'w, 's, ChildrenQuery, ()>,
+    pub(crate) parents: Query<'w, 's, &'static Parent>,
     pub(crate) id_index: Res<'w, ElementIdIndex>,
     states: Local<'s, HashMap<Entity, HashMap<Tag, bool>>>,
     classes: Local<'s, HashMap<Entity, HashSet<Tag>>>,
@@ -217,7 +259,7 @@ impl<'w, 's> Elements<'w, 's> {
                 entities: result,
             };
         }
-        let mut branch = vec![];
+        let mut branch = ElementsBranch::new();
         if let Some(id) = selector.get_root_id() {
             // indexed-by-id branch lookup
             if let Some(entity) = self.id_index.get(&id) {
@@ -227,41 +269,41 @@ impl<'w, 's> Elements<'w, 's> {
             }
         } else {
             for root in self.roots.iter() {
-                // branch.append(&*entuty);
                 self.select_branch(root, &mut branch, &selector, &mut result);
             }
         }
+        drop(branch);
         SelectedElements {
             elements: self,
             entities: result,
         }
     }
 
-    fn select_branch(
-        &self,
+    fn select_branch<'e>(
+        &'e self,
         entity: Entity,
-        element_ptrs: &mut Vec<*const Element>,
+        branch: &mut ElementsBranch<'e>,
         selector: &Selector,
         result: &mut Vec<Entity>,
     ) {
-        let Ok(elem) = self.elements.get(entity) else {
+        let pushed = branch.push_entity(
+            entity,
+            |e| self.parents.get(e).ok().map(|p| p.get()),
+            |e| self.children.get(e).ok().map(|c| c.children),
+            |e| self.elements.get(e).ok().map(|e| e.element),
+        );
+        if !pushed {
             return;
-        };
-        let elem = &*elem as *const Element;
-        element_ptrs.push(elem);
-        let mut branch = ElementsBranch::new();
-        for e in element_ptrs.iter() {
-            branch.append(unsafe { e.as_ref().unwrap() })
         }
-        if selector.matches(&branch) {
+        if selector.matches(&*branch) {
             result.push(entity);
         }
         if let Ok(children) = self.children.get(entity) {
             for ch in children.children {
-                self.select_branch(*ch, element_ptrs, selector, result);
+                self.select_branch(*ch, branch, selector, result);
             }
         }
-        element_ptrs.pop();
+        branch.pop_entity();
     }
 
     pub fn set_state(&mut self, entity: Entity, state: Tag, value: bool) {
@@ -539,10 +581,32 @@ impl Command for CleanupElementCommand {
     }
 }
 
+/// Invalidates the elements which children were added or removed, so
+/// the structural selectors (`:first-child`, `:nth-child()`, `:empty`, etc)
+/// are resolved against the actual tree. Only the element itself and its
+/// direct children are invalidated: the children are the only elements which
+/// positions or preceding siblings (for `+` and `~` combinators) may change.
+pub fn invalidate_structure(
+    changed: Query<Entity, Changed<Children>>,
+    mut removed: RemovedComponents<Children>,
+    children: Query<&Children>,
+    mut elements: Query<&mut Element>,
+) {
+    for entity in changed.iter().chain(removed.iter()) {
+        let chs = children.get(entity).into_iter().flat_map(|chs| chs.iter());
+        for entity in std::iter::once(entity).chain(chs.copied()) {
+            if let Ok(mut element) = elements.get_mut(entity) {
+                element.invalidate();
+            }
+        }
+    }
+}
+
 #[derive(Component, Default)]
 pub struct InvalidateElement;
 pub fn invalidate_elements(
     invalid: Query<Entity, With<InvalidateElement>>,
+    parents: Query<&Parent>,
     children: Query<&Children>,
     mut elements: Query<&mut Element>,
     mut invalidated: Local<HashSet<Entity>>,
@@ -551,6 +615,16 @@ pub fn invalidate_elements(
     invalidated.clear();
     for entity in invalid.iter() {
         invalidate_children(entity, &children, &mut elements, invalidated.deref_mut());
+        // the following siblings may be selected by `+` or `~` combinators
+        let siblings = parents
+            .get(entity)
+            .ok()
+            .and_then(|p| children.get(p.get()).ok());
+        if let Some(siblings) = siblings {
+            for sibling in siblings.iter().skip_while(|s| **s != entity).skip(1) {
+                invalidate_children(*sibling, &children, &mut elements, invalidated.deref_mut());
+            }
+        }
         if let Some(mut entity) = commands.get_entity(entity) {
             entity.remove::<InvalidateElement>();
         }
@@ -1,7 +1,10 @@
 use super::{Params, StyleParams, Variant};
 use crate::{
     element::{Element, ElementIdIndex},
-    ess::{PropertyExtractor, PropertyTransformer, StyleRule, StyleSheetParser},
+    ess::{
+        AnimationEvent, AnimationEventKind, PropertyExtractor, PropertyTransformer, StyleRule,
+        StyleSheetParser,
+    },
     relations::connect::{EventFilter, EventSource},
     tags,
 };
@@ -379,6 +382,7 @@ pub trait Widget {
     }
 
     fn build(&self, world: &mut World, mut data: WidgetData) {
+        let attributes = data.params.attributes();
         let components = self.instantiate_components(world, &mut data.params);
         let mut queue = CommandQueue::default();
         let commands = Commands::new(&mut queue, world);
@@ -451,6 +455,7 @@ pub trait Widget {
             element.aliases = aliases;
             element.id = id;
             element.classes.extend(classes);
+            element.attributes.extend(attributes);
             element.styles.extend(styles);
         });
 
@@ -528,6 +533,24 @@ impl DefaultSignals {
     pub fn ready(&self) -> EventFilter<ReadyEvent> {
         EventFilter::Entity(|e| EventSource::single(e.0))
     }
+    pub fn animation_start(&self) -> EventFilter<AnimationEvent> {
+        EventFilter::Entity(|e| match e.kind {
+            AnimationEventKind::Start => EventSource::single(e.entity),
+            _ => EventSource::none(),
+        })
+    }
+    pub fn animation_iteration(&self) -> EventFilter<AnimationEvent> {
+        EventFilter::Entity(|e| match e.kind {
+            AnimationEventKind::Iteration => EventSource::single(e.entity),
+            _ => EventSource::none(),
+        })
+    }
+    pub fn animation_end(&self) -> EventFilter<AnimationEvent> {
+        EventFilter::Entity(|e| match e.kind {
+            AnimationEventKind::End => EventSource::single(e.entity),
+            _ => EventSource::none(),
+        })
+    }
 }
 
 pub struct Eml {
@@ -9,6 +9,7 @@ use bevy::{
     ecs::system::EntityCommands,
     utils::{HashMap, HashSet},
 };
+use std::any::Any;
 use std::fmt::Display;
 use std::{fmt::Debug, mem};
 use tagstr::*;
@@ -190,6 +191,23 @@ impl Params {
             default
         }
     }
+    /// Returns string representation of the plain params (strings, numbers
+    /// and `true` flags) without consuming them.
+    pub fn attributes(&self) -> HashMap<Tag, String> {
+        let mut attributes = HashMap::default();
+        for (name, param) in self.rest.iter() {
+            let value = match &param.value {
+                Variant::String(s) => Some(s.clone()),
+                Variant::Bool(true) => Some("true".to_string()),
+                Variant::Boxed(b) => plain_string(b.as_ref()),
+                _ => None,
+            };
+            if let Some(value) = value {
+                attributes.insert(*name, value);
+            }
+        }
+        attributes
+    }
     pub fn apply_commands(&mut self, for_param: Tag, commands: &mut EntityCommands) {
         if let Some(param_commands) = self.commands(for_param) {
             param_commands(commands)
@@ -249,6 +267,23 @@ macro_rules! bindattr {
     };
 }
 
+/// Returns string representation of the boxed plain value: the strings,
+/// the numbers and the `true` flags.
+fn plain_string(value: &dyn Any) -> Option<String> {
+    macro_rules! downcast {
+        ($($ty:ty),*) => {
+            $(if let Some(value) = value.downcast_ref::<$ty>() {
+                return Some(value.to_string());
+            })*
+        };
+    }
+    if let Some(flag) = value.downcast_ref::<bool>() {
+        return flag.then(|| "true".to_string());
+    }
+    downcast!(String, &str, f32, f64, u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+    None
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -302,4 +337,34 @@ mod test {
             Some(&"black".to_string())
         );
     }
+
+    #[test]
+    fn test_boxed_attributes() {
+        let mut attrs = Params::default();
+        attrs.add(Param::new("label", Variant::boxed("panel".to_string())));
+        attrs.add(Param::new("count", Variant::boxed(3i32)));
+        attrs.add(Param::new("size", Variant::boxed(12usize)));
+        attrs.add(Param::new("checked", Variant::boxed(true)));
+        attrs.add(Param::new("hidden", Variant::boxed(false)));
+        attrs.add(Param::new(
+            "entity",
+            Variant::Entity(bevy::prelude::Entity::from_raw(0)),
+        ));
+        let attributes = attrs.attributes();
+        let matches = |name: &str, matcher: AttributeMatcher| {
+            attributes
+                .get(&name.as_tag())
+                .map(|value| matcher.matches(value))
+                .unwrap_or(false)
+        };
+        assert!(matches(
+            "label",
+            AttributeMatcher::Equals("panel".to_string())
+        ));
+        assert!(matches("count", AttributeMatcher::Equals("3".to_string())));
+        assert!(matches("size", AttributeMatcher::Prefix("1".to_string())));
+        assert!(matches("checked", AttributeMatcher::Exists));
+        assert!(!matches("hidden", AttributeMatcher::Exists));
+        assert!(!matches("entity", AttributeMatcher::Exists));
+    }
 }
@@ -2,7 +2,7 @@ use std::any::type_name;
 
 use crate::{
     eml::Params,
-    ess::{ColorFromHexExtension, StyleProperty, StylePropertyMethods},
+    ess::{ColorFunctionsExtension, LengthRect, StyleProperty, StylePropertyMethods},
     ElementsError,
 };
 use bevy::{asset::Asset, prelude::*};
@@ -143,7 +143,7 @@ impl TryFrom<Variant> for Color {
     type Error = String;
     fn try_from(value: Variant) -> Result<Self, Self::Error> {
         match value {
-            Variant::String(s) => Color::try_from_hex(s),
+            Variant::String(s) => Color::try_from_css(s),
             Variant::Boxed(v) => v
                 .downcast::<Color>()
                 .map(|b| *b)
@@ -231,6 +231,26 @@ impl TryFrom<Variant> for UiRect {
     }
 }
 
+impl TryFrom<Variant> for LengthRect {
+    type Error = ElementsError;
+    fn try_from(value: Variant) -> Result<Self, Self::Error> {
+        match value {
+            Variant::String(unparsed) => {
+                StyleProperty::try_from(unparsed).and_then(|prop| LengthRect::parse(&prop))
+            }
+            Variant::Style(prop) => LengthRect::parse(&prop),
+            Variant::Boxed(value) if value.is::<UiRect>() => {
+                Ok(LengthRect::from(*value.downcast::<UiRect>().unwrap()))
+            }
+            variant => variant
+                .take::<LengthRect>()
+                .ok_or(ElementsError::InvalidPropertyValue(format!(
+                    "Can't extract rect from variant"
+                ))),
+        }
+    }
+}
+
 impl<T: Asset> From<Handle<T>> for Variant {
     fn from(value: Handle<T>) -> Self {
         Variant::boxed(value)
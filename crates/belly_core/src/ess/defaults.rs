@@ -1,4 +1,5 @@
 use crate::eml::WidgetRegistry;
+use crate::ess::property::shadow::shadow_image;
 use crate::ess::PropertyExtractor;
 use crate::ess::PropertyTransformer;
 use crate::ess::StyleSheet;
@@ -12,11 +13,13 @@ pub struct Defaults {
     pub bold_font: Handle<Font>,
     pub bold_italic_font: Handle<Font>,
     pub style_sheet: Handle<StyleSheet>,
+    pub box_shadow_image: Handle<Image>,
 }
 
 pub fn setup_defaults(
     mut commands: Commands,
     mut fonts: ResMut<Assets<Font>>,
+    mut images: ResMut<Assets<Image>>,
     mut defaults: ResMut<Defaults>,
     widgets: Res<WidgetRegistry>,
     extractor: Res<PropertyExtractor>,
@@ -38,14 +41,12 @@ pub fn setup_defaults(
     let font_asset = Font::try_from_bytes(font_bytes).unwrap();
     let font_handle = fonts.add(font_asset);
     defaults.bold_italic_font = font_handle;
+    defaults.box_shadow_image = images.add(shadow_image());
 
     let parser = StyleSheetParser::new(validator.clone(), extractor.clone());
     let mut rules = parser.parse(
         r#"
             * {
-                font: regular;
-                color: #cfcfcf;
-                font-size: 22px;
                 display: flex;
                 background-color: transparent;
             }
@@ -0,0 +1,189 @@
+use super::{parser::EssRule, StyleSheetParser};
+use crate::ElementsError;
+use bevy::{asset::LoadContext, utils::HashMap};
+use std::path::{Component, Path, PathBuf};
+
+/// Resolves the `@import` path relative to the importing stylesheet.
+/// Absolute paths are resolved relative to the assets folder.
+pub(crate) fn import_path(from: &Path, import: &str) -> PathBuf {
+    let joined = from.parent().unwrap_or(Path::new("")).join(import);
+    let mut path = PathBuf::new();
+    for component in joined.components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::ParentDir => {
+                path.pop();
+            }
+            Component::RootDir | Component::Prefix(_) => path = PathBuf::new(),
+            Component::CurDir => {}
+        }
+    }
+    path
+}
+
+fn direct_imports(parser: &StyleSheetParser, path: &Path, source: &str) -> Vec<PathBuf> {
+    parser
+        .parse_rules(source)
+        .into_iter()
+        .filter_map(|rule| match rule {
+            EssRule::Import { path: import, .. } => Some(import_path(path, &import)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Reads the sources of all stylesheets imported by the root one directly or
+/// through other stylesheets. Every import becomes a dependency of the root
+/// asset: the root asset is reloaded when any imported file changes.
+pub(crate) async fn load_imports(
+    parser: &StyleSheetParser,
+    load_context: &LoadContext<'_>,
+    root: &Path,
+    source: &str,
+) -> Result<HashMap<PathBuf, String>, bevy::asset::Error> {
+    let mut sources = HashMap::default();
+    sources.insert(root.to_path_buf(), source.to_string());
+    let mut queue = vec![root.to_path_buf()];
+    while let Some(path) = queue.pop() {
+        let imports = direct_imports(parser, &path, &sources[&path]);
+        for import in imports {
+            if sources.contains_key(&import) {
+                continue;
+            }
+            let bytes = load_context.read_asset_bytes(&import).await.map_err(|e| {
+                bevy::asset::Error::msg(format!(
+                    "Unable to import {} from {}: {}",
+                    import.display(),
+                    path.display(),
+                    e
+                ))
+            })?;
+            sources.insert(import.clone(), String::from_utf8(bytes)?);
+            queue.push(import);
+        }
+    }
+    Ok(sources)
+}
+
+/// Parses the stylesheet at `path` replacing `@import` rules with the rules
+/// of imported stylesheets. The imported rules are placed where the `@import`
+/// rule is, so the later rules of importing stylesheet win.
+pub(crate) fn resolve_imports(
+    parser: &StyleSheetParser,
+    path: &Path,
+    sources: &HashMap<PathBuf, String>,
+) -> Result<Vec<EssRule>, ElementsError> {
+    let mut stack = vec![];
+    resolve(parser, path, sources, &mut stack)
+}
+
+fn resolve(
+    parser: &StyleSheetParser,
+    path: &Path,
+    sources: &HashMap<PathBuf, String>,
+    stack: &mut Vec<PathBuf>,
+) -> Result<Vec<EssRule>, ElementsError> {
+    if stack.iter().any(|p| p == path) {
+        let cycle = stack
+            .iter()
+            .skip_while(|p| *p != path)
+            .chain(std::iter::once(&path.to_path_buf()))
+            .map(|p| p.display().to_string())
+            .collect::<Vec<_>>()
+            .join(" -> ");
+        return Err(ElementsError::ImportCycle(cycle));
+    }
+    let Some(source) = sources.get(path) else {
+        return Ok(vec![]);
+    };
+    stack.push(path.to_path_buf());
+    let mut rules = vec![];
+    for rule in parser.parse_rules(source) {
+        match rule {
+            EssRule::Import {
+                path: import,
+                media,
+            } => {
+                let import = import_path(path, &import);
+                for rule in resolve(parser, &import, sources, stack)? {
+                    rules.push(media.iter().rev().fold(rule, |rule, m| rule.with_media(m)));
+                }
+            }
+            rule => rules.push(rule),
+        }
+    }
+    stack.pop();
+    Ok(rules)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ess::{PropertyExtractor, PropertyTransformer, StyleSheet};
+    use tagstr::AsTag;
+
+    fn files(files: &[(&str, &str)]) -> HashMap<PathBuf, String> {
+        files
+            .iter()
+            .map(|(path, source)| (PathBuf::from(path), source.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn resolve_import_paths() {
+        let from = Path::new("styles/theme.ess");
+        assert_eq!(
+            import_path(from, "colors.ess"),
+            Path::new("styles/colors.ess")
+        );
+        assert_eq!(
+            import_path(from, "./hud/../buttons.ess"),
+            Path::new("styles/buttons.ess")
+        );
+        assert_eq!(import_path(from, "../common.ess"), Path::new("common.ess"));
+        assert_eq!(import_path(from, "/fonts.ess"), Path::new("fonts.ess"));
+    }
+
+    #[test]
+    fn resolve_nested_imports() {
+        let parser =
+            StyleSheetParser::new(PropertyTransformer::default(), PropertyExtractor::default());
+        let sources = files(&[
+            (
+                "theme.ess",
+                r#"@import "ui/buttons.ess"; a {} @import url(hud.ess) (max-width: 900px); b {}"#,
+            ),
+            ("ui/buttons.ess", r#"@import "../colors.ess"; button {}"#),
+            ("colors.ess", "c {}"),
+            ("hud.ess", "d {}"),
+        ]);
+        let rules = resolve_imports(&parser, Path::new("theme.ess"), &sources).unwrap();
+        let stylesheet: StyleSheet = rules.into_iter().collect();
+        assert_eq!(stylesheet.len(), 5);
+        for (rule, tag) in stylesheet.iter().zip(["c", "button", "a", "d", "b"]) {
+            assert!(
+                rule.selector.entries()[0].has_tag(tag.as_tag()),
+                "Should be {tag}"
+            );
+        }
+        assert!(stylesheet[2].media.is_empty());
+        assert_eq!(stylesheet[3].media.len(), 1);
+    }
+
+    #[test]
+    fn detect_import_cycles() {
+        let parser =
+            StyleSheetParser::new(PropertyTransformer::default(), PropertyExtractor::default());
+        let sources = files(&[
+            ("a.ess", r#"@import "b.ess";"#),
+            ("b.ess", r#"@import "c.ess";"#),
+            ("c.ess", r#"@import "b.ess";"#),
+        ]);
+        assert_eq!(
+            resolve_imports(&parser, Path::new("a.ess"), &sources).err(),
+            Some(ElementsError::ImportCycle(
+                "b.ess -> c.ess -> b.ess".to_string()
+            ))
+        );
+    }
+}
@@ -0,0 +1,297 @@
+use super::{
+    has_custom_properties, ElementsBranch, Media, SelectorElement, SelectorWeight, StyleRule,
+    StyleSheet, Styles,
+};
+use crate::element::Element;
+use bevy::{prelude::*, utils::HashMap};
+use smallvec::SmallVec;
+use tagstr::Tag;
+
+type RuleIndices = SmallVec<[usize; 4]>;
+
+/// The rules of the single stylesheet bucketed by the right-most compound
+/// selector: the rule may match the element only if the element has the id,
+/// the class or the tag of the bucket. Rules without any of them go to the
+/// universal bucket.
+#[derive(Default)]
+struct SheetIndex {
+    ids: HashMap<Tag, RuleIndices>,
+    classes: HashMap<Tag, RuleIndices>,
+    tags: HashMap<Tag, RuleIndices>,
+    universal: RuleIndices,
+    variables: RuleIndices,
+}
+
+impl SheetIndex {
+    fn new(stylesheet: &StyleSheet) -> SheetIndex {
+        let mut index = SheetIndex::default();
+        for (idx, rule) in stylesheet.iter().enumerate() {
+            if has_custom_properties(rule) {
+                index.variables.push(idx);
+            }
+            let tail = || rule.selector.elements.iter().take_while(|e| e.is_value());
+            let id = tail().find_map(|e| match e {
+                SelectorElement::Id(id) => Some(*id),
+                _ => None,
+            });
+            let class = tail().find_map(|e| match e {
+                SelectorElement::Class(class) => Some(*class),
+                _ => None,
+            });
+            let tag = tail().find_map(|e| match e {
+                SelectorElement::Tag(tag) => Some(*tag),
+                _ => None,
+            });
+            let bucket = match (id, class, tag) {
+                (Some(id), _, _) => index.ids.entry(id).or_default(),
+                (_, Some(class), _) => index.classes.entry(class).or_default(),
+                (_, _, Some(tag)) => index.tags.entry(tag).or_default(),
+                _ => &mut index.universal,
+            };
+            bucket.push(idx);
+        }
+        index
+    }
+
+    /// Returns the indices of the rules which may match the element
+    /// in the order the rules are defined.
+    fn candidates(&self, element: &Element) -> SmallVec<[usize; 16]> {
+        let mut candidates: SmallVec<[usize; 16]> = self.universal.iter().copied().collect();
+        if let Some(rules) = element.id.and_then(|id| self.ids.get(&id)) {
+            candidates.extend(rules.iter().copied());
+        }
+        for class in element.classes.iter() {
+            if let Some(rules) = self.classes.get(class) {
+                candidates.extend(rules.iter().copied());
+            }
+        }
+        for tag in element.names.iter().chain(element.aliases.iter()) {
+            if let Some(rules) = self.tags.get(tag) {
+                candidates.extend(rules.iter().copied());
+            }
+        }
+        candidates.sort_unstable();
+        candidates.dedup();
+        candidates
+    }
+}
+
+#[derive(Debug)]
+struct MatchedRule {
+    sheet: Handle<StyleSheet>,
+    rule: usize,
+    depth: u8,
+    weight: SelectorWeight,
+}
+
+/// Keeps the rules of loaded stylesheets bucketed by selector tails and the
+/// rules matched by every element. The rules are matched once per element
+/// change and shared between all the properties.
+#[derive(Resource, Default)]
+pub struct RuleIndex {
+    sheets: HashMap<Handle<StyleSheet>, SheetIndex>,
+    matched: HashMap<Entity, SmallVec<[MatchedRule; 8]>>,
+}
+
+impl RuleIndex {
+    /// Returns the rules matched by the element with the match depth,
+    /// the heaviest rules go first.
+    pub fn matched_rules<'a>(
+        &'a self,
+        entity: Entity,
+        stylesheets: &'a Assets<StyleSheet>,
+    ) -> impl Iterator<Item = (&'a StyleRule, u8)> + 'a {
+        self.matched
+            .get(&entity)
+            .into_iter()
+            .flatten()
+            .filter_map(|matched| {
+                let rule = stylesheets.get(&matched.sheet)?.get(matched.rule)?;
+                Some((rule, matched.depth))
+            })
+    }
+
+    /// Returns the active rules defining custom properties
+    pub(crate) fn variable_rules<'a>(
+        &'a self,
+        styles: &Styles,
+        stylesheets: &'a Assets<StyleSheet>,
+        media: &Media,
+    ) -> Vec<&'a StyleRule> {
+        styles
+            .iter()
+            .filter_map(|handle| Some((stylesheets.get(handle)?, self.sheets.get(handle)?)))
+            .flat_map(|(stylesheet, index)| {
+                index
+                    .variables
+                    .iter()
+                    .filter_map(|idx| stylesheet.get(*idx))
+            })
+            .filter(|rule| rule.matches_media(media))
+            .collect()
+    }
+}
+
+pub(crate) fn update_rule_index(
+    mut index: ResMut<RuleIndex>,
+    mut events: EventReader<AssetEvent<StyleSheet>>,
+    stylesheets: Res<Assets<StyleSheet>>,
+) {
+    for event in events.iter() {
+        match event {
+            AssetEvent::Created { handle } | AssetEvent::Modified { handle } => {
+                if let Some(stylesheet) = stylesheets.get(handle) {
+                    index
+                        .sheets
+                        .insert(handle.clone_weak(), SheetIndex::new(stylesheet));
+                }
+            }
+            AssetEvent::Removed { handle } => {
+                index.sheets.remove(handle);
+            }
+        }
+    }
+}
+
+pub(crate) fn match_rules(
+    mut index: ResMut<RuleIndex>,
+    changed: Query<Entity, Changed<Element>>,
+    mut removed: RemovedComponents<Element>,
+    styles: Res<Styles>,
+    stylesheets: Res<Assets<StyleSheet>>,
+    media: Res<Media>,
+    parents: Query<&Parent>,
+    children: Query<&Children>,
+    elements: Query<&Element>,
+) {
+    let index = index.as_mut();
+    for entity in removed.iter() {
+        index.matched.remove(&entity);
+    }
+    for entity in changed.iter() {
+        let Ok(element) = elements.get(entity) else {
+            continue;
+        };
+        let branch = ElementsBranch::build(
+            entity,
+            true,
+            |e| parents.get(e).ok().map(|p| p.get()),
+            |e| children.get(e).ok(),
+            |e| elements.get(e).ok(),
+        );
+        // virtual elements (like text) are matched against the closest
+        // non-virtual ancestor, so the candidates are taken from it as well
+        let target = if branch.len() > 0 {
+            branch.node(0).element()
+        } else {
+            element
+        };
+        let mut matched = SmallVec::<[MatchedRule; 8]>::new();
+        for handle in styles.iter() {
+            let (Some(stylesheet), Some(sheet)) =
+                (stylesheets.get(handle), index.sheets.get(handle))
+            else {
+                continue;
+            };
+            for idx in sheet.candidates(target) {
+                let Some(rule) = stylesheet.get(idx) else {
+                    continue;
+                };
+                if !rule.matches_media(&media) {
+                    continue;
+                }
+                if let Some(depth) = rule.selector.match_depth(&branch) {
+                    matched.push(MatchedRule {
+                        sheet: handle.clone_weak(),
+                        rule: idx,
+                        depth,
+                        weight: rule.selector.weight,
+                    });
+                }
+            }
+        }
+        matched.sort_by_key(|m| -m.weight);
+        index.matched.insert(entity, matched);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ess::{PropertyExtractor, PropertyTransformer, StyleSheetParser};
+    use tagstr::AsTag;
+
+    #[test]
+    fn bucket_rules() {
+        let parser =
+            StyleSheetParser::new(PropertyTransformer::default(), PropertyExtractor::default());
+        let stylesheet = parser.parse_stylesheet(
+            r#"
+            * {}
+            #menu {}
+            .red {}
+            button {}
+            button.red:hover {}
+            #menu .red span {}
+            :not(.red) {}
+            div > .red {}
+            "#,
+        );
+        let index = SheetIndex::new(&stylesheet);
+        assert_eq!(index.universal.as_slice(), &[0, 6]);
+        assert_eq!(index.ids[&"menu".as_tag()].as_slice(), &[1]);
+        assert_eq!(index.classes[&"red".as_tag()].as_slice(), &[2, 4, 7]);
+        assert_eq!(index.tags[&"button".as_tag()].as_slice(), &[3]);
+        assert_eq!(index.tags[&"span".as_tag()].as_slice(), &[5]);
+
+        let mut element = Element::default();
+        element.names.push("button".as_tag());
+        element.classes.insert("red".as_tag());
+        assert_eq!(index.candidates(&element).as_slice(), &[0, 2, 3, 4, 6, 7]);
+        element.id = Some("menu".as_tag());
+        element.aliases.push("button".as_tag());
+        assert_eq!(
+            index.candidates(&element).as_slice(),
+            &[0, 1, 2, 3, 4, 6, 7]
+        );
+    }
+
+    #[test]
+    fn match_text_through_parent_class() {
+        let mut app = App::new();
+        app.add_plugins((TaskPoolPlugin::default(), AssetPlugin::default()));
+        app.add_asset::<StyleSheet>();
+        app.init_resource::<RuleIndex>()
+            .init_resource::<Styles>()
+            .init_resource::<Media>();
+        let parser =
+            StyleSheetParser::new(PropertyTransformer::default(), PropertyExtractor::default());
+        let stylesheet = parser.parse_stylesheet(".label {} #title {} div {}");
+        let sheet = SheetIndex::new(&stylesheet);
+        let handle = app
+            .world
+            .resource_mut::<Assets<StyleSheet>>()
+            .add(stylesheet);
+        app.world.resource_mut::<Styles>().insert(handle.clone());
+        app.world
+            .resource_mut::<RuleIndex>()
+            .sheets
+            .insert(handle.clone_weak(), sheet);
+
+        let mut label = Element::default();
+        label.names.push("span".as_tag());
+        label.classes.insert("label".as_tag());
+        let parent = app.world.spawn(label).id();
+        let text = app.world.spawn(Element::inline()).id();
+        app.world.entity_mut(parent).push_children(&[text]);
+
+        let mut system = IntoSystem::into_system(match_rules);
+        system.initialize(&mut app.world);
+        system.run((), &mut app.world);
+
+        let index = app.world.resource::<RuleIndex>();
+        let stylesheets = app.world.resource::<Assets<StyleSheet>>();
+        assert_eq!(index.matched_rules(parent, stylesheets).count(), 1);
+        assert_eq!(index.matched_rules(text, stylesheets).count(), 1);
+    }
+}
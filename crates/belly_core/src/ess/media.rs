@@ -0,0 +1,236 @@
+use super::{StyleRule, StyleSheet, Styles};
+use crate::element::Elements;
+use bevy::{prelude::*, window::PrimaryWindow};
+use smallvec::SmallVec;
+
+/// The state of the primary window `@media` queries are evaluated against.
+/// The sizes are measured in the ui pixels: the logical size of the window
+/// divided by [`UiScale`], the resolution is the number of the physical pixels
+/// per ui pixel (`dppx`).
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct Media {
+    pub width: f32,
+    pub height: f32,
+    pub resolution: f32,
+}
+
+impl Default for Media {
+    fn default() -> Self {
+        Media {
+            width: 0.,
+            height: 0.,
+            resolution: 1.,
+        }
+    }
+}
+
+impl Media {
+    pub fn aspect_ratio(&self) -> f32 {
+        self.width / self.height
+    }
+
+    pub fn orientation(&self) -> Orientation {
+        if self.height > self.width {
+            Orientation::Portrait
+        } else {
+            Orientation::Landscape
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Orientation {
+    Portrait,
+    Landscape,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaType {
+    All,
+    Screen,
+    Print,
+}
+
+/// The way feature value is compared: `min-` prefix, `max-` prefix or exact match
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaRange {
+    Min,
+    Max,
+    Exact,
+}
+
+impl MediaRange {
+    fn check(&self, actual: f32, expected: f32) -> bool {
+        match self {
+            MediaRange::Min => actual >= expected,
+            MediaRange::Max => actual <= expected,
+            MediaRange::Exact => (actual - expected).abs() < 0.001,
+        }
+    }
+}
+
+/// The single media feature inside parenthesis: `(max-width: 900px)`
+#[derive(Debug, Clone, PartialEq)]
+pub enum MediaFeature {
+    Width(MediaRange, f32),
+    Height(MediaRange, f32),
+    AspectRatio(MediaRange, f32),
+    Resolution(MediaRange, f32),
+    Orientation(Orientation),
+}
+
+impl MediaFeature {
+    pub fn matches(&self, media: &Media) -> bool {
+        match self {
+            MediaFeature::Width(range, value) => range.check(media.width, *value),
+            MediaFeature::Height(range, value) => range.check(media.height, *value),
+            MediaFeature::AspectRatio(range, value) => range.check(media.aspect_ratio(), *value),
+            MediaFeature::Resolution(range, value) => range.check(media.resolution, *value),
+            MediaFeature::Orientation(orientation) => media.orientation() == *orientation,
+        }
+    }
+}
+
+/// The single query of the comma-separated list: `not screen and (orientation: portrait)`
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaQuery {
+    pub negated: bool,
+    pub media_type: MediaType,
+    pub features: SmallVec<[MediaFeature; 2]>,
+}
+
+impl MediaQuery {
+    pub fn matches(&self, media: &Media) -> bool {
+        let matches =
+            self.media_type != MediaType::Print && self.features.iter().all(|f| f.matches(media));
+        matches != self.negated
+    }
+}
+
+/// The prelude of the `@media` rule, matches when any of the queries matches
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MediaQueryList(pub SmallVec<[MediaQuery; 1]>);
+
+impl MediaQueryList {
+    pub fn matches(&self, media: &Media) -> bool {
+        self.0.is_empty() || self.0.iter().any(|q| q.matches(media))
+    }
+}
+
+/// Returns `true` if some of the `@media` queries wrapping the `rules`
+/// match the `previous` media and don't match the `actual` one or vice versa.
+fn media_queries_flipped<'a>(
+    rules: impl IntoIterator<Item = &'a StyleRule>,
+    previous: &Media,
+    actual: &Media,
+) -> bool {
+    rules
+        .into_iter()
+        .flat_map(|rule| rule.media.iter())
+        .any(|query| query.matches(previous) != query.matches(actual))
+}
+
+pub(crate) fn update_media_system(
+    mut media: ResMut<Media>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    ui_scale: Option<Res<UiScale>>,
+    styles: Res<Styles>,
+    stylesheets: Res<Assets<StyleSheet>>,
+    mut elements: Elements,
+) {
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let scale = ui_scale.map(|s| s.scale as f32).unwrap_or(1.);
+    let actual = Media {
+        width: window.width() / scale,
+        height: window.height() / scale,
+        resolution: window.scale_factor() as f32 * scale,
+    };
+    if *media == actual {
+        return;
+    }
+    // the styles are applied again only when some query result flips,
+    // so resizing the window doesn't restyle the whole tree every frame
+    let rules = styles
+        .iter()
+        .filter_map(|handle| stylesheets.get(handle))
+        .flat_map(|stylesheet| stylesheet.iter());
+    let flipped = media_queries_flipped(rules, &media, &actual);
+    *media = actual;
+    if flipped {
+        elements.invalidate_all();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use smallvec::smallvec;
+
+    fn query(media_type: MediaType, features: &[MediaFeature]) -> MediaQuery {
+        MediaQuery {
+            negated: false,
+            media_type,
+            features: features.iter().cloned().collect(),
+        }
+    }
+
+    #[test]
+    fn match_media_queries() {
+        let media = Media {
+            width: 800.,
+            height: 600.,
+            resolution: 2.,
+        };
+        let narrow = query(
+            MediaType::All,
+            &[MediaFeature::Width(MediaRange::Max, 900.)],
+        );
+        assert!(narrow.matches(&media));
+        let portrait = query(
+            MediaType::Screen,
+            &[MediaFeature::Orientation(Orientation::Portrait)],
+        );
+        assert!(!portrait.matches(&media));
+        let hidpi = query(
+            MediaType::All,
+            &[
+                MediaFeature::Resolution(MediaRange::Min, 2.),
+                MediaFeature::AspectRatio(MediaRange::Exact, 4. / 3.),
+            ],
+        );
+        assert!(hidpi.matches(&media));
+        let not_portrait = MediaQuery {
+            negated: true,
+            ..portrait.clone()
+        };
+        assert!(not_portrait.matches(&media));
+        assert!(!query(MediaType::Print, &[]).matches(&media));
+
+        assert!(MediaQueryList(smallvec![portrait.clone(), narrow]).matches(&media));
+        assert!(!MediaQueryList(smallvec![portrait]).matches(&media));
+        assert!(MediaQueryList::default().matches(&media));
+    }
+
+    #[test]
+    fn flip_media_queries() {
+        let narrow = query(
+            MediaType::All,
+            &[MediaFeature::Width(MediaRange::Max, 900.)],
+        );
+        let rule = StyleRule {
+            selector: "div".into(),
+            properties: Default::default(),
+            media: vec![MediaQueryList(smallvec![narrow])],
+        };
+        let media = |width| Media {
+            width,
+            height: 600.,
+            resolution: 1.,
+        };
+        assert!(!media_queries_flipped([&rule], &media(800.), &media(850.)));
+        assert!(media_queries_flipped([&rule], &media(850.), &media(950.)));
+        assert!(!media_queries_flipped([&rule], &media(950.), &media(1200.)));
+    }
+}
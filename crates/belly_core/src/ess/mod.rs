@@ -1,21 +1,31 @@
 mod defaults;
+mod import;
+mod index;
+mod media;
 mod parser;
 pub mod property;
 mod selector;
 
 pub use self::parser::StyleSheetParser;
-use crate::{element::Elements, ess::defaults::Defaults};
+use crate::{
+    element::{Elements, InvalidateElements},
+    ess::defaults::Defaults,
+};
 use bevy::{
     asset::{AssetLoader, LoadedAsset},
     ecs::system::Command,
     prelude::*,
     reflect::{TypePath, TypeUuid},
+    ui::UiSystem,
     utils::{hashbrown::hash_map::Keys, HashMap},
 };
+pub use index::*;
+pub use media::*;
 pub use property::*;
 pub use selector::*;
 use smallvec::SmallVec;
 use std::ops::Deref;
+use std::sync::Arc;
 use tagstr::Tag;
 
 #[derive(Default)]
@@ -24,6 +34,8 @@ pub struct EssPlugin;
 impl Plugin for EssPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
         app.init_resource::<Styles>();
+        app.init_resource::<Media>();
+        app.init_resource::<RuleIndex>();
 
         // TODO: may be desabled with feature
         app.insert_resource(Defaults::default());
@@ -42,7 +54,15 @@ impl Plugin for EssPlugin {
             validator,
             extractor,
         });
-        app.add_systems(Update, process_styles_system);
+        app.add_systems(Update, (process_styles_system, media::update_media_system));
+        app.add_systems(Update, index::update_rule_index);
+        app.add_systems(
+            PostUpdate,
+            index::match_rules
+                .after(InvalidateElements)
+                .before(ApplyStyleProperties)
+                .before(UiSystem::Layout),
+        );
         app.add_plugins(property::PropertyPlugin);
         app.add_plugins(bevy_stylebox::StyleboxPlugin);
 
@@ -70,11 +90,10 @@ impl AssetLoader for EssLoader {
         Box::pin(async move {
             let source = std::str::from_utf8(bytes)?;
             let parser = StyleSheetParser::new(self.validator.clone(), self.extractor.clone());
-            let rules = parser.parse(source);
-            let mut stylesheet = StyleSheet::default();
-            for rule in rules {
-                stylesheet.add_rule(rule)
-            }
+            let path = load_context.path().to_path_buf();
+            let sources = import::load_imports(&parser, load_context, &path, source).await?;
+            let rules = import::resolve_imports(&parser, &path, &sources)?;
+            let stylesheet: StyleSheet = rules.into_iter().collect();
             load_context.set_default_asset(LoadedAsset::new(stylesheet));
             Ok(())
         })
@@ -86,6 +105,7 @@ impl AssetLoader for EssLoader {
 pub struct StyleSheet {
     weight: usize,
     rules: Vec<StyleRule>,
+    keyframes: HashMap<Tag, Arc<Keyframes>>,
 }
 
 unsafe impl Send for StyleSheet {}
@@ -106,8 +126,7 @@ impl Command for ParseCommand {
         let extractor = world.resource::<PropertyExtractor>().clone();
         let validator = world.resource::<PropertyTransformer>().clone();
         let parser = StyleSheetParser::new(validator, extractor);
-        let rules = parser.parse(&self.source);
-        let stylesheet = StyleSheet::new(rules);
+        let stylesheet = parser.parse_stylesheet(&self.source);
         let mut styles = world.resource_mut::<Styles>();
         let mut assets = world.resource_mut::<Assets<StyleSheet>>();
         let handle = assets.add(stylesheet);
@@ -188,6 +207,15 @@ impl StyleSheet {
         self.rules.push(rule);
     }
 
+    pub fn add_keyframes(&mut self, keyframes: Keyframes) {
+        self.keyframes.insert(keyframes.name, Arc::new(keyframes));
+    }
+
+    /// Returns the `@keyframes` rule defined by this stylesheet
+    pub fn keyframes(&self, name: Tag) -> Option<&Arc<Keyframes>> {
+        self.keyframes.get(&name)
+    }
+
     pub(crate) fn extra_weight(&self) -> usize {
         self.weight
     }
@@ -213,6 +241,16 @@ pub struct StyleRule {
     pub selector: Selector,
     // pub properties: HashMap<Tag, StyleProperty>,
     pub properties: HashMap<Tag, PropertyValue>,
+    /// The queries of `@media` blocks wrapping the rule
+    pub media: Vec<MediaQueryList>,
+}
+
+impl StyleRule {
+    /// Checks if the rule is active for the current [`Media`]:
+    /// every `@media` block wrapping the rule should match.
+    pub fn matches_media(&self, media: &Media) -> bool {
+        self.media.iter().all(|q| q.matches(media))
+    }
 }
 
 #[derive(Default, Resource)]
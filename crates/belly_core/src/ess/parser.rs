@@ -1,12 +1,18 @@
-use bevy::prelude::{error, warn};
+use bevy::{
+    prelude::{error, warn},
+    utils::HashMap,
+};
 use smallvec::{smallvec, SmallVec};
 
 use cssparser::*;
 use tagstr::{AsTag, Tag};
 
 use crate::{
-    eml::Variant, ess::PropertyExtractor, ess::PropertyTransformer, ess::Selector,
-    ess::SelectorElement, ess::StyleProperty, ess::StyleRule, ElementsError,
+    eml::Variant, ess::AttributeMatcher, ess::Keyframe, ess::Keyframes, ess::MediaFeature,
+    ess::MediaQuery, ess::MediaQueryList, ess::MediaRange, ess::MediaType, ess::Orientation,
+    ess::PropertyExtractor, ess::PropertyTransformer, ess::PropertyValue, ess::Selector,
+    ess::SelectorElement, ess::StructuralPseudoClass, ess::StyleProperty, ess::StyleRule,
+    ess::StyleSheet, ElementsError,
 };
 
 use super::StylePropertyToken;
@@ -24,9 +30,27 @@ impl StyleSheetParser {
             transformer,
         }
     }
+    /// Parses the style rules, the at-rules like `@keyframes` are skipped
     pub fn parse(&self, content: &str) -> SmallVec<[StyleRule; 8]> {
+        self.parse_rules(content)
+            .into_iter()
+            .filter_map(|rule| match rule {
+                EssRule::Style(rule) => Some(rule),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Parses the complete stylesheet: style rules & at-rules. The `@import`
+    /// rules are resolved only for stylesheets loaded by `AssetServer`.
+    pub fn parse_stylesheet(&self, content: &str) -> StyleSheet {
+        self.parse_rules(content).into_iter().collect()
+    }
+
+    pub(crate) fn parse_rules(&self, content: &str) -> Vec<EssRule> {
         let mut input = ParserInput::new(content);
         let mut parser = Parser::new(&mut input);
+        let mut rules = vec![];
         RuleListParser::new_for_stylesheet(&mut parser, self)
             .into_iter()
             .filter_map(|result| match result {
@@ -40,7 +64,322 @@ impl StyleSheetParser {
                     None
                 }
             })
-            .collect()
+            .for_each(|rule| rule.flatten(&mut rules));
+        rules
+    }
+
+    fn parse_properties<'i, 't>(
+        &self,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<HashMap<Tag, PropertyValue>, ParseError<'i, ElementsError>> {
+        self.transform_properties(parse_declarations(input))
+    }
+
+    /// Transforms the declarations of the block into property values,
+    /// compound properties are extracted into the longhand ones.
+    fn transform_properties<'i>(
+        &self,
+        declarations: Vec<Declaration>,
+    ) -> Result<HashMap<Tag, PropertyValue>, ParseError<'i, ElementsError>> {
+        let mut properties = HashMap::default();
+        for (name, property, location) in declarations {
+            if self.extractor.is_compound_property(name) {
+                let extracted = match self.extractor.extract(name, Variant::style(property)) {
+                    Err(e) => return Err(location.new_custom_error(e)),
+                    Ok(extracted) => extracted,
+                };
+                for (name, property) in extracted {
+                    properties.insert(name, property);
+                }
+            } else {
+                match self.transformer.transform(name, Variant::style(property)) {
+                    Ok(variant) => {
+                        properties.insert(name, variant);
+                    }
+                    Err(e) => return Err(location.new_custom_error(e)),
+                }
+            }
+        }
+        Ok(properties)
+    }
+
+    /// Parses the block of the style rule: the declarations mixed with
+    /// the nested rules. The nested rules are flattened into the rules
+    /// placed right after the rules of the block.
+    fn parse_style_block<'i, 't>(
+        &self,
+        selectors: Vec<Selector>,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<EssRule, ParseError<'i, ElementsError>> {
+        let mut declarations = vec![];
+        let mut nested = vec![];
+        loop {
+            input.skip_whitespace();
+            if input.is_exhausted() {
+                break;
+            }
+            let start = input.position();
+            if let Ok(declaration) = input.try_parse(parse_declaration) {
+                declarations.push(declaration);
+                continue;
+            }
+            match input.try_parse(|input| self.parse_nested_rule(&selectors, input)) {
+                Ok(rule) => nested.push(rule),
+                Err(err) => {
+                    let delimiters = Delimiter::Semicolon | Delimiter::CurlyBracketBlock;
+                    let _ = input.parse_until_after(delimiters, |input| {
+                        while input.next().is_ok() {}
+                        Ok::<_, ParseError<()>>(())
+                    });
+                    error!(
+                        "Failed to parse nested rule or declaration: {}. Error: {}",
+                        input.slice_from(start).trim(),
+                        format_error(err)
+                    );
+                }
+            }
+        }
+        // every selector of the list produces its own rule
+        // with its own weight, sharing the declarations
+        let mut rules = selectors
+            .into_iter()
+            .map(|selector| {
+                Ok(EssRule::Style(StyleRule {
+                    selector,
+                    properties: self.transform_properties(declarations.clone())?,
+                    media: vec![],
+                }))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        rules.extend(nested);
+        if rules.len() == 1 {
+            Ok(rules.pop().unwrap())
+        } else {
+            Ok(EssRule::Group(rules))
+        }
+    }
+
+    /// Parses the rule nested into the block of the rule with `parents`
+    /// selectors: `&:hover { ... }`, `.icon { ... }`, `> span { ... }`
+    fn parse_nested_rule<'i, 't>(
+        &self,
+        parents: &[Selector],
+        input: &mut Parser<'i, 't>,
+    ) -> Result<EssRule, ParseError<'i, ElementsError>> {
+        let delimiters = Delimiter::Semicolon | Delimiter::CurlyBracketBlock;
+        let selectors = input.parse_until_before(delimiters, |input| {
+            let start = input.state();
+            let mut selectors = vec![];
+            for parent in parents {
+                input.reset(&start);
+                selectors.extend(
+                    input.parse_comma_separated(|input| parse_nested_selector(input, parent))?,
+                );
+            }
+            Ok(selectors)
+        })?;
+        input.expect_curly_bracket_block()?;
+        input.parse_nested_block(|input| self.parse_style_block(selectors, input))
+    }
+}
+
+/// The property name, the raw value and the location of the value in the source
+type Declaration = (Tag, StyleProperty, SourceLocation);
+
+/// Parses the single declaration of the style rule block: `name: value;`
+fn parse_declaration<'i, 't>(
+    input: &mut Parser<'i, 't>,
+) -> Result<Declaration, ParseError<'i, ElementsError>> {
+    let name = input.expect_ident()?.clone();
+    input.expect_colon()?;
+    input.parse_until_after(Delimiter::Semicolon, |input| {
+        PropertyParser.parse_value(name, input)
+    })
+}
+
+fn parse_declarations<'i, 't>(input: &mut Parser<'i, 't>) -> Vec<Declaration> {
+    DeclarationListParser::new(input, PropertyParser)
+        .filter_map(|declaration| match declaration {
+            Ok(declaration) => Some(declaration),
+            Err((err, a)) => {
+                println!("Failed: {:?} ({})", err, a);
+                None
+            }
+        })
+        .collect()
+}
+
+/// The top-level rule of the stylesheet
+pub enum EssRule {
+    Style(StyleRule),
+    Keyframes(Keyframes),
+    /// The rules of the `@media` block or the rules produced by the selector list
+    Group(Vec<EssRule>),
+    Import {
+        path: String,
+        media: Vec<MediaQueryList>,
+    },
+}
+
+impl FromIterator<EssRule> for StyleSheet {
+    fn from_iter<T: IntoIterator<Item = EssRule>>(rules: T) -> Self {
+        let mut stylesheet = StyleSheet::default();
+        for rule in rules {
+            match rule {
+                EssRule::Style(rule) => stylesheet.add_rule(rule),
+                EssRule::Keyframes(keyframes) => stylesheet.add_keyframes(keyframes),
+                // grouped rules are already flattened by parse_rules
+                EssRule::Group(_) => {}
+                EssRule::Import { path, .. } => {
+                    warn!("Unable to import {path}: only stylesheet assets may use @import")
+                }
+            }
+        }
+        stylesheet
+    }
+}
+
+impl EssRule {
+    /// Moves the grouped rules to the top level
+    fn flatten(self, rules: &mut Vec<EssRule>) {
+        match self {
+            EssRule::Group(nested) => nested.into_iter().for_each(|r| r.flatten(rules)),
+            rule => rules.push(rule),
+        }
+    }
+
+    /// Restricts the style rules by the `@media` query
+    pub(crate) fn with_media(self, media: &MediaQueryList) -> EssRule {
+        match self {
+            EssRule::Style(mut rule) => {
+                rule.media.insert(0, media.clone());
+                EssRule::Style(rule)
+            }
+            EssRule::Import {
+                path,
+                media: mut queries,
+            } => {
+                queries.insert(0, media.clone());
+                EssRule::Import {
+                    path,
+                    media: queries,
+                }
+            }
+            EssRule::Group(nested) => {
+                EssRule::Group(nested.into_iter().map(|r| r.with_media(media)).collect())
+            }
+            rule => rule,
+        }
+    }
+}
+
+/// Parses the prelude of the `@media` rule:
+/// `screen and (max-width: 900px), (orientation: portrait)`
+pub(crate) fn parse_media_query_list<'i, 't>(
+    input: &mut Parser<'i, 't>,
+) -> Result<MediaQueryList, ParseError<'i, ElementsError>> {
+    let queries = input.parse_comma_separated(parse_media_query)?;
+    Ok(MediaQueryList(SmallVec::from_vec(queries)))
+}
+
+fn parse_media_query<'i, 't>(
+    input: &mut Parser<'i, 't>,
+) -> Result<MediaQuery, ParseError<'i, ElementsError>> {
+    let negated = input.try_parse(|i| i.expect_ident_matching("not")).is_ok();
+    if !negated {
+        let _ = input.try_parse(|i| i.expect_ident_matching("only"));
+    }
+    let media_type = input.try_parse(|input| {
+        let location = input.current_source_location();
+        let ident = input.expect_ident()?.clone();
+        match_ignore_ascii_case! { &ident,
+            "all" => Ok(MediaType::All),
+            "screen" => Ok(MediaType::Screen),
+            "print" => Ok(MediaType::Print),
+            _ => Err(location.new_unexpected_token_error(Token::Ident(ident))),
+        }
+    });
+    let mut features = SmallVec::new();
+    if media_type.is_err() {
+        features.push(parse_media_feature(input)?);
+    }
+    while input.try_parse(|i| i.expect_ident_matching("and")).is_ok() {
+        features.push(parse_media_feature(input)?);
+    }
+    Ok(MediaQuery {
+        negated,
+        media_type: media_type.unwrap_or(MediaType::All),
+        features,
+    })
+}
+
+fn parse_media_feature<'i, 't>(
+    input: &mut Parser<'i, 't>,
+) -> Result<MediaFeature, ParseError<'i, ElementsError>> {
+    input.expect_parenthesis_block()?;
+    input.parse_nested_block(|input| {
+        let location = input.current_source_location();
+        let name = input.expect_ident()?.clone();
+        input.expect_colon()?;
+        let lowercase = name.to_ascii_lowercase();
+        let (range, feature) = if let Some(feature) = lowercase.strip_prefix("min-") {
+            (MediaRange::Min, feature)
+        } else if let Some(feature) = lowercase.strip_prefix("max-") {
+            (MediaRange::Max, feature)
+        } else {
+            (MediaRange::Exact, lowercase.as_str())
+        };
+        match (feature, range) {
+            ("width", _) => Ok(MediaFeature::Width(range, parse_media_length(input)?)),
+            ("height", _) => Ok(MediaFeature::Height(range, parse_media_length(input)?)),
+            ("aspect-ratio", _) => {
+                let width = input.expect_number()?;
+                let height = match input.try_parse(|i| i.expect_delim('/')) {
+                    Ok(_) => input.expect_number()?,
+                    Err(_) => 1.,
+                };
+                Ok(MediaFeature::AspectRatio(range, width / height))
+            }
+            ("resolution", _) => {
+                let location = input.current_source_location();
+                let token = input.next()?.clone();
+                let dppx = match &token {
+                    Token::Dimension { value, unit, .. } => match_ignore_ascii_case! { &**unit,
+                        "dppx" | "x" => Some(*value),
+                        "dpi" => Some(*value / 96.),
+                        "dpcm" => Some(*value * 2.54 / 96.),
+                        _ => None,
+                    },
+                    _ => None,
+                };
+                match dppx {
+                    Some(dppx) => Ok(MediaFeature::Resolution(range, dppx)),
+                    None => Err(location.new_unexpected_token_error(token)),
+                }
+            }
+            ("orientation", MediaRange::Exact) => {
+                let location = input.current_source_location();
+                let ident = input.expect_ident()?.clone();
+                match_ignore_ascii_case! { &ident,
+                    "portrait" => Ok(MediaFeature::Orientation(Orientation::Portrait)),
+                    "landscape" => Ok(MediaFeature::Orientation(Orientation::Landscape)),
+                    _ => Err(location.new_unexpected_token_error(Token::Ident(ident))),
+                }
+            }
+            _ => Err(location.new_unexpected_token_error(Token::Ident(name))),
+        }
+    })
+}
+
+/// Parses the length of media feature: `900px` or `0`
+fn parse_media_length<'i, 't>(
+    input: &mut Parser<'i, 't>,
+) -> Result<f32, ParseError<'i, ElementsError>> {
+    let location = input.current_source_location();
+    match input.next()? {
+        Token::Dimension { value, unit, .. } if unit.eq_ignore_ascii_case("px") => Ok(*value),
+        Token::Number { value, .. } if *value == 0. => Ok(0.),
+        token => Err(location.new_unexpected_token_error(token.clone())),
     }
 }
 
@@ -76,87 +415,195 @@ enum NextElement {
     Attribute,
 }
 
-impl<'i> QualifiedRuleParser<'i> for &StyleSheetParser {
-    type Prelude = Selector;
-    type QualifiedRule = StyleRule;
-    type Error = ElementsError;
+/// Parses the selector until the end of input
+pub(crate) fn parse_selector<'i, 't>(
+    input: &mut Parser<'i, 't>,
+) -> Result<Selector, ParseError<'i, ElementsError>> {
+    parse_selector_elements(input, None)
+}
 
-    fn parse_prelude<'t>(
-        &mut self,
-        input: &mut Parser<'i, 't>,
-    ) -> Result<Self::Prelude, ParseError<'i, Self::Error>> {
-        let mut elements: SmallVec<[SelectorElement; 8]> = smallvec![];
+/// Parses the selector of the nested rule. The `&` is replaced with the
+/// `parent` selector, the selector without `&` describes the descendants
+/// of the `parent` (or the siblings, if it starts with `+` or `~`).
+fn parse_nested_selector<'i, 't>(
+    input: &mut Parser<'i, 't>,
+    parent: &Selector,
+) -> Result<Selector, ParseError<'i, ElementsError>> {
+    parse_selector_elements(input, Some(parent))
+}
 
-        let mut next = NextElement::Tag;
+fn parse_selector_elements<'i, 't>(
+    input: &mut Parser<'i, 't>,
+    parent: Option<&Selector>,
+) -> Result<Selector, ParseError<'i, ElementsError>> {
+    let mut elements: SmallVec<[SelectorElement; 8]> = smallvec![];
+    let mut has_parent_reference = false;
 
-        while let Ok(token) = input.next_including_whitespace() {
-            use cssparser::Token::*;
-            match token {
-                Ident(v) => {
-                    match next {
-                        NextElement::Tag => {
-                            elements.insert(0, SelectorElement::Tag(v.to_string().as_tag()))
-                        }
-                        NextElement::Class => {
-                            elements.insert(0, SelectorElement::Class(v.to_string().as_tag()))
-                        }
-                        NextElement::Attribute => {
+    let mut next = NextElement::Tag;
+
+    while let Ok(token) = input.next_including_whitespace() {
+        use cssparser::Token::*;
+        match token {
+            Ident(v) => {
+                match next {
+                    NextElement::Tag => {
+                        elements.insert(0, SelectorElement::Tag(v.to_string().as_tag()))
+                    }
+                    NextElement::Class => {
+                        elements.insert(0, SelectorElement::Class(v.to_string().as_tag()))
+                    }
+                    NextElement::Attribute => {
+                        if let Some(pseudo) = StructuralPseudoClass::from_ident(v) {
+                            elements.insert(0, SelectorElement::Structural(pseudo))
+                        } else {
                             elements.insert(0, SelectorElement::State(v.to_string().as_tag()))
                         }
-                    };
-                    next = NextElement::Tag;
-                }
-                IDHash(v) => {
-                    if v.is_empty() {
-                        return Err(input.new_custom_error(ElementsError::InvalidSelector));
-                    } else {
-                        elements.insert(0, SelectorElement::Id(v.to_string().as_tag()));
                     }
-                }
-                WhiteSpace(_) => {
-                    if let Some(token) = elements.first() {
-                        if token.is_separator() {
-                            continue;
-                        }
+                };
+                next = NextElement::Tag;
+            }
+            Function(name) if matches!(next, NextElement::Attribute) => {
+                let element = match &**name {
+                    "not" => SelectorElement::Not(parse_selector_list(input)?),
+                    "is" => SelectorElement::Is(parse_selector_list(input)?),
+                    "nth-child" | "nth-last-child" => {
+                        let name = name.to_string();
+                        let (a, b) = input.parse_nested_block::<_, _, ElementsError>(|input| {
+                            let nth = parse_nth(input)?;
+                            input.expect_exhausted()?;
+                            Ok(nth)
+                        })?;
+                        SelectorElement::Structural(
+                            StructuralPseudoClass::from_function(&name, a, b).unwrap(),
+                        )
                     }
-                    elements.insert(0, SelectorElement::AnyChild);
+                    _ => return Err(input.new_custom_error(ElementsError::UnsupportedSelector)),
+                };
+                elements.insert(0, element);
+                next = NextElement::Tag;
+            }
+            IDHash(v) => {
+                if v.is_empty() {
+                    return Err(input.new_custom_error(ElementsError::InvalidSelector));
+                } else {
+                    elements.insert(0, SelectorElement::Id(v.to_string().as_tag()));
                 }
-                Delim(c) if *c == '.' => next = NextElement::Class,
-                Delim(c) if *c == '*' => elements.insert(0, SelectorElement::Any),
-                Delim(c) if *c == '>' => {
-                    if let Some(token) = elements.first() {
-                        if token.is_any_child() {
-                            elements[0] = SelectorElement::DirectChild;
-                            continue;
-                        }
+            }
+            WhiteSpace(_) => {
+                if let Some(token) = elements.first() {
+                    if token.is_separator() {
+                        continue;
                     }
-                    elements.insert(0, SelectorElement::DirectChild);
                 }
-                Colon => next = NextElement::Attribute,
-                _ => {
-                    warn!("Unexpected token: {:?}", token);
-                    let token = token.to_css_string();
-                    return Err(input.new_custom_error(ElementsError::UnexpectedToken(token)));
+                elements.insert(0, SelectorElement::AnyChild);
+            }
+            Delim(c) if *c == '.' => next = NextElement::Class,
+            Delim(c) if *c == '*' => elements.insert(0, SelectorElement::Any),
+            Delim(c) if *c == '&' && parent.is_some() => {
+                // elements are stored in reverse order
+                for element in parent.unwrap().elements.iter().rev() {
+                    elements.insert(0, element.clone());
                 }
+                has_parent_reference = true;
+            }
+            Delim(c) if *c == '>' || *c == '+' || *c == '~' => {
+                let combinator = match c {
+                    '>' => SelectorElement::DirectChild,
+                    '+' => SelectorElement::AdjacentSibling,
+                    _ => SelectorElement::GeneralSibling,
+                };
+                if let Some(token) = elements.first() {
+                    if token.is_any_child() {
+                        elements[0] = combinator;
+                        continue;
+                    }
+                }
+                elements.insert(0, combinator);
+            }
+            Colon => next = NextElement::Attribute,
+            SquareBracketBlock => {
+                elements.insert(0, input.parse_nested_block(parse_attribute_selector)?);
+            }
+            _ => {
+                warn!("Unexpected token: {:?}", token);
+                let token = token.to_css_string();
+                return Err(input.new_custom_error(ElementsError::UnexpectedToken(token)));
             }
         }
+    }
+
+    if elements.is_empty() {
+        return Err(input.new_custom_error(ElementsError::InvalidSelector));
+    }
 
-        if elements.is_empty() {
-            return Err(input.new_custom_error(ElementsError::InvalidSelector));
+    // Remove noise the trailing white spaces, if any
+    while !elements.is_empty() {
+        if elements.last().unwrap().is_any_child() {
+            elements.pop();
+        } else if elements.first().unwrap().is_any_child() {
+            elements.remove(0);
+        } else {
+            break;
         }
+    }
 
-        // Remove noise the trailing white spaces, if any
-        while !elements.is_empty() {
-            if elements.last().unwrap().is_any_child() {
-                elements.pop();
-            } else if elements.first().unwrap().is_any_child() {
-                elements.remove(0);
-            } else {
-                break;
-            }
+    if let Some(parent) = parent.filter(|_| !has_parent_reference) {
+        match elements.last() {
+            None => return Err(input.new_custom_error(ElementsError::InvalidSelector)),
+            Some(head) if head.is_value() => elements.push(SelectorElement::AnyChild),
+            _ => {}
         }
+        elements.extend(parent.elements.iter().cloned());
+    }
+
+    Ok(Selector::new(elements))
+}
+
+/// Parses comma-separated selectors inside the function block: `:is(a, b)`
+fn parse_selector_list<'i, 't>(
+    input: &mut Parser<'i, 't>,
+) -> Result<Vec<Selector>, ParseError<'i, ElementsError>> {
+    input.parse_nested_block(|input| input.parse_comma_separated(parse_selector))
+}
 
-        Ok(Selector::new(elements))
+/// Parses the content of `[...]` block into [`SelectorElement::Attribute`]
+pub(crate) fn parse_attribute_selector<'i, 't>(
+    input: &mut Parser<'i, 't>,
+) -> Result<SelectorElement, ParseError<'i, ElementsError>> {
+    let name = input.expect_ident()?.to_string().as_tag();
+    if input.is_exhausted() {
+        return Ok(SelectorElement::Attribute(name, AttributeMatcher::Exists));
+    }
+    let location = input.current_source_location();
+    let operator = input.next()?.clone();
+    let value = input.expect_ident_or_string()?.to_string();
+    let matcher = match operator {
+        Token::Delim('=') => AttributeMatcher::Equals(value),
+        Token::IncludeMatch => AttributeMatcher::Includes(value),
+        Token::DashMatch => AttributeMatcher::DashMatch(value),
+        Token::PrefixMatch => AttributeMatcher::Prefix(value),
+        Token::SuffixMatch => AttributeMatcher::Suffix(value),
+        Token::SubstringMatch => AttributeMatcher::Substring(value),
+        token => {
+            return Err(location.new_custom_error(ElementsError::UnexpectedToken(
+                token.to_css_string(),
+            )))
+        }
+    };
+    input.expect_exhausted()?;
+    Ok(SelectorElement::Attribute(name, matcher))
+}
+
+impl<'i> QualifiedRuleParser<'i> for &StyleSheetParser {
+    type Prelude = Vec<Selector>;
+    type QualifiedRule = EssRule;
+    type Error = ElementsError;
+
+    fn parse_prelude<'t>(
+        &mut self,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self::Prelude, ParseError<'i, Self::Error>> {
+        input.parse_comma_separated(parse_selector)
     }
 
     fn parse_block<'t>(
@@ -165,50 +612,158 @@ impl<'i> QualifiedRuleParser<'i> for &StyleSheetParser {
         _start: &cssparser::ParserState,
         input: &mut Parser<'i, 't>,
     ) -> Result<Self::QualifiedRule, ParseError<'i, Self::Error>> {
-        let mut rule = StyleRule {
-            selector: prelude,
-            properties: Default::default(),
-        };
+        self.parse_style_block(prelude, input)
+    }
+}
 
-        for property in DeclarationListParser::new(input, PropertyParser) {
-            match property {
-                Ok((name, property)) => {
-                    if self.extractor.is_compound_property(name) {
-                        let extracted = match self.extractor.extract(name, Variant::style(property))
-                        {
-                            Err(e) => return Err(input.new_custom_error(e)),
-                            Ok(extracted) => extracted,
-                        };
-                        for (name, property) in extracted {
-                            rule.properties.insert(name, property);
-                        }
-                    } else {
-                        match self.transformer.transform(name, Variant::style(property)) {
-                            Ok(variant) => {
-                                rule.properties.insert(name, variant);
-                            }
-                            Err(e) => return Err(input.new_custom_error(e)),
-                        }
+/// The prelude of the supported at-rules
+pub enum AtRulePrelude {
+    Keyframes(Tag),
+    Media(MediaQueryList),
+    Import(String, MediaQueryList),
+}
+
+impl<'i> AtRuleParser<'i> for &StyleSheetParser {
+    type Prelude = AtRulePrelude;
+    type AtRule = EssRule;
+    type Error = ElementsError;
+
+    fn parse_prelude<'t>(
+        &mut self,
+        name: CowRcStr<'i>,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self::Prelude, ParseError<'i, Self::Error>> {
+        match_ignore_ascii_case! { &name,
+            "keyframes" => {
+                let name = input.expect_ident_or_string()?.to_string();
+                Ok(AtRulePrelude::Keyframes(name.as_tag()))
+            },
+            "media" => Ok(AtRulePrelude::Media(parse_media_query_list(input)?)),
+            "import" => {
+                let path = input.expect_url_or_string()?.to_string();
+                let media = if input.is_exhausted() {
+                    MediaQueryList::default()
+                } else {
+                    parse_media_query_list(input)?
+                };
+                Ok(AtRulePrelude::Import(path, media))
+            },
+            _ => Err(input.new_error(BasicParseErrorKind::AtRuleInvalid(name))),
+        }
+    }
+
+    fn parse_block<'t>(
+        &mut self,
+        prelude: Self::Prelude,
+        _start: &ParserState,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self::AtRule, ParseError<'i, Self::Error>> {
+        match prelude {
+            AtRulePrelude::Keyframes(name) => {
+                let mut keyframes = Keyframes {
+                    name,
+                    frames: vec![],
+                };
+                for frame in RuleListParser::new_for_nested_rule(input, KeyframeParser(self)) {
+                    match frame {
+                        Ok(frame) => keyframes.frames.push(frame),
+                        Err((err, frame)) => error!(
+                            "Failed to parse keyframe: {}. Error: {}",
+                            frame,
+                            format_error(err)
+                        ),
+                    }
+                }
+                Ok(EssRule::Keyframes(keyframes))
+            }
+            AtRulePrelude::Media(media) => {
+                let mut rules = vec![];
+                for rule in RuleListParser::new_for_nested_rule(input, *self) {
+                    match rule {
+                        Ok(rule) => rules.push(rule.with_media(&media)),
+                        Err((err, rule)) => error!(
+                            "Failed to parse rule: {}. Error: {}",
+                            rule,
+                            format_error(err)
+                        ),
                     }
                 }
-                Err((err, a)) => println!("Failed: {:?} ({})", err, a),
+                Ok(EssRule::Group(rules))
+            }
+            AtRulePrelude::Import(..) => {
+                Err(input.new_error(BasicParseErrorKind::AtRuleBodyInvalid))
             }
         }
+    }
 
-        Ok(rule)
+    fn rule_without_block(
+        &mut self,
+        prelude: Self::Prelude,
+        _start: &ParserState,
+    ) -> Result<Self::AtRule, ()> {
+        match prelude {
+            AtRulePrelude::Import(path, media) if media.0.is_empty() => Ok(EssRule::Import {
+                path,
+                media: vec![],
+            }),
+            AtRulePrelude::Import(path, media) => Ok(EssRule::Import {
+                path,
+                media: vec![media],
+            }),
+            _ => Err(()),
+        }
     }
 }
 
-impl<'i> AtRuleParser<'i> for &StyleSheetParser {
+/// Parses the keyframes of `@keyframes` rule: `from`, `to` or percentages
+struct KeyframeParser<'a>(&'a StyleSheetParser);
+
+impl<'a, 'i> QualifiedRuleParser<'i> for KeyframeParser<'a> {
+    type Prelude = SmallVec<[f32; 2]>;
+    type QualifiedRule = Keyframe;
+    type Error = ElementsError;
+
+    fn parse_prelude<'t>(
+        &mut self,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self::Prelude, ParseError<'i, Self::Error>> {
+        let offsets = input.parse_comma_separated(|input| {
+            let location = input.current_source_location();
+            match input.next()? {
+                Token::Ident(ident) if ident.eq_ignore_ascii_case("from") => Ok(0.0),
+                Token::Ident(ident) if ident.eq_ignore_ascii_case("to") => Ok(1.0),
+                Token::Percentage { unit_value, .. } if (0.0..=1.0).contains(unit_value) => {
+                    Ok(*unit_value)
+                }
+                token => Err(location.new_unexpected_token_error(token.clone())),
+            }
+        })?;
+        Ok(SmallVec::from_vec(offsets))
+    }
+
+    fn parse_block<'t>(
+        &mut self,
+        prelude: Self::Prelude,
+        _start: &ParserState,
+        input: &mut Parser<'i, 't>,
+    ) -> Result<Self::QualifiedRule, ParseError<'i, Self::Error>> {
+        Ok(Keyframe {
+            offsets: prelude,
+            properties: self.0.parse_properties(input)?,
+        })
+    }
+}
+
+impl<'a, 'i> AtRuleParser<'i> for KeyframeParser<'a> {
     type Prelude = ();
-    type AtRule = StyleRule;
+    type AtRule = Keyframe;
     type Error = ElementsError;
 }
 
 struct PropertyParser;
 
 impl<'i> DeclarationParser<'i> for PropertyParser {
-    type Declaration = (Tag, StyleProperty);
+    type Declaration = Declaration;
 
     type Error = ElementsError;
 
@@ -217,7 +772,12 @@ impl<'i> DeclarationParser<'i> for PropertyParser {
         name: cssparser::CowRcStr<'i>,
         parser: &mut Parser<'i, 't>,
     ) -> Result<Self::Declaration, ParseError<'i, ElementsError>> {
-        Ok((name.to_string().as_tag(), StyleProperty(parse_values(parser)?)))
+        let location = parser.current_source_location();
+        Ok((
+            name.to_string().as_tag(),
+            StyleProperty(parse_values(parser)?),
+            location,
+        ))
     }
 }
 
@@ -274,8 +834,24 @@ fn parse_value<'i, 'tt>(
             Token::Dimension { value, unit, .. } => {
                 Ok(StylePropertyToken::Dimension(value.into(), unit.to_string()))
             }
+            Token::ParenthesisBlock => {
+                // parenthesized group of math expression: calc((100% - 10px) / 2)
+                let mut tokens = parser.parse_nested_block(parse_values)?;
+                let arg = if tokens.len() == 1 {
+                    tokens.pop().unwrap()
+                } else {
+                    StylePropertyToken::Tokens(tokens.into_vec())
+                };
+                Ok(StylePropertyToken::Function(StylePropertyFunction {
+                    name: String::new(),
+                    args: vec![arg],
+                }))
+            }
             Token::Comma => Ok(StylePropertyToken::Comma),
             Token::Delim(d) if d == '/' => Ok(StylePropertyToken::Slash),
+            Token::Delim(d) if d == '+' => Ok(StylePropertyToken::Plus),
+            Token::Delim(d) if d == '-' => Ok(StylePropertyToken::Minus),
+            Token::Delim(d) if d == '*' => Ok(StylePropertyToken::Asterisk),
             token => Err(loc.new_custom_error(
                 ElementsError::UnexpectedToken(format!("Invalid token: {:?}", token))
             ))
@@ -307,7 +883,10 @@ pub fn parse_style_property_value<T: AsRef<str>>(value: T) -> Result<StyleProper
 mod tests {
     use std::str::FromStr;
 
-    use crate::ess::{ExtractProperty, PropertyValue, StylePropertyToken, TransformProperty};
+    use crate::ess::{
+        AttributeMatcher, CssWideKeyword, CustomProperty, ExtractProperty, Longhands, Media,
+        PropertyValue, SelectorWeight, StylePropertyToken, TransformProperty, UnresolvedProperty,
+    };
 
     use super::*;
     use bevy::utils::HashMap;
@@ -333,8 +912,8 @@ mod tests {
                 transformers.insert(tag.as_tag(), transform);
                 transformers.insert(format!("{}-{}", tag, tag).as_tag(), transform);
             }
-            let mut extractors: HashMap<Tag, ExtractProperty> = Default::default();
-            extractors.insert("compound".as_tag(), |_| {
+            let mut extractors: HashMap<Tag, (ExtractProperty, Longhands)> = Default::default();
+            let compound: ExtractProperty = |_| {
                 let mut map = HashMap::default();
                 map.insert(
                     "a".as_tag(),
@@ -349,7 +928,8 @@ mod tests {
                     )])),
                 );
                 Ok(map)
-            });
+            };
+            extractors.insert("compound".as_tag(), (compound, &["a", "b"]));
 
             let validator = PropertyTransformer::new(transformers);
             let extractor = PropertyExtractor::new(extractors);
@@ -365,6 +945,11 @@ mod tests {
             // StyleSheetParser::parse(content, self.validator.clone(), self.extractor.clone())
         }
 
+        fn parse_stylesheet(&self, content: &str) -> StyleSheet {
+            let parser = StyleSheetParser::new(self.transformer.clone(), self.extractor.clone());
+            parser.parse_stylesheet(content)
+        }
+
         // fn
     }
 
@@ -512,6 +1097,296 @@ mod tests {
         assert!(rule.properties.is_empty(), "Should have no properties");
     }
 
+    #[test]
+    fn parse_attribute_selectors() {
+        let rules = TestParser::new().parse(
+            r#"button[mode] {}
+            button[mode=toggle] {}
+            range[mode="vertical"] {}
+            [name^=prefix] {}"#,
+        );
+        assert_eq!(rules.len(), 4, "Should have 4 rules");
+        let expected = [
+            ("mode", AttributeMatcher::Exists),
+            ("mode", AttributeMatcher::Equals("toggle".into())),
+            ("mode", AttributeMatcher::Equals("vertical".into())),
+            ("name", AttributeMatcher::Prefix("prefix".into())),
+        ];
+        for (rule, (name, matcher)) in rules.iter().zip(expected) {
+            let attribute = rule
+                .selector
+                .elements
+                .iter()
+                .find_map(|e| match e {
+                    SelectorElement::Attribute(n, m) => Some((*n, m.clone())),
+                    _ => None,
+                })
+                .expect("Should have an attribute selector");
+            assert_eq!(attribute, (name.as_tag(), matcher));
+        }
+        assert_eq!(rules[0].selector.weight, SelectorWeight(11, 0));
+        assert_eq!(rules[3].selector.weight, SelectorWeight(10, 0));
+
+        assert!(TestParser::new().parse("[mode=] {}").is_empty());
+        assert!(TestParser::new().parse("[mode==a] {}").is_empty());
+    }
+
+    #[test]
+    fn parse_negation_and_grouping_selectors() {
+        let rules = TestParser::new().parse(
+            r#"button:not(:pressed) {}
+            .panel :is(label, span) {}"#,
+        );
+        assert_eq!(rules.len(), 2, "Should have 2 rules");
+        assert!(matches!(
+            &rules[0].selector.elements[0],
+            SelectorElement::Not(s) if s.len() == 1
+        ));
+        assert!(matches!(
+            &rules[1].selector.elements[0],
+            SelectorElement::Is(s) if s.len() == 2
+        ));
+        assert_eq!(rules[0].selector.weight, SelectorWeight(11, 0));
+        assert_eq!(rules[1].selector.weight, SelectorWeight(11, 0));
+
+        assert!(TestParser::new().parse(":not() {}").is_empty());
+        assert!(TestParser::new().parse(":is(a,) {}").is_empty());
+        assert!(TestParser::new().parse(":unknown(a) {}").is_empty());
+    }
+
+    #[test]
+    fn parse_sibling_combinators() {
+        let rules = TestParser::new().parse(
+            r#"button:pressed + label {}
+            h1 ~ p {}
+            .a>.b~.c+.d {}"#,
+        );
+        assert_eq!(rules.len(), 3, "Should have 3 rules");
+        assert!(rules[0].selector.elements[1].is_adjacent_sibling());
+        assert!(rules[1].selector.elements[1].is_general_sibling());
+        assert_eq!(rules[0].selector.to_string(), "button:pressed + label");
+        assert_eq!(rules[1].selector.to_string(), "h1 ~ p");
+        assert_eq!(rules[2].selector.to_string(), ".a > .b ~ .c + .d");
+        assert_eq!(rules[0].selector.weight, SelectorWeight(13, 0));
+        assert_eq!(rules[1].selector.weight, SelectorWeight(2, 0));
+    }
+
+    #[test]
+    fn parse_selector_lists() {
+        let stylesheet = TestParser::new().parse_stylesheet(
+            r#"a, .b > c,#d { a: a; compound: value }
+            e {}
+            f, g, { a: a }"#,
+        );
+        assert_eq!(stylesheet.len(), 4, "Should skip the invalid selector list");
+        let selectors: Vec<_> = stylesheet.iter().map(|r| r.selector.to_string()).collect();
+        assert_eq!(selectors, ["a", ".b > c", "#d", "e"]);
+        assert_eq!(stylesheet[0].selector.weight, SelectorWeight(1, 0));
+        assert_eq!(stylesheet[1].selector.weight, SelectorWeight(11, 0));
+        assert_eq!(stylesheet[2].selector.weight, SelectorWeight(100, 0));
+        for rule in stylesheet.iter().take(3) {
+            assert_eq!(rule.properties.len(), 2);
+            assert!(rule.properties.contains_key(&"b".as_tag()));
+        }
+    }
+
+    #[test]
+    fn parse_nested_rules() {
+        let stylesheet = TestParser::new().parse_stylesheet(
+            r#"
+            .inventory .slot {
+                a: a;
+                &:hover { b: b; }
+                .icon, > span { c: c; }
+                + .slot { d: d }
+                .a & { e: e; }
+                & #broken { z: z; }
+                &.active { .icon { f: f } }
+                g: g;
+            }
+            a, b { &:hover { a: a } }
+            "#,
+        );
+        let selectors: Vec<_> = stylesheet.iter().map(|r| r.selector.to_string()).collect();
+        assert_eq!(
+            selectors,
+            [
+                ".inventory .slot",
+                ".inventory .slot:hover",
+                ".inventory .slot .icon",
+                ".inventory .slot > span",
+                ".inventory .slot + .slot",
+                ".a .inventory .slot",
+                ".inventory .slot.active",
+                ".inventory .slot.active .icon",
+                "a",
+                "b",
+                "a:hover",
+                "b:hover",
+            ]
+        );
+        assert_eq!(
+            stylesheet[0].properties.len(),
+            2,
+            "Should keep trailing declarations"
+        );
+        assert_eq!(stylesheet[1].selector.weight, SelectorWeight(30, 0));
+        assert_eq!(stylesheet[2].selector.weight, SelectorWeight(30, 0));
+        assert_eq!(stylesheet[3].selector.weight, SelectorWeight(22, 0));
+        assert_eq!(stylesheet[7].selector.weight, SelectorWeight(40, 0));
+        assert!(stylesheet[6].properties.is_empty());
+
+        assert!(TestParser::new().parse("& a { a: a }").is_empty());
+    }
+
+    #[test]
+    fn parse_custom_properties() {
+        let rules = TestParser::new().parse(
+            r#"body {
+                --accent: #ff7f00;
+                --gap: 5px 10px;
+                a: var(--accent);
+                b: 1px var(--missing, 2px);
+                c: 3px;
+            }"#,
+        );
+        assert_eq!(rules.len(), 1, "Should have a single rule");
+        let properties = &rules[0].properties;
+        assert_eq!(properties.len(), 5, "Should have 5 properties");
+        let accent = properties
+            .get(&"--accent".as_tag())
+            .unwrap()
+            .downcast_ref::<CustomProperty>()
+            .unwrap();
+        assert_eq!(accent.0, StyleProperty::try_from("#ff7f00").unwrap());
+        for name in ["a", "b"] {
+            let unresolved = properties
+                .get(&name.as_tag())
+                .unwrap()
+                .downcast_ref::<UnresolvedProperty>()
+                .unwrap();
+            assert_eq!(unresolved.name, name.as_tag());
+            assert!(!unresolved.compound);
+        }
+        assert!(properties
+            .get(&"c".as_tag())
+            .unwrap()
+            .downcast_ref::<StyleProperty>()
+            .is_some());
+    }
+
+    #[test]
+    fn parse_keyframes() {
+        let stylesheet = TestParser::new().parse_stylesheet(
+            r#"
+            @keyframes pulse {
+                from { a: a; }
+                50%, 75% { b: b; }
+                to { a: c; }
+            }
+            @keyframes broken {
+                half { a: a; }
+                100% { a: b; }
+            }
+            @unknown rule;
+            a { c: c; }
+            "#,
+        );
+        assert_eq!(stylesheet.len(), 1, "Should have a single style rule");
+        let pulse = stylesheet.keyframes("pulse".as_tag()).unwrap();
+        assert_eq!(pulse.frames.len(), 3);
+        assert_eq!(pulse.frames[0].offsets.as_slice(), &[0.0]);
+        assert_eq!(pulse.frames[1].offsets.as_slice(), &[0.5, 0.75]);
+        assert_eq!(pulse.frames[2].offsets.as_slice(), &[1.0]);
+        assert!(pulse.frames[1].properties.contains_key(&"b".as_tag()));
+        let broken = stylesheet.keyframes("broken".as_tag()).unwrap();
+        assert_eq!(broken.frames.len(), 1, "Invalid keyframe should be skipped");
+
+        let rules = TestParser::new().parse("@keyframes k { to { a: a; } } a { c: c; }");
+        assert_eq!(rules.len(), 1, "Keyframes should be skipped by parse()");
+    }
+
+    #[test]
+    fn parse_media() {
+        let stylesheet = TestParser::new().parse_stylesheet(
+            r#"
+            a { a: a; }
+            @media (max-width: 900px), not screen and (orientation: portrait) {
+                b { b: b; }
+                @media (min-resolution: 192dpi) {
+                    c { c: c; }
+                }
+            }
+            @media (unknown-feature: 1) {
+                d { d: d; }
+            }
+            "#,
+        );
+        assert_eq!(stylesheet.len(), 3, "Should skip the invalid @media block");
+        assert!(stylesheet[0].media.is_empty());
+        assert_eq!(stylesheet[1].media.len(), 1);
+        assert_eq!(stylesheet[2].media.len(), 2);
+        let queries = &stylesheet[1].media[0].0;
+        assert_eq!(queries.len(), 2);
+        assert_eq!(
+            queries[0].features.as_slice(),
+            &[MediaFeature::Width(MediaRange::Max, 900.)]
+        );
+        assert!(queries[1].negated);
+        assert_eq!(queries[1].media_type, MediaType::Screen);
+        assert_eq!(
+            queries[1].features.as_slice(),
+            &[MediaFeature::Orientation(Orientation::Portrait)]
+        );
+        assert_eq!(
+            stylesheet[2].media[1].0[0].features.as_slice(),
+            &[MediaFeature::Resolution(MediaRange::Min, 2.)]
+        );
+
+        let wide = Media {
+            width: 1280.,
+            height: 720.,
+            resolution: 1.,
+        };
+        let narrow = Media {
+            width: 800.,
+            height: 600.,
+            resolution: 2.,
+        };
+        assert!(!stylesheet[1].matches_media(&wide));
+        assert!(stylesheet[1].matches_media(&narrow));
+        assert!(!stylesheet[2].matches_media(&Media {
+            resolution: 1.,
+            ..narrow
+        }));
+        assert!(stylesheet[2].matches_media(&narrow));
+    }
+
+    #[test]
+    fn parse_media_features() {
+        let parse = |query: &str| {
+            let mut input = ParserInput::new(query);
+            let mut parser = Parser::new(&mut input);
+            parse_media_query_list(&mut parser).map_err(|_| ())
+        };
+        let feature = |query: &str| parse(query).unwrap().0[0].features[0].clone();
+        assert_eq!(
+            feature("(aspect-ratio: 16/9)"),
+            MediaFeature::AspectRatio(MediaRange::Exact, 16. / 9.)
+        );
+        assert_eq!(
+            feature("(min-height: 0)"),
+            MediaFeature::Height(MediaRange::Min, 0.)
+        );
+        assert_eq!(
+            feature("only screen and (resolution: 2x)"),
+            MediaFeature::Resolution(MediaRange::Exact, 2.)
+        );
+        assert!(parse("(max-width: 50%)").is_err());
+        assert!(parse("(min-orientation: portrait)").is_err());
+        assert!(parse("screen (width: 10px)").is_err());
+    }
+
     #[test]
     fn parse_single_token() {
         let rules = TestParser::new().parse("a {b: c}");
@@ -687,6 +1562,20 @@ mod tests {
         });
     }
 
+    #[test]
+    fn parse_compound_css_wide_keywords() {
+        let rules = TestParser::new().parse("a { compound: inherit }");
+        assert_eq!(rules.len(), 1);
+        let properties = &rules[0].properties;
+        assert_eq!(properties.len(), 2, "{:?}", properties);
+        for name in ["a", "b"] {
+            let keyword = properties
+                .get(&name.as_tag())
+                .and_then(|p| p.downcast_ref::<CssWideKeyword>());
+            assert_eq!(keyword, Some(&CssWideKeyword::Inherit), "{name}");
+        }
+    }
+
     #[test]
     fn parse_function() {
         let rules = TestParser::new().parse("a { f: minmax(1, \"23\", 4px); }");
@@ -0,0 +1,572 @@
+use std::ops::Deref;
+use std::sync::Arc;
+
+use super::transition::EasingFunction;
+use super::{Property, PropertyValue};
+use crate::{
+    element::Element,
+    ess::{StyleSheet, Styles},
+};
+use bevy::{prelude::*, utils::HashMap};
+use smallvec::SmallVec;
+use tagstr::*;
+
+/// The single keyframe of the `@keyframes` rule: `0%, 50% { ... }`
+#[derive(Debug)]
+pub struct Keyframe {
+    /// Offsets of the keyframe in `0.0..=1.0` range
+    pub offsets: SmallVec<[f32; 2]>,
+    pub properties: HashMap<Tag, PropertyValue>,
+}
+
+/// The `@keyframes name { from { ... } to { ... } }` rule
+#[derive(Debug)]
+pub struct Keyframes {
+    pub name: Tag,
+    pub frames: Vec<Keyframe>,
+}
+
+impl Keyframes {
+    /// Returns the value of the property `T` at `progress` of the animation.
+    /// Values of the adjacent keyframes are interpolated, the properties
+    /// which can't be interpolated are switched in the middle of the keyframes.
+    pub fn value<T: Property>(
+        &self,
+        progress: f32,
+        easing: EasingFunction,
+    ) -> Option<AnimatedValue<T::Item>> {
+        let name = T::name();
+        let mut frames: SmallVec<[(f32, &T::Item); 8]> = self
+            .frames
+            .iter()
+            .filter_map(|f| {
+                let value = f.properties.get(&name)?.downcast_ref::<T::Item>()?;
+                Some(f.offsets.iter().map(move |offset| (*offset, value)))
+            })
+            .flatten()
+            .collect();
+        // the stable sort keeps the later keyframe with the same offset last
+        frames.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let next = frames.iter().position(|(offset, _)| *offset > progress);
+        match next {
+            None => frames.last().map(|(_, v)| AnimatedValue::Borrowed(*v)),
+            Some(0) => frames.first().map(|(_, v)| AnimatedValue::Borrowed(*v)),
+            Some(idx) => {
+                let (from_offset, from) = frames[idx - 1];
+                let (to_offset, to) = frames[idx];
+                let t = easing.ease((progress - from_offset) / (to_offset - from_offset));
+                if let Some(value) = T::interpolate(from, to, t) {
+                    Some(AnimatedValue::Owned(value))
+                } else if t < 0.5 {
+                    Some(AnimatedValue::Borrowed(from))
+                } else {
+                    Some(AnimatedValue::Borrowed(to))
+                }
+            }
+        }
+    }
+}
+
+pub enum AnimatedValue<'a, T> {
+    Borrowed(&'a T),
+    Owned(T),
+}
+
+impl<'a, T> Deref for AnimatedValue<'a, T> {
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        match self {
+            AnimatedValue::Borrowed(value) => value,
+            AnimatedValue::Owned(value) => value,
+        }
+    }
+}
+
+/// Finds the `@keyframes` rule by name. When multiple stylesheets define
+/// the same keyframes, the one loaded later wins.
+pub fn find_keyframes(
+    name: Tag,
+    styles: &Styles,
+    stylesheets: &Assets<StyleSheet>,
+) -> Option<Arc<Keyframes>> {
+    styles
+        .iter()
+        .filter_map(|h| stylesheets.get(h))
+        .filter_map(|s| s.keyframes(name).map(|k| (s.extra_weight(), k)))
+        .max_by_key(|(weight, _)| *weight)
+        .map(|(_, keyframes)| keyframes.clone())
+}
+
+/// The number of times the animation is played: `infinite` or number
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IterationCount {
+    Count(f32),
+    Infinite,
+}
+
+impl Default for IterationCount {
+    fn default() -> Self {
+        IterationCount::Count(1.0)
+    }
+}
+
+/// Whether the animation plays forwards, backwards, or alternates
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum AnimationDirection {
+    #[default]
+    Normal,
+    Reverse,
+    Alternate,
+    AlternateReverse,
+}
+
+/// How the animation applies the values before and after it is played
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum FillMode {
+    #[default]
+    None,
+    Forwards,
+    Backwards,
+    Both,
+}
+
+impl FillMode {
+    fn forwards(&self) -> bool {
+        matches!(self, FillMode::Forwards | FillMode::Both)
+    }
+    fn backwards(&self) -> bool {
+        matches!(self, FillMode::Backwards | FillMode::Both)
+    }
+}
+
+/// The phase of the animation at some point of time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AnimationPhase {
+    /// The animation is delayed
+    Before,
+    /// The animation is playing its `iteration` at directed `progress`
+    Active { iteration: u32, progress: f32 },
+    /// The animation is finished at directed `progress`
+    After { progress: f32 },
+}
+
+/// Settings of the single animation of the element
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnimationSpec {
+    pub name: Tag,
+    /// Duration of the single iteration in seconds
+    pub duration: f32,
+    pub easing: EasingFunction,
+    /// Delay in seconds
+    pub delay: f32,
+    pub iterations: IterationCount,
+    pub direction: AnimationDirection,
+    pub fill_mode: FillMode,
+}
+
+impl AnimationSpec {
+    fn directed(&self, iteration: u32, progress: f32) -> f32 {
+        let reversed = match self.direction {
+            AnimationDirection::Normal => false,
+            AnimationDirection::Reverse => true,
+            AnimationDirection::Alternate => iteration % 2 == 1,
+            AnimationDirection::AlternateReverse => iteration % 2 == 0,
+        };
+        if reversed {
+            1.0 - progress
+        } else {
+            progress
+        }
+    }
+
+    /// Returns the phase of the animation after `elapsed` seconds from its start
+    pub fn phase(&self, elapsed: f32) -> AnimationPhase {
+        let time = elapsed - self.delay;
+        if time < 0.0 {
+            return AnimationPhase::Before;
+        }
+        let count = match self.iterations {
+            IterationCount::Count(count) => Some(count.max(0.0)),
+            IterationCount::Infinite => None,
+        };
+        let finished = match count {
+            Some(count) => self.duration <= 0.0 || time >= self.duration * count,
+            None => false,
+        };
+        if finished {
+            let count = count.unwrap_or_default();
+            let iteration = (count.ceil() as u32).max(1) - 1;
+            let progress = if count == 0.0 {
+                0.0
+            } else {
+                count - iteration as f32
+            };
+            AnimationPhase::After {
+                progress: self.directed(iteration, progress),
+            }
+        } else if self.duration <= 0.0 {
+            // infinite animation without duration never progresses
+            AnimationPhase::Active {
+                iteration: 0,
+                progress: self.directed(0, 0.0),
+            }
+        } else {
+            let iteration = (time / self.duration).floor();
+            let progress = time / self.duration - iteration;
+            AnimationPhase::Active {
+                iteration: iteration as u32,
+                progress: self.directed(iteration as u32, progress),
+            }
+        }
+    }
+
+    /// Returns the progress of the animation which values should be
+    /// applied after `elapsed` seconds, respecting the fill mode.
+    pub fn fill_progress(&self, elapsed: f32) -> Option<f32> {
+        match self.phase(elapsed) {
+            AnimationPhase::Before if self.fill_mode.backwards() => Some(self.directed(0, 0.0)),
+            AnimationPhase::Before => None,
+            AnimationPhase::Active { progress, .. } => Some(progress),
+            AnimationPhase::After { progress } if self.fill_mode.forwards() => Some(progress),
+            AnimationPhase::After { .. } => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimationEventKind {
+    Start,
+    Iteration,
+    End,
+}
+
+/// Sent when the animation of the element starts, repeats or ends.
+/// Connected with `on:animation_start`, `on:animation_iteration` and
+/// `on:animation_end`.
+#[derive(Event, Debug, Clone, PartialEq)]
+pub struct AnimationEvent {
+    pub entity: Entity,
+    /// The name of the `@keyframes`
+    pub name: Tag,
+    pub kind: AnimationEventKind,
+}
+
+#[derive(Debug, Clone)]
+struct RunningAnimation {
+    name: Tag,
+    elapsed: f32,
+    iteration: u32,
+    started: bool,
+    finished: bool,
+    /// The `@keyframes` resolved when the animation starts or stylesheets change
+    keyframes: Option<Arc<Keyframes>>,
+    resolved: bool,
+}
+
+/// Animations of the element, defined by the `animation-*` properties.
+/// Lists are matched the css way: the `names` list defines the number of
+/// animations, the other lists are repeated when they are shorter.
+#[derive(Component, Debug, Clone, Default)]
+pub struct Animations {
+    pub names: Vec<Tag>,
+    pub durations: Vec<f32>,
+    pub easings: Vec<EasingFunction>,
+    pub delays: Vec<f32>,
+    pub iterations: Vec<IterationCount>,
+    pub directions: Vec<AnimationDirection>,
+    pub fill_modes: Vec<FillMode>,
+    running: Vec<RunningAnimation>,
+}
+
+impl Animations {
+    /// Returns the settings of the animation at `idx` of the `names` list
+    pub fn get(&self, idx: usize) -> Option<AnimationSpec> {
+        fn cycle<T: Copy + Default>(values: &[T], idx: usize) -> T {
+            if values.is_empty() {
+                T::default()
+            } else {
+                values[idx % values.len()]
+            }
+        }
+        Some(AnimationSpec {
+            name: *self.names.get(idx)?,
+            duration: cycle(&self.durations, idx).max(0.0),
+            easing: cycle(&self.easings, idx),
+            delay: cycle(&self.delays, idx),
+            iterations: cycle(&self.iterations, idx),
+            direction: cycle(&self.directions, idx),
+            fill_mode: cycle(&self.fill_modes, idx),
+        })
+    }
+
+    /// Iterates over the playing animations with the time elapsed since their
+    /// start and the resolved `@keyframes`
+    pub fn playing(&self) -> impl Iterator<Item = (AnimationSpec, f32, &Keyframes)> + '_ {
+        self.names.iter().enumerate().filter_map(|(idx, name)| {
+            let running = self.running.iter().find(|r| r.name == *name)?;
+            let keyframes = running.keyframes.as_deref()?;
+            Some((self.get(idx)?, running.elapsed, keyframes))
+        })
+    }
+
+    /// Resolves the `@keyframes` of the started animations with `find`.
+    /// When `all` is set, the already resolved keyframes are resolved again.
+    fn resolve_keyframes(&mut self, all: bool, find: impl Fn(Tag) -> Option<Arc<Keyframes>>) {
+        for running in self.running.iter_mut() {
+            if all || !running.resolved {
+                running.keyframes = find(running.name);
+                running.resolved = true;
+            }
+        }
+    }
+
+    fn is_idle(&self) -> bool {
+        self.running.iter().all(|r| r.finished)
+            && self
+                .names
+                .iter()
+                .all(|n| *n == tag!("none") || self.running.iter().any(|r| r.name == *n))
+    }
+
+    /// Starts the animations added to `names` and drops the removed ones.
+    /// Returns `true` if some animation was removed.
+    fn sync(&mut self) -> bool {
+        let names = &self.names;
+        let count = self.running.len();
+        self.running.retain(|r| names.contains(&r.name));
+        let removed = count != self.running.len();
+        for name in self.names.iter() {
+            if *name != tag!("none") && !self.running.iter().any(|r| r.name == *name) {
+                self.running.push(RunningAnimation {
+                    name: *name,
+                    elapsed: 0.0,
+                    iteration: 0,
+                    started: false,
+                    finished: false,
+                    keyframes: None,
+                    resolved: false,
+                })
+            }
+        }
+        removed
+    }
+
+    /// Advances the animations by `delta` seconds reporting the started,
+    /// repeated and finished animations with `emit`. Returns `true` if
+    /// the styles of the element should be applied again, because some
+    /// animation stopped to affect them.
+    pub(crate) fn advance(
+        &mut self,
+        delta: f32,
+        mut emit: impl FnMut(Tag, AnimationEventKind),
+    ) -> bool {
+        let mut restore = self.sync();
+        for idx in 0..self.running.len() {
+            let name = self.running[idx].name;
+            let Some(spec) = self
+                .names
+                .iter()
+                .position(|n| *n == name)
+                .and_then(|idx| self.get(idx))
+            else {
+                continue;
+            };
+            let running = &mut self.running[idx];
+            if running.finished {
+                continue;
+            }
+            running.elapsed += delta;
+            let phase = spec.phase(running.elapsed);
+            if !running.started && phase != AnimationPhase::Before {
+                running.started = true;
+                emit(name, AnimationEventKind::Start);
+            }
+            match phase {
+                AnimationPhase::Active { iteration, .. } if iteration > running.iteration => {
+                    running.iteration = iteration;
+                    emit(name, AnimationEventKind::Iteration);
+                }
+                AnimationPhase::After { .. } => {
+                    running.finished = true;
+                    emit(name, AnimationEventKind::End);
+                    restore |= !spec.fill_mode.forwards();
+                }
+                _ => {}
+            }
+        }
+        restore
+    }
+}
+
+/// Advances the animations of the elements and sends [`AnimationEvent`]s.
+/// The `@keyframes` are resolved once when the animation starts and
+/// again when the stylesheets change.
+pub fn advance_animations(
+    mut animated: Query<(Entity, &mut Animations, &mut Element)>,
+    mut events: EventWriter<AnimationEvent>,
+    mut stylesheet_events: EventReader<AssetEvent<StyleSheet>>,
+    styles: Res<Styles>,
+    stylesheets: Res<Assets<StyleSheet>>,
+    time: Res<Time>,
+) {
+    let delta = time.delta_seconds();
+    let stylesheets_changed = styles.is_changed() || stylesheet_events.iter().count() > 0;
+    let find = |name| find_keyframes(name, &styles, &stylesheets);
+    for (entity, mut animations, mut element) in animated.iter_mut() {
+        if animations.is_idle() {
+            if stylesheets_changed {
+                animations.resolve_keyframes(true, &find);
+            }
+            continue;
+        }
+        let restore = animations.advance(delta, |name, kind| {
+            events.send(AnimationEvent { entity, name, kind })
+        });
+        animations.resolve_keyframes(stylesheets_changed, &find);
+        if restore {
+            element.invalidate();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(
+        duration: f32,
+        delay: f32,
+        iterations: IterationCount,
+        direction: AnimationDirection,
+        fill_mode: FillMode,
+    ) -> AnimationSpec {
+        AnimationSpec {
+            name: tag!("test"),
+            duration,
+            easing: EasingFunction::Linear,
+            delay,
+            iterations,
+            direction,
+            fill_mode,
+        }
+    }
+
+    #[test]
+    fn animation_phases() {
+        let once = spec(
+            2.0,
+            1.0,
+            IterationCount::Count(1.0),
+            AnimationDirection::Normal,
+            FillMode::None,
+        );
+        assert_eq!(once.phase(0.5), AnimationPhase::Before);
+        assert_eq!(
+            once.phase(2.0),
+            AnimationPhase::Active {
+                iteration: 0,
+                progress: 0.5
+            }
+        );
+        assert_eq!(once.phase(3.0), AnimationPhase::After { progress: 1.0 });
+        assert_eq!(once.fill_progress(0.5), None);
+        assert_eq!(once.fill_progress(3.5), None);
+
+        let alternate = spec(
+            1.0,
+            0.0,
+            IterationCount::Count(2.5),
+            AnimationDirection::Alternate,
+            FillMode::Both,
+        );
+        assert_eq!(
+            alternate.phase(1.25),
+            AnimationPhase::Active {
+                iteration: 1,
+                progress: 0.75
+            }
+        );
+        assert_eq!(
+            alternate.phase(3.0),
+            AnimationPhase::After { progress: 0.5 }
+        );
+        assert_eq!(alternate.fill_progress(10.0), Some(0.5));
+
+        let reverse = spec(
+            1.0,
+            1.0,
+            IterationCount::Infinite,
+            AnimationDirection::Reverse,
+            FillMode::Backwards,
+        );
+        assert_eq!(reverse.fill_progress(0.5), Some(1.0));
+        assert_eq!(
+            reverse.phase(101.25),
+            AnimationPhase::Active {
+                iteration: 100,
+                progress: 0.75
+            }
+        );
+    }
+
+    #[test]
+    fn animation_events() {
+        let mut animations = Animations {
+            names: vec![tag!("pulse")],
+            durations: vec![1.0],
+            iterations: vec![IterationCount::Count(2.0)],
+            ..default()
+        };
+        let mut events = vec![];
+        let mut advance = |animations: &mut Animations, delta: f32| {
+            animations.advance(delta, |name, kind| events.push((name, kind)))
+        };
+        assert!(!advance(&mut animations, 0.5));
+        assert!(!advance(&mut animations, 0.75));
+        assert!(advance(&mut animations, 1.0));
+        assert!(animations.is_idle());
+        assert_eq!(
+            events,
+            vec![
+                (tag!("pulse"), AnimationEventKind::Start),
+                (tag!("pulse"), AnimationEventKind::Iteration),
+                (tag!("pulse"), AnimationEventKind::End),
+            ]
+        );
+
+        // the animation restarts when its name is set again
+        animations.names = vec![tag!("none")];
+        let mut events = vec![];
+        assert!(animations.advance(0.1, |name, kind| events.push((name, kind))));
+        animations.names = vec![tag!("pulse")];
+        assert!(!animations.advance(0.1, |name, kind| events.push((name, kind))));
+        assert_eq!(events, vec![(tag!("pulse"), AnimationEventKind::Start)]);
+    }
+
+    #[test]
+    fn resolve_keyframes_once() {
+        let mut animations = Animations {
+            names: vec![tag!("pulse")],
+            durations: vec![1.0],
+            ..default()
+        };
+        let lookups = std::cell::Cell::new(0);
+        let find = |name| {
+            lookups.set(lookups.get() + 1);
+            Some(Arc::new(Keyframes {
+                name,
+                frames: vec![],
+            }))
+        };
+        animations.advance(0.1, |_, _| {});
+        animations.resolve_keyframes(false, &find);
+        assert_eq!(animations.playing().count(), 1);
+        animations.advance(0.1, |_, _| {});
+        animations.resolve_keyframes(false, &find);
+        assert_eq!(lookups.get(), 1);
+
+        // the changed stylesheets resolve the keyframes again
+        animations.resolve_keyframes(true, |_| None);
+        assert_eq!(animations.playing().count(), 0);
+    }
+}
@@ -0,0 +1,328 @@
+use super::{colors, PropertyValue, StylePropertyToken};
+use crate::ElementsError;
+use bevy::{
+    prelude::*,
+    render::{texture::DEFAULT_IMAGE_HANDLE, Extract},
+    ui::{ExtractedUiNode, ExtractedUiNodes, UiStack},
+    utils::HashMap,
+    window::PrimaryWindow,
+};
+use tagstr::Tag;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BorderSide {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+/// The colors of the element borders. When all the sides share the same
+/// color it is passed to the [`BorderColor`] component, otherwise every
+/// side is rendered separately.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct BorderColors {
+    pub left: Color,
+    pub right: Color,
+    pub top: Color,
+    pub bottom: Color,
+}
+
+impl Default for BorderColors {
+    fn default() -> Self {
+        BorderColors::all(Color::NONE)
+    }
+}
+
+impl BorderColors {
+    pub fn new(left: Color, right: Color, top: Color, bottom: Color) -> BorderColors {
+        BorderColors {
+            left,
+            right,
+            top,
+            bottom,
+        }
+    }
+
+    pub fn all(color: Color) -> BorderColors {
+        BorderColors::new(color, color, color, color)
+    }
+
+    /// Splits the colors into the `border-{side}-color` longhands
+    pub fn to_longhands(&self) -> HashMap<Tag, PropertyValue> {
+        let mut props = HashMap::default();
+        for (edge, value) in [
+            ("left", self.left),
+            ("right", self.right),
+            ("top", self.top),
+            ("bottom", self.bottom),
+        ] {
+            props.insert(
+                Tag::new(format!("border-{edge}-color")),
+                PropertyValue::new(value),
+            );
+        }
+        props
+    }
+
+    /// Parses one to four colors in the `top right bottom left` order
+    /// of the css rects.
+    pub fn parse(tokens: &[StylePropertyToken]) -> Result<BorderColors, ElementsError> {
+        let color = colors::parse_color;
+        match tokens {
+            [all] => color(all).map(BorderColors::all),
+            [top_bottom, left_right] => {
+                let top_bottom = color(top_bottom)?;
+                let left_right = color(left_right)?;
+                Ok(BorderColors::new(
+                    left_right, left_right, top_bottom, top_bottom,
+                ))
+            }
+            [top, left_right, bottom] => {
+                let left_right = color(left_right)?;
+                Ok(BorderColors::new(
+                    left_right,
+                    left_right,
+                    color(top)?,
+                    color(bottom)?,
+                ))
+            }
+            [top, right, bottom, left] => Ok(BorderColors::new(
+                color(left)?,
+                color(right)?,
+                color(top)?,
+                color(bottom)?,
+            )),
+            tokens => Err(ElementsError::InvalidPropertyValue(format!(
+                "Expected 1 to 4 colors, got `{}`",
+                tokens
+                    .iter()
+                    .map(|t| t.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            ))),
+        }
+    }
+
+    pub fn get(&self, side: BorderSide) -> Color {
+        match side {
+            BorderSide::Left => self.left,
+            BorderSide::Right => self.right,
+            BorderSide::Top => self.top,
+            BorderSide::Bottom => self.bottom,
+        }
+    }
+
+    pub fn set(&mut self, side: BorderSide, color: Color) {
+        match side {
+            BorderSide::Left => self.left = color,
+            BorderSide::Right => self.right = color,
+            BorderSide::Top => self.top = color,
+            BorderSide::Bottom => self.bottom = color,
+        }
+    }
+
+    /// Returns the color of the borders if all the sides share it
+    pub fn uniform(&self) -> Option<Color> {
+        if self.left == self.right && self.left == self.top && self.left == self.bottom {
+            Some(self.left)
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn apply(
+        side: BorderSide,
+        color: Color,
+        colors: Option<Mut<BorderColors>>,
+        commands: &mut Commands,
+        entity: Entity,
+    ) {
+        match colors {
+            Some(mut colors) => {
+                if colors.get(side) != color {
+                    colors.set(side, color);
+                }
+            }
+            None if color == Color::NONE => {}
+            None => commands.add(move |world: &mut World| {
+                let Some(mut entity) = world.get_entity_mut(entity) else {
+                    return;
+                };
+                if let Some(mut colors) = entity.get_mut::<BorderColors>() {
+                    colors.set(side, color);
+                } else {
+                    let mut colors = BorderColors::default();
+                    colors.set(side, color);
+                    entity.insert(colors);
+                }
+            }),
+        }
+    }
+}
+
+/// Passes the uniform border color to the [`BorderColor`] component. The
+/// [`BorderColor`] is cleared for the borders with different colors per
+/// side, they are extracted by [`extract_border_colors`].
+pub(crate) fn update_border_colors(
+    mut commands: Commands,
+    mut elements: Query<(Entity, &BorderColors, Option<&mut BorderColor>), Changed<BorderColors>>,
+) {
+    for (entity, colors, border_color) in elements.iter_mut() {
+        let color = colors.uniform().unwrap_or(Color::NONE);
+        match border_color {
+            Some(mut border_color) if border_color.0 != color => border_color.0 = color,
+            Some(_) => {}
+            None => {
+                commands.entity(entity).insert(BorderColor(color));
+            }
+        }
+    }
+}
+
+fn resolve_border(value: Val, parent_width: f32, viewport: Vec2, scale: f32) -> f32 {
+    match value {
+        Val::Px(px) => px * scale,
+        Val::Percent(percent) => parent_width * percent / 100.,
+        Val::Vw(percent) => viewport.x * percent / 100.,
+        Val::Vh(percent) => viewport.y * percent / 100.,
+        Val::VMin(percent) => viewport.min_element() * percent / 100.,
+        Val::VMax(percent) => viewport.max_element() * percent / 100.,
+        _ => 0.,
+    }
+}
+
+/// Extracts the borders with different colors per side, one quad per side.
+/// The quads are extracted after the gradients and the styleboxes, so the
+/// borders are drawn over the element background.
+pub(crate) fn extract_border_colors(
+    mut extracted_uinodes: ResMut<ExtractedUiNodes>,
+    ui_stack: Extract<Res<UiStack>>,
+    ui_scale: Extract<Res<UiScale>>,
+    windows: Extract<Query<&Window, With<PrimaryWindow>>>,
+    nodes: Extract<
+        Query<(
+            &Node,
+            &GlobalTransform,
+            &Style,
+            &BorderColors,
+            Option<&Parent>,
+            &ComputedVisibility,
+            Option<&CalculatedClip>,
+        )>,
+    >,
+    parents: Extract<Query<&Node>>,
+) {
+    let scale = ui_scale.scale as f32;
+    let viewport = windows
+        .get_single()
+        .map(|window| Vec2::new(window.width(), window.height()))
+        .unwrap_or(Vec2::ZERO);
+    for (stack_index, entity) in ui_stack.uinodes.iter().enumerate() {
+        let Ok((node, transform, style, colors, parent, visibility, clip)) = nodes.get(*entity)
+        else {
+            continue;
+        };
+        if colors.uniform().is_some() || !visibility.is_visible() {
+            continue;
+        }
+        let size = node.size();
+        if size.x <= 0. || size.y <= 0. {
+            continue;
+        }
+        let parent_width = parent
+            .and_then(|parent| parents.get(parent.get()).ok())
+            .map(|parent| parent.size().x)
+            .unwrap_or(viewport.x);
+        let border = |value| resolve_border(value, parent_width, viewport, scale);
+        let left = border(style.border.left);
+        let right = border(style.border.right);
+        let top = border(style.border.top);
+        let bottom = border(style.border.bottom);
+
+        let max = 0.5 * size;
+        let min = -max;
+        let inner_min = min + Vec2::new(left, top);
+        let inner_max = (max - Vec2::new(right, bottom)).max(inner_min);
+        let edges = [
+            (
+                colors.left,
+                Rect::from_corners(min, Vec2::new(inner_min.x, max.y)),
+            ),
+            (
+                colors.right,
+                Rect::from_corners(Vec2::new(inner_max.x, min.y), max),
+            ),
+            (
+                colors.top,
+                Rect::from_corners(
+                    Vec2::new(inner_min.x, min.y),
+                    Vec2::new(inner_max.x, inner_min.y),
+                ),
+            ),
+            (
+                colors.bottom,
+                Rect::from_corners(
+                    Vec2::new(inner_min.x, inner_max.y),
+                    Vec2::new(inner_max.x, max.y),
+                ),
+            ),
+        ];
+        let transform = transform.compute_matrix();
+        for (color, edge) in edges {
+            if color.a() <= 0. || edge.is_empty() {
+                continue;
+            }
+            extracted_uinodes.uinodes.push(ExtractedUiNode {
+                transform: transform * Mat4::from_translation(edge.center().extend(0.)),
+                color,
+                rect: Rect {
+                    max: edge.size(),
+                    ..default()
+                },
+                image: DEFAULT_IMAGE_HANDLE.typed(),
+                atlas_size: None,
+                clip: clip.map(|clip| clip.clip),
+                stack_index,
+                flip_x: false,
+                flip_y: false,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ess::StyleProperty;
+
+    fn colors(value: &str) -> Result<BorderColors, ElementsError> {
+        BorderColors::parse(&StyleProperty::try_from(value).unwrap())
+    }
+
+    #[test]
+    fn parse_border_colors() {
+        assert_eq!(colors("red"), Ok(BorderColors::all(Color::RED)));
+        assert_eq!(
+            colors("red blue"),
+            Ok(BorderColors::new(
+                Color::BLUE,
+                Color::BLUE,
+                Color::RED,
+                Color::RED
+            ))
+        );
+        assert_eq!(
+            colors("red blue yellow white"),
+            Ok(BorderColors::new(
+                Color::WHITE,
+                Color::BLUE,
+                Color::RED,
+                Color::YELLOW
+            ))
+        );
+        assert_eq!(colors("red blue yellow").map(|c| c.uniform()), Ok(None));
+        assert!(colors("red 2px").is_err());
+        assert!(colors("red red red red red").is_err());
+    }
+}
@@ -0,0 +1,682 @@
+use super::{Interpolate, PropertyValue, StylePropertyFunction, StylePropertyToken, ToRectMap};
+use crate::{ess::Media, ElementsError};
+use bevy::{prelude::*, utils::HashMap};
+use tagstr::Tag;
+
+fn source(tokens: &[StylePropertyToken]) -> String {
+    tokens
+        .iter()
+        .map(|t| t.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The expression of the math functions: `calc()`, `min()`, `max()` and
+/// `clamp()`. Percentages are resolved against the basis passed to
+/// [`CalcExpr::eval`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum CalcExpr {
+    Px(f32),
+    Percent(f32),
+    Number(f32),
+    Add(Box<CalcExpr>, Box<CalcExpr>),
+    Sub(Box<CalcExpr>, Box<CalcExpr>),
+    Mul(Box<CalcExpr>, Box<CalcExpr>),
+    Div(Box<CalcExpr>, Box<CalcExpr>),
+    Min(Vec<CalcExpr>),
+    Max(Vec<CalcExpr>),
+}
+
+impl CalcExpr {
+    pub fn is_math_function(name: &str) -> bool {
+        matches!(name, "calc" | "min" | "max" | "clamp")
+    }
+
+    /// Parses the math function resolving to the length
+    pub fn parse(func: &StylePropertyFunction) -> Result<CalcExpr, ElementsError> {
+        let expr = CalcExpr::parse_function(func)?;
+        if expr.is_number() {
+            return Err(ElementsError::InvalidPropertyValue(format!(
+                "Expected length, got number `{}`",
+                StylePropertyToken::Function(func.clone()).to_string()
+            )));
+        }
+        Ok(expr)
+    }
+
+    fn parse_function(func: &StylePropertyFunction) -> Result<CalcExpr, ElementsError> {
+        let mut args = func
+            .args
+            .iter()
+            .map(|arg| match arg {
+                StylePropertyToken::Tokens(tokens) => CalcExpr::parse_sum(tokens),
+                token => CalcExpr::parse_sum(std::slice::from_ref(token)),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if args.iter().any(|a| a.is_number() != args[0].is_number()) {
+            return Err(ElementsError::InvalidPropertyValue(format!(
+                "Can't mix numbers and lengths in `{}`",
+                StylePropertyToken::Function(func.clone()).to_string()
+            )));
+        }
+        match (func.name.as_str(), args.len()) {
+            // the empty name stands for the parenthesized group
+            ("calc" | "", 1) => Ok(args.pop().unwrap()),
+            ("min", n) if n > 0 => Ok(CalcExpr::Min(args)),
+            ("max", n) if n > 0 => Ok(CalcExpr::Max(args)),
+            ("clamp", 3) => {
+                let max = args.pop().unwrap();
+                let value = args.pop().unwrap();
+                let min = args.pop().unwrap();
+                Ok(CalcExpr::Max(vec![min, CalcExpr::Min(vec![value, max])]))
+            }
+            (name, n) => Err(ElementsError::InvalidPropertyValue(format!(
+                "Unexpected number of `{name}()` arguments: {n}"
+            ))),
+        }
+    }
+
+    fn parse_sum(tokens: &[StylePropertyToken]) -> Result<CalcExpr, ElementsError> {
+        let Some(idx) = tokens
+            .iter()
+            .rposition(|t| matches!(t, StylePropertyToken::Plus | StylePropertyToken::Minus))
+        else {
+            return CalcExpr::parse_product(tokens);
+        };
+        let left = CalcExpr::parse_sum(&tokens[..idx])?;
+        let right = CalcExpr::parse_product(&tokens[idx + 1..])?;
+        if left.is_number() != right.is_number() {
+            return Err(ElementsError::InvalidPropertyValue(format!(
+                "Can't add number to length in `{}`",
+                source(tokens)
+            )));
+        }
+        match tokens[idx] {
+            StylePropertyToken::Plus => Ok(CalcExpr::Add(Box::new(left), Box::new(right))),
+            _ => Ok(CalcExpr::Sub(Box::new(left), Box::new(right))),
+        }
+    }
+
+    fn parse_product(tokens: &[StylePropertyToken]) -> Result<CalcExpr, ElementsError> {
+        let Some(idx) = tokens
+            .iter()
+            .rposition(|t| matches!(t, StylePropertyToken::Asterisk | StylePropertyToken::Slash))
+        else {
+            return CalcExpr::parse_value(tokens);
+        };
+        let left = CalcExpr::parse_product(&tokens[..idx])?;
+        let right = CalcExpr::parse_value(&tokens[idx + 1..])?;
+        match tokens[idx] {
+            StylePropertyToken::Asterisk if left.is_number() || right.is_number() => {
+                Ok(CalcExpr::Mul(Box::new(left), Box::new(right)))
+            }
+            StylePropertyToken::Slash if right.is_number() => {
+                Ok(CalcExpr::Div(Box::new(left), Box::new(right)))
+            }
+            _ => Err(ElementsError::InvalidPropertyValue(format!(
+                "Lengths can be multiplied or divided only by numbers, got `{}`",
+                source(tokens)
+            ))),
+        }
+    }
+
+    fn parse_value(tokens: &[StylePropertyToken]) -> Result<CalcExpr, ElementsError> {
+        match tokens {
+            [StylePropertyToken::Percentage(val)] => Ok(CalcExpr::Percent(val.into())),
+            [StylePropertyToken::Dimension(val, unit)] if unit == "px" => {
+                Ok(CalcExpr::Px(val.into()))
+            }
+            [StylePropertyToken::Number(val)] => Ok(CalcExpr::Number(val.into())),
+            [StylePropertyToken::Function(func)]
+                if func.name.is_empty() || CalcExpr::is_math_function(&func.name) =>
+            {
+                CalcExpr::parse_function(func)
+            }
+            [] => Err(ElementsError::InvalidPropertyValue(format!(
+                "Expected math expression operand, got nothing"
+            ))),
+            tokens => Err(ElementsError::InvalidPropertyValue(format!(
+                "Expected math expression operand, got `{}`",
+                source(tokens)
+            ))),
+        }
+    }
+
+    /// Returns `true` if the expression resolves to the plain number
+    pub fn is_number(&self) -> bool {
+        match self {
+            CalcExpr::Number(_) => true,
+            CalcExpr::Px(_) | CalcExpr::Percent(_) => false,
+            CalcExpr::Add(a, _) | CalcExpr::Sub(a, _) | CalcExpr::Div(a, _) => a.is_number(),
+            CalcExpr::Mul(a, b) => a.is_number() && b.is_number(),
+            CalcExpr::Min(args) | CalcExpr::Max(args) => args.iter().any(|a| a.is_number()),
+        }
+    }
+
+    /// Returns `true` if the expression depends on the basis
+    pub fn has_percent(&self) -> bool {
+        match self {
+            CalcExpr::Percent(_) => true,
+            CalcExpr::Px(_) | CalcExpr::Number(_) => false,
+            CalcExpr::Add(a, b)
+            | CalcExpr::Sub(a, b)
+            | CalcExpr::Mul(a, b)
+            | CalcExpr::Div(a, b) => a.has_percent() || b.has_percent(),
+            CalcExpr::Min(args) | CalcExpr::Max(args) => args.iter().any(|a| a.has_percent()),
+        }
+    }
+
+    /// Evaluates the expression in ui pixels, percentages are resolved
+    /// against the `basis`.
+    pub fn eval(&self, basis: f32) -> f32 {
+        match self {
+            CalcExpr::Px(px) => *px,
+            CalcExpr::Percent(percent) => basis * percent / 100.,
+            CalcExpr::Number(num) => *num,
+            CalcExpr::Add(a, b) => a.eval(basis) + b.eval(basis),
+            CalcExpr::Sub(a, b) => a.eval(basis) - b.eval(basis),
+            CalcExpr::Mul(a, b) => a.eval(basis) * b.eval(basis),
+            CalcExpr::Div(a, b) => a.eval(basis) / b.eval(basis),
+            CalcExpr::Min(args) => args.iter().map(|a| a.eval(basis)).fold(f32::MAX, f32::min),
+            CalcExpr::Max(args) => args.iter().map(|a| a.eval(basis)).fold(f32::MIN, f32::max),
+        }
+    }
+}
+
+/// The value of the length properties: either the plain [`Val`] or the math
+/// expression which is evaluated after the layout.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Length {
+    Val(Val),
+    Calc(CalcExpr),
+}
+
+impl Default for Length {
+    fn default() -> Self {
+        Length::Val(Val::default())
+    }
+}
+
+impl From<Val> for Length {
+    fn from(val: Val) -> Self {
+        Length::Val(val)
+    }
+}
+
+impl Length {
+    /// Parses the single token as the length. The math functions without
+    /// percentages are evaluated right away.
+    pub fn from_token(token: &StylePropertyToken) -> Result<Length, ElementsError> {
+        match token {
+            StylePropertyToken::Function(func) if CalcExpr::is_math_function(&func.name) => {
+                let expr = CalcExpr::parse(func)?;
+                if expr.has_percent() {
+                    Ok(Length::Calc(expr))
+                } else {
+                    Ok(Length::Val(Val::Px(expr.eval(0.))))
+                }
+            }
+            token => token.val().map(Length::Val),
+        }
+    }
+
+    fn to_expr(&self) -> Option<CalcExpr> {
+        match self {
+            Length::Val(Val::Px(px)) => Some(CalcExpr::Px(*px)),
+            Length::Val(Val::Percent(percent)) => Some(CalcExpr::Percent(*percent)),
+            Length::Calc(expr) => Some(expr.clone()),
+            _ => None,
+        }
+    }
+
+    /// Writes the length into the `field` of the `style`. The math expressions
+    /// are kept by the [`CalcLengths`] component until the plain value replaces them.
+    pub(crate) fn apply(
+        &self,
+        field: LengthField,
+        mut style: Mut<Style>,
+        calc: Option<Mut<CalcLengths>>,
+        commands: &mut Commands,
+        entity: Entity,
+    ) {
+        match self {
+            Length::Val(val) => {
+                if let Some(mut calc) = calc.filter(|c| c.0.contains_key(&field)) {
+                    calc.0.remove(&field);
+                }
+                if field.val(&style) != *val {
+                    *field.val_mut(&mut style) = *val;
+                }
+            }
+            Length::Calc(expr) => match calc {
+                Some(mut calc) if calc.0.get(&field) != Some(expr) => {
+                    calc.0.insert(field, expr.clone());
+                }
+                Some(_) => {}
+                None => {
+                    let expr = expr.clone();
+                    commands.add(move |world: &mut World| {
+                        let Some(mut entity) = world.get_entity_mut(entity) else {
+                            return;
+                        };
+                        if let Some(mut calc) = entity.get_mut::<CalcLengths>() {
+                            calc.0.insert(field, expr);
+                        } else {
+                            let mut calc = CalcLengths::default();
+                            calc.0.insert(field, expr);
+                            entity.insert(calc);
+                        }
+                    });
+                }
+            },
+        }
+    }
+}
+
+impl Interpolate for Length {
+    fn interpolate(&self, to: &Self, t: f32) -> Option<Self> {
+        if let (Length::Val(from), Length::Val(to)) = (self, to) {
+            if let Some(value) = from.interpolate(to, t) {
+                return Some(Length::Val(value));
+            }
+        }
+        // mixed units are interpolated by expression: calc(from * (1 - t) + to * t)
+        let from = CalcExpr::Mul(
+            Box::new(self.to_expr()?),
+            Box::new(CalcExpr::Number(1. - t)),
+        );
+        let to = CalcExpr::Mul(Box::new(to.to_expr()?), Box::new(CalcExpr::Number(t)));
+        Some(Length::Calc(CalcExpr::Add(Box::new(from), Box::new(to))))
+    }
+}
+
+/// The edges of the box described by the [`$rect`](super::parse::RectParser)
+/// shorthand with [`Length`] values.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct LengthRect {
+    pub left: Length,
+    pub right: Length,
+    pub top: Length,
+    pub bottom: Length,
+}
+
+impl LengthRect {
+    pub fn new(left: Length, right: Length, top: Length, bottom: Length) -> LengthRect {
+        LengthRect {
+            left,
+            right,
+            top,
+            bottom,
+        }
+    }
+
+    pub fn all(value: Length) -> LengthRect {
+        LengthRect::new(value.clone(), value.clone(), value.clone(), value)
+    }
+
+    pub fn parse(tokens: &[StylePropertyToken]) -> Result<LengthRect, ElementsError> {
+        match tokens {
+            [all] => Length::from_token(all).map(LengthRect::all),
+            [top_bottom, left_right] => {
+                let top_bottom = Length::from_token(top_bottom)?;
+                let left_right = Length::from_token(left_right)?;
+                Ok(LengthRect::new(
+                    left_right.clone(),
+                    left_right,
+                    top_bottom.clone(),
+                    top_bottom,
+                ))
+            }
+            [top, left_right, bottom] => {
+                let top = Length::from_token(top)?;
+                let left_right = Length::from_token(left_right)?;
+                let bottom = Length::from_token(bottom)?;
+                Ok(LengthRect::new(left_right.clone(), left_right, top, bottom))
+            }
+            [top, right, bottom, left] => Ok(LengthRect::new(
+                Length::from_token(left)?,
+                Length::from_token(right)?,
+                Length::from_token(top)?,
+                Length::from_token(bottom)?,
+            )),
+            tokens => Err(ElementsError::InvalidPropertyValue(format!(
+                "Can't extract rect from `{}`",
+                source(tokens)
+            ))),
+        }
+    }
+}
+
+impl From<UiRect> for LengthRect {
+    fn from(rect: UiRect) -> Self {
+        LengthRect::new(
+            rect.left.into(),
+            rect.right.into(),
+            rect.top.into(),
+            rect.bottom.into(),
+        )
+    }
+}
+
+impl ToRectMap for LengthRect {
+    fn to_rect_map(&self, prefix: &str) -> HashMap<Tag, PropertyValue> {
+        let mut props = HashMap::default();
+        for (edge, value) in [
+            ("left", &self.left),
+            ("right", &self.right),
+            ("top", &self.top),
+            ("bottom", &self.bottom),
+        ] {
+            props.insert(
+                Tag::new(prefix.to_string() + edge),
+                PropertyValue::new(value.clone()),
+            );
+        }
+        props
+    }
+}
+
+/// The fields of [`Style`] the length properties are applied to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LengthField {
+    Left,
+    Right,
+    Top,
+    Bottom,
+    Width,
+    Height,
+    MinWidth,
+    MinHeight,
+    MaxWidth,
+    MaxHeight,
+    FlexBasis,
+    ColumnGap,
+    RowGap,
+    MarginLeft,
+    MarginRight,
+    MarginTop,
+    MarginBottom,
+    PaddingLeft,
+    PaddingRight,
+    PaddingTop,
+    PaddingBottom,
+    BorderLeft,
+    BorderRight,
+    BorderTop,
+    BorderBottom,
+}
+
+impl LengthField {
+    pub fn val(&self, style: &Style) -> Val {
+        match self {
+            LengthField::Left => style.left,
+            LengthField::Right => style.right,
+            LengthField::Top => style.top,
+            LengthField::Bottom => style.bottom,
+            LengthField::Width => style.width,
+            LengthField::Height => style.height,
+            LengthField::MinWidth => style.min_width,
+            LengthField::MinHeight => style.min_height,
+            LengthField::MaxWidth => style.max_width,
+            LengthField::MaxHeight => style.max_height,
+            LengthField::FlexBasis => style.flex_basis,
+            LengthField::ColumnGap => style.column_gap,
+            LengthField::RowGap => style.row_gap,
+            LengthField::MarginLeft => style.margin.left,
+            LengthField::MarginRight => style.margin.right,
+            LengthField::MarginTop => style.margin.top,
+            LengthField::MarginBottom => style.margin.bottom,
+            LengthField::PaddingLeft => style.padding.left,
+            LengthField::PaddingRight => style.padding.right,
+            LengthField::PaddingTop => style.padding.top,
+            LengthField::PaddingBottom => style.padding.bottom,
+            LengthField::BorderLeft => style.border.left,
+            LengthField::BorderRight => style.border.right,
+            LengthField::BorderTop => style.border.top,
+            LengthField::BorderBottom => style.border.bottom,
+        }
+    }
+
+    pub fn val_mut<'a>(&self, style: &'a mut Style) -> &'a mut Val {
+        match self {
+            LengthField::Left => &mut style.left,
+            LengthField::Right => &mut style.right,
+            LengthField::Top => &mut style.top,
+            LengthField::Bottom => &mut style.bottom,
+            LengthField::Width => &mut style.width,
+            LengthField::Height => &mut style.height,
+            LengthField::MinWidth => &mut style.min_width,
+            LengthField::MinHeight => &mut style.min_height,
+            LengthField::MaxWidth => &mut style.max_width,
+            LengthField::MaxHeight => &mut style.max_height,
+            LengthField::FlexBasis => &mut style.flex_basis,
+            LengthField::ColumnGap => &mut style.column_gap,
+            LengthField::RowGap => &mut style.row_gap,
+            LengthField::MarginLeft => &mut style.margin.left,
+            LengthField::MarginRight => &mut style.margin.right,
+            LengthField::MarginTop => &mut style.margin.top,
+            LengthField::MarginBottom => &mut style.margin.bottom,
+            LengthField::PaddingLeft => &mut style.padding.left,
+            LengthField::PaddingRight => &mut style.padding.right,
+            LengthField::PaddingTop => &mut style.padding.top,
+            LengthField::PaddingBottom => &mut style.padding.bottom,
+            LengthField::BorderLeft => &mut style.border.left,
+            LengthField::BorderRight => &mut style.border.right,
+            LengthField::BorderTop => &mut style.border.top,
+            LengthField::BorderBottom => &mut style.border.bottom,
+        }
+    }
+
+    /// Returns the size percentages are resolved against: gaps use the size
+    /// of the element itself, vertical offsets and sizes use the height of the
+    /// parent, the rest (including vertical margins and paddings, like in CSS)
+    /// use the width of the parent.
+    pub fn basis(&self, own: Vec2, parent: Vec2) -> f32 {
+        match self {
+            LengthField::ColumnGap => own.x,
+            LengthField::RowGap => own.y,
+            LengthField::Top
+            | LengthField::Bottom
+            | LengthField::Height
+            | LengthField::MinHeight
+            | LengthField::MaxHeight => parent.y,
+            _ => parent.x,
+        }
+    }
+}
+
+/// The math expressions of the length properties applied to the element.
+/// The expressions are evaluated against the node sizes of the previous
+/// layout, the results are written into [`Style`] as `Val::Px`.
+#[derive(Component, Default, Debug)]
+pub struct CalcLengths(HashMap<LengthField, CalcExpr>);
+
+impl CalcLengths {
+    pub fn get(&self, field: LengthField) -> Option<&CalcExpr> {
+        self.0.get(&field)
+    }
+}
+
+/// Returns the content box of the node: the padding and the border are
+/// subtracted from the border box computed by the layout. Their percentages
+/// are resolved against the `basis` width like in CSS.
+fn content_size(size: Vec2, style: &Style, basis: f32, window: Vec2) -> Vec2 {
+    let resolve = |val: Val| match val {
+        Val::Px(px) => px,
+        Val::Percent(percent) => basis * percent / 100.,
+        Val::Vw(vw) => window.x * vw / 100.,
+        Val::Vh(vh) => window.y * vh / 100.,
+        Val::VMin(vmin) => window.min_element() * vmin / 100.,
+        Val::VMax(vmax) => window.max_element() * vmax / 100.,
+        Val::Auto => 0.,
+    };
+    let horizontal = [
+        style.padding.left,
+        style.padding.right,
+        style.border.left,
+        style.border.right,
+    ];
+    let vertical = [
+        style.padding.top,
+        style.padding.bottom,
+        style.border.top,
+        style.border.bottom,
+    ];
+    let edges = Vec2::new(
+        horizontal.into_iter().map(resolve).sum(),
+        vertical.into_iter().map(resolve).sum(),
+    );
+    (size - edges).max(Vec2::ZERO)
+}
+
+/// Re-evaluates the math expressions of the length properties right before
+/// the layout. The expressions use the node sizes of the previous layout, so
+/// the result follows the change of the parent size one frame later. Percentages
+/// are resolved against the content box of the parent, root nodes resolve them
+/// against the window size. The style is changed only when the result changes.
+pub(crate) fn evaluate_lengths(
+    elements: Query<(Entity, &CalcLengths, &Node, Option<&Parent>)>,
+    mut styles: ParamSet<(
+        Query<&mut Style, With<CalcLengths>>,
+        Query<(&Node, &Style, Option<&Parent>)>,
+    )>,
+    media: Res<Media>,
+    ui_scale: Res<UiScale>,
+) {
+    let scale = ui_scale.scale as f32;
+    let window = Vec2::new(media.width, media.height);
+    let values = {
+        let nodes = styles.p1();
+        // the content box of the parent, the percentages of its paddings
+        // are resolved against the width of the grandparent
+        let content = |parent: Entity| {
+            let (node, style, grandparent) = nodes.get(parent).ok()?;
+            let basis = grandparent
+                .and_then(|grandparent| nodes.get(grandparent.get()).ok())
+                .map(|(node, ..)| node.size().x / scale)
+                .unwrap_or(window.x);
+            Some(content_size(node.size() / scale, style, basis, window))
+        };
+        elements
+            .iter()
+            .map(|(entity, calc, node, parent)| {
+                let own = node.size() / scale;
+                let parent = parent
+                    .and_then(|parent| content(parent.get()))
+                    .unwrap_or(window);
+                let lengths = calc
+                    .0
+                    .iter()
+                    .map(|(field, expr)| (*field, expr.eval(field.basis(own, parent))))
+                    .filter(|(_, value)| value.is_finite())
+                    .collect::<Vec<_>>();
+                (entity, lengths)
+            })
+            .collect::<Vec<_>>()
+    };
+    let mut styles = styles.p0();
+    for (entity, lengths) in values {
+        let Ok(mut style) = styles.get_mut(entity) else {
+            continue;
+        };
+        for (field, value) in lengths {
+            if !matches!(field.val(&style), Val::Px(px) if (px - value).abs() < 0.01) {
+                *field.val_mut(&mut style) = Val::Px(value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ess::StyleProperty;
+
+    fn length(value: &str) -> Result<Length, ElementsError> {
+        let prop = StyleProperty::try_from(value).unwrap();
+        Length::from_token(&prop[0])
+    }
+
+    fn eval(value: &str, basis: f32) -> f32 {
+        match length(value).unwrap() {
+            Length::Calc(expr) => expr.eval(basis),
+            Length::Val(val) => panic!("Expected expression, got {val:?}"),
+        }
+    }
+
+    #[test]
+    fn parse_math_functions() {
+        assert_eq!(length("25px"), Ok(Length::Val(Val::Px(25.))));
+        assert_eq!(
+            length("calc(10px * 2 + 5px)"),
+            Ok(Length::Val(Val::Px(25.)))
+        );
+        assert_eq!(eval("calc(100% - 240px)", 1000.), 760.);
+        assert_eq!(eval("calc((100% - 20px) / 2)", 420.), 200.);
+        assert_eq!(eval("calc(100% - 20px - 10px)", 100.), 70.);
+        assert_eq!(eval("calc(2 * 10%)", 100.), 20.);
+        assert_eq!(eval("max(200px, 30%)", 1000.), 300.);
+        assert_eq!(eval("max(200px, 30%)", 500.), 200.);
+        assert_eq!(eval("min(50%, calc(100% - 300px))", 400.), 100.);
+        assert_eq!(eval("clamp(100px, 50%, 300px)", 100.), 100.);
+        assert_eq!(eval("clamp(100px, 50%, 300px)", 400.), 200.);
+        assert_eq!(eval("clamp(100px, 50%, 300px)", 1000.), 300.);
+    }
+
+    #[test]
+    fn reject_invalid_math_functions() {
+        assert!(length("calc(10px * 5px)").is_err());
+        assert!(length("calc(10px / 5%)").is_err());
+        assert!(length("calc(10px + 2)").is_err());
+        assert!(length("calc(2 * 3)").is_err());
+        assert!(length("calc(10px +)").is_err());
+        assert!(length("clamp(10px, 50%)").is_err());
+        assert!(length("calc(auto - 10px)").is_err());
+    }
+
+    #[test]
+    fn interpolate_lengths() {
+        let from = Length::Val(Val::Px(100.));
+        let to = length("calc(50% + 20px)").unwrap();
+        let Some(Length::Calc(expr)) = from.interpolate(&to, 0.25) else {
+            panic!("Expected expression");
+        };
+        assert_eq!(expr.eval(200.), 100. * 0.75 + 120. * 0.25);
+        assert_eq!(
+            from.interpolate(&Length::Val(Val::Px(200.)), 0.5),
+            Some(Length::Val(Val::Px(150.)))
+        );
+        let Some(Length::Calc(expr)) = from.interpolate(&Length::Val(Val::Percent(100.)), 0.5)
+        else {
+            panic!("Expected expression");
+        };
+        assert_eq!(expr.eval(300.), 200.);
+        assert_eq!(from.interpolate(&Length::Val(Val::Auto), 0.5), None);
+    }
+
+    #[test]
+    fn parse_length_rect() {
+        let prop = StyleProperty::try_from("10px calc(50% - 10px)").unwrap();
+        let rect = LengthRect::parse(&prop).unwrap();
+        assert_eq!(rect.top, Length::Val(Val::Px(10.)));
+        assert_eq!(rect.bottom, Length::Val(Val::Px(10.)));
+        assert_eq!(rect.left, rect.right);
+        assert!(matches!(rect.left, Length::Calc(_)));
+    }
+
+    #[test]
+    fn resolve_against_content_box() {
+        let style = Style {
+            padding: UiRect::all(Val::Px(10.)),
+            border: UiRect::new(Val::Percent(5.), Val::Px(0.), Val::Px(2.), Val::Px(2.)),
+            ..default()
+        };
+        let window = Vec2::new(800., 600.);
+        let content = content_size(Vec2::new(200., 100.), &style, 400., window);
+        assert_eq!(content, Vec2::new(160., 76.));
+        assert_eq!(
+            content_size(Vec2::new(10., 10.), &style, 400., window),
+            Vec2::ZERO
+        );
+    }
+}
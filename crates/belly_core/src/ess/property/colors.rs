@@ -1,5 +1,6 @@
 use bevy::prelude::Color;
 
+use super::{StyleProperty, StylePropertyFunction, StylePropertyToken};
 use crate::ElementsError;
 
 pub trait ColorFromHexExtension {
@@ -31,6 +32,209 @@ impl ColorFromHexExtension for Color {
     }
 }
 
+/// Color helpers matching the color functions of stylesheets.
+pub trait ColorFunctionsExtension {
+    /// Parses any color accepted by stylesheets: `red`, `#ff0000`,
+    /// `rgb(255 0 0)`, `lighten(#ff0000, 10%)`, etc. Returns white
+    /// if the color can't be parsed.
+    fn from_css<T: AsRef<str>>(color: T) -> Color {
+        Self::try_from_css(color).unwrap_or(Color::WHITE)
+    }
+    fn try_from_css<T: AsRef<str>>(color: T) -> Result<Color, String> {
+        let color = color.as_ref();
+        StyleProperty::try_from(color)
+            .and_then(|prop| match prop.as_slice() {
+                [token] => parse_color(token),
+                _ => Err(ElementsError::InvalidPropertyValue(format!(
+                    "Can't parse color from '{color}'"
+                ))),
+            })
+            // hex colors without leading `#`
+            .or_else(|e| parse_hex_color(color.trim()).map_err(|_| e))
+            .map_err(|e| format!("{e}"))
+    }
+    /// Mixes the color with `other` in sRGB space, `weight` is the
+    /// proportion of `self` in the result (from `0.0` to `1.0`).
+    fn mix(&self, other: Color, weight: f32) -> Color;
+    /// Increases the HSL lightness of the color by `amount` (from `0.0` to `1.0`).
+    fn lighten(&self, amount: f32) -> Color;
+    /// Decreases the HSL lightness of the color by `amount` (from `0.0` to `1.0`).
+    fn darken(&self, amount: f32) -> Color;
+    /// Returns the same color with the alpha replaced by `alpha`.
+    fn with_alpha(&self, alpha: f32) -> Color;
+}
+
+impl ColorFunctionsExtension for Color {
+    fn mix(&self, other: Color, weight: f32) -> Color {
+        let weight = weight.clamp(0., 1.);
+        let [r1, g1, b1, a1] = self.as_rgba_f32();
+        let [r2, g2, b2, a2] = other.as_rgba_f32();
+        let mix = |a: f32, b: f32| a * weight + b * (1. - weight);
+        Color::rgba(mix(r1, r2), mix(g1, g2), mix(b1, b2), mix(a1, a2))
+    }
+    fn lighten(&self, amount: f32) -> Color {
+        let Color::Hsla {
+            hue,
+            saturation,
+            lightness,
+            alpha,
+        } = self.as_hsla()
+        else {
+            return *self;
+        };
+        let lightness = (lightness + amount).clamp(0., 1.);
+        Color::hsla(hue, saturation, lightness, alpha).as_rgba()
+    }
+    fn darken(&self, amount: f32) -> Color {
+        self.lighten(-amount)
+    }
+    fn with_alpha(&self, alpha: f32) -> Color {
+        self.as_rgba().with_a(alpha.clamp(0., 1.))
+    }
+}
+
+/// Parses the single color token: the color name, the hex color
+/// or the color function.
+pub(super) fn parse_color(token: &StylePropertyToken) -> Result<Color, ElementsError> {
+    match token {
+        StylePropertyToken::Identifier(name) => parse_named_color(name.as_str()).ok_or_else(|| {
+            ElementsError::InvalidPropertyValue(format!("Unknown color name `{name}`"))
+        }),
+        StylePropertyToken::Hash(hash) => parse_hex_color(hash.as_str()),
+        StylePropertyToken::Function(func) => parse_color_function(func),
+        token => Err(ElementsError::InvalidPropertyValue(format!(
+            "Expected $color, got `{}`",
+            token.to_string()
+        ))),
+    }
+}
+
+/// Parses `rgb()`, `rgba()`, `hsl()`, `hsla()`, `mix()`, `lighten()`,
+/// `darken()` and `alpha()` color functions.
+fn parse_color_function(func: &StylePropertyFunction) -> Result<Color, ElementsError> {
+    let name = func.name.as_str();
+    let args = function_args(func);
+    let invalid = || {
+        ElementsError::InvalidPropertyValue(format!(
+            "Invalid arguments of `{}`",
+            StylePropertyToken::Function(func.clone()).to_string()
+        ))
+    };
+    match name {
+        "rgb" | "rgba" | "hsl" | "hsla" => {
+            let slash = args.iter().position(|t| **t == StylePropertyToken::Slash);
+            let (channels, alpha) = match slash {
+                Some(idx) if idx + 2 == args.len() => (&args[..idx], Some(args[idx + 1])),
+                Some(_) => return Err(invalid()),
+                None if args.len() == 4 => (&args[..3], Some(args[3])),
+                None => (&args[..], None),
+            };
+            let [c1, c2, c3] = channels else {
+                return Err(invalid());
+            };
+            let alpha = alpha.map(fraction).transpose()?.unwrap_or(1.).clamp(0., 1.);
+            if name.starts_with("rgb") {
+                Ok(Color::rgba(
+                    rgb_channel(c1)?,
+                    rgb_channel(c2)?,
+                    rgb_channel(c3)?,
+                    alpha,
+                ))
+            } else {
+                Ok(Color::hsla(hue(c1)?, percent(c2)?, percent(c3)?, alpha).as_rgba())
+            }
+        }
+        "mix" => match args.as_slice() {
+            [a, b] => Ok(parse_color(a)?.mix(parse_color(b)?, 0.5)),
+            [a, b, weight] => Ok(parse_color(a)?.mix(parse_color(b)?, fraction(weight)?)),
+            _ => Err(invalid()),
+        },
+        "lighten" | "darken" | "alpha" => {
+            let [color, amount] = args.as_slice() else {
+                return Err(invalid());
+            };
+            let color = parse_color(color)?;
+            let amount = fraction(amount)?;
+            Ok(match name {
+                "lighten" => color.lighten(amount),
+                "darken" => color.darken(amount),
+                _ => color.with_alpha(amount),
+            })
+        }
+        name => Err(ElementsError::InvalidPropertyValue(format!(
+            "Unknown color function `{name}()`"
+        ))),
+    }
+}
+
+/// Returns the arguments of the function as the flat list of tokens,
+/// so `rgb(1, 2, 3)` and `rgb(1 2 3)` look the same.
+fn function_args(func: &StylePropertyFunction) -> Vec<&StylePropertyToken> {
+    func.args
+        .iter()
+        .flat_map(|arg| match arg {
+            StylePropertyToken::Tokens(tokens) => tokens.iter().collect(),
+            token => vec![token],
+        })
+        .collect()
+}
+
+/// `0-255` number or percentage into `0.0-1.0`
+fn rgb_channel(token: &StylePropertyToken) -> Result<f32, ElementsError> {
+    match token {
+        StylePropertyToken::Number(value) => Ok((value.to_float() / 255.).clamp(0., 1.)),
+        StylePropertyToken::Percentage(value) => Ok((value.to_float() / 100.).clamp(0., 1.)),
+        token => Err(ElementsError::InvalidPropertyValue(format!(
+            "Expected color channel, got `{}`",
+            token.to_string()
+        ))),
+    }
+}
+
+/// Number (`0.5`) or percentage (`50%`) into `0.0-1.0`
+fn fraction(token: &StylePropertyToken) -> Result<f32, ElementsError> {
+    match token {
+        StylePropertyToken::Number(value) => Ok(value.to_float()),
+        StylePropertyToken::Percentage(value) => Ok(value.to_float() / 100.),
+        token => Err(ElementsError::InvalidPropertyValue(format!(
+            "Expected number or percentage, got `{}`",
+            token.to_string()
+        ))),
+    }
+}
+
+/// Saturation or lightness: percentage or number of percents into `0.0-1.0`
+fn percent(token: &StylePropertyToken) -> Result<f32, ElementsError> {
+    match token {
+        StylePropertyToken::Number(value) | StylePropertyToken::Percentage(value) => {
+            Ok((value.to_float() / 100.).clamp(0., 1.))
+        }
+        token => Err(ElementsError::InvalidPropertyValue(format!(
+            "Expected percentage, got `{}`",
+            token.to_string()
+        ))),
+    }
+}
+
+/// Hue in degrees: `210`, `210deg`, `0.5turn`, `3.14rad`
+fn hue(token: &StylePropertyToken) -> Result<f32, ElementsError> {
+    let degrees = match token {
+        StylePropertyToken::Number(value) => value.to_float(),
+        StylePropertyToken::Dimension(value, unit) if unit == "deg" => value.to_float(),
+        StylePropertyToken::Dimension(value, unit) if unit == "turn" => value.to_float() * 360.,
+        StylePropertyToken::Dimension(value, unit) if unit == "rad" => {
+            value.to_float().to_degrees()
+        }
+        token => {
+            return Err(ElementsError::InvalidPropertyValue(format!(
+                "Expected hue, got `{}`",
+                token.to_string()
+            )))
+        }
+    };
+    Ok(degrees.rem_euclid(360.))
+}
+
 pub(super) fn parse_hex_color(hex: &str) -> Result<Color, ElementsError> {
     let color = cssparser::Color::parse_hash(hex.as_bytes()).map_err(|_| {
         ElementsError::InvalidPropertyValue(format!("Can't parse color from '{hex}'"))
@@ -216,3 +420,39 @@ pub(super) fn parse_named_color(name: &str) -> Option<Color> {
         _ => None,
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn css(color: &str) -> [f32; 4] {
+        let [r, g, b, a] = Color::try_from_css(color).unwrap().as_rgba_f32();
+        [r, g, b, a].map(|c| (c * 1000.).round() / 1000.)
+    }
+
+    #[test]
+    fn parse_color_functions() {
+        assert_eq!(css("rgb(255, 0, 0)"), [1., 0., 0., 1.]);
+        assert_eq!(css("rgb(255 0 0 / 50%)"), [1., 0., 0., 0.5]);
+        assert_eq!(css("rgba(0, 100%, 0, 0.25)"), [0., 1., 0., 0.25]);
+        assert_eq!(css("hsl(0, 100%, 50%)"), [1., 0., 0., 1.]);
+        assert_eq!(css("hsl(120deg 100% 25%)"), [0., 0.5, 0., 1.]);
+        assert_eq!(css("hsla(0.5turn, 100%, 50%, 0.5)"), [0., 1., 1., 0.5]);
+        assert_eq!(css("mix(#ffffff, #000000)"), [0.5, 0.5, 0.5, 1.]);
+        assert_eq!(css("mix(white, black, 25%)"), [0.25, 0.25, 0.25, 1.]);
+        assert_eq!(css("lighten(hsl(0, 100%, 25%), 25%)"), [1., 0., 0., 1.]);
+        assert_eq!(css("darken(red, 0.25)"), [0.5, 0., 0., 1.]);
+        assert_eq!(css("alpha(rgb(255 0 0), 50%)"), [1., 0., 0., 0.5]);
+        assert_eq!(css("ff0000"), [1., 0., 0., 1.]);
+    }
+
+    #[test]
+    fn reject_invalid_color_functions() {
+        assert!(Color::try_from_css("rgb(255, 0)").is_err());
+        assert!(Color::try_from_css("rgb(255 0 0 /)").is_err());
+        assert!(Color::try_from_css("hsl(red, 100%, 50%)").is_err());
+        assert!(Color::try_from_css("lighten(red)").is_err());
+        assert!(Color::try_from_css("shade(red, 10%)").is_err());
+        assert!(Color::try_from_css("red blue").is_err());
+    }
+}
@@ -0,0 +1,408 @@
+use std::ops::Deref;
+
+use super::style::StylePropertyTokens;
+use super::{PropertyExtractor, PropertyValue, StyleProperty, StylePropertyFunction};
+use super::StylePropertyToken;
+use crate::{
+    element::{Element, Elements},
+    eml::Variant,
+    ess::{ElementsBranch, EmlNode, StyleRule},
+    ElementsError,
+};
+use bevy::{prelude::*, utils::HashMap};
+use smallvec::SmallVec;
+use tagstr::*;
+
+/// Limits the depth of nested `var()` substitutions, so the custom
+/// properties referring each other (`--a: var(--b); --b: var(--a)`)
+/// can't hang the resolving.
+const MAX_SUBSTITUTION_DEPTH: usize = 32;
+
+/// Custom properties visible to the element, by name
+pub type Variables<'a> = HashMap<Tag, &'a StyleProperty>;
+
+/// Returns `true` if `name` is a custom property name: `--accent-color`
+pub fn is_custom_property(name: Tag) -> bool {
+    name.as_str().starts_with("--")
+}
+
+/// Creates the custom property value to be inserted into [`Element::styles`]
+/// at runtime:
+/// ```rust
+/// # use bevy::prelude::*;
+/// # use belly_core::prelude::*;
+/// # use belly_core::AsTag;
+/// fn system(mut elements: Query<&mut Element>) {
+///     for mut element in elements.iter_mut() {
+///         element.styles.insert("--accent".as_tag(), custom_property("#ff7f00"));
+///     }
+/// }
+/// ```
+pub fn custom_property(value: &str) -> PropertyValue {
+    match StyleProperty::try_from(value) {
+        Ok(value) => PropertyValue::new(CustomProperty(value)),
+        Err(e) => {
+            error!("Error parsing custom property value '{value}': {e}");
+            PropertyValue::new(CustomProperty(Default::default()))
+        }
+    }
+}
+
+/// The value of the custom property (`--accent: #ff7f00`). It is
+/// inherited by the element children and substituted into the
+/// other properties with `var(--accent)`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CustomProperty(pub StyleProperty);
+
+impl CustomProperty {
+    pub(crate) fn transform(variant: Variant) -> Result<PropertyValue, ElementsError> {
+        match variant {
+            Variant::Style(p) => Ok(PropertyValue::new(CustomProperty(p))),
+            Variant::String(s) => {
+                StyleProperty::try_from(s).map(|p| PropertyValue::new(CustomProperty(p)))
+            }
+            Variant::Property(p) if p.is::<CustomProperty>() => Ok(p),
+            variant => Err(ElementsError::InvalidPropertyValue(format!(
+                "Don't know how to transform {:?} into custom property",
+                variant
+            ))),
+        }
+    }
+}
+
+/// The property value which refers custom properties with
+/// `var(--name, fallback)`. It can't be transformed while parsing,
+/// so it is stored as is and resolved against the element tree
+/// when the property is applied.
+#[derive(Debug, Clone)]
+pub struct UnresolvedProperty {
+    /// Name of the property, may be the name of the compound property
+    pub name: Tag,
+    pub compound: bool,
+    pub value: StyleProperty,
+}
+
+impl UnresolvedProperty {
+    /// Returns the unresolved property if the `variant` contains `var()` references
+    pub(crate) fn defer(name: Tag, compound: bool, variant: &Variant) -> Option<UnresolvedProperty> {
+        let value = match variant {
+            Variant::Style(p) => p.clone(),
+            Variant::String(s) => StyleProperty::try_from(s).ok()?,
+            _ => return None,
+        };
+        if value.iter().any(has_variables) {
+            Some(UnresolvedProperty {
+                name,
+                compound,
+                value,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Substitutes `variables` and transforms the result into the `property` value.
+    /// Returns `None` if the compound property doesn't define the `property`.
+    pub(crate) fn resolve(
+        &self,
+        property: Tag,
+        variables: &Variables,
+        transform: impl Fn(Variant) -> Result<PropertyValue, ElementsError>,
+        extractor: &PropertyExtractor,
+    ) -> Result<Option<PropertyValue>, ElementsError> {
+        let value = substitute(&self.value, variables)?;
+        if self.compound {
+            Ok(extractor
+                .extract(self.name, Variant::style(value))?
+                .remove(&property))
+        } else {
+            transform(Variant::style(value)).map(Some)
+        }
+    }
+}
+
+/// The property value found while applying properties: borrowed from the
+/// rule or element styles, or owned when resolved from [`UnresolvedProperty`]
+#[derive(Debug)]
+pub(crate) enum ResolvedProperty<'a> {
+    Borrowed(&'a PropertyValue),
+    Owned(PropertyValue),
+}
+
+impl<'a> Deref for ResolvedProperty<'a> {
+    type Target = PropertyValue;
+    fn deref(&self) -> &Self::Target {
+        match self {
+            ResolvedProperty::Borrowed(value) => value,
+            ResolvedProperty::Owned(value) => value,
+        }
+    }
+}
+
+/// Finds the value of the `name` property in `properties`, resolving
+/// `var()` references of the property itself or of the compound property
+pub(crate) fn find_property<'a>(
+    name: Tag,
+    properties: &'a HashMap<Tag, PropertyValue>,
+    resolve: impl Fn(&UnresolvedProperty) -> Option<PropertyValue>,
+) -> Option<ResolvedProperty<'a>> {
+    if let Some(value) = properties.get(&name) {
+        return match value.downcast_ref::<UnresolvedProperty>() {
+            Some(unresolved) => resolve(unresolved).map(ResolvedProperty::Owned),
+            None => Some(ResolvedProperty::Borrowed(value)),
+        };
+    }
+    properties
+        .values()
+        .filter_map(|value| value.downcast_ref::<UnresolvedProperty>())
+        .filter(|unresolved| unresolved.compound)
+        .find_map(resolve)
+        .map(ResolvedProperty::Owned)
+}
+
+/// Returns `true` if the rule defines custom properties
+pub(crate) fn has_custom_properties(rule: &StyleRule) -> bool {
+    rule.properties.keys().any(|name| is_custom_property(*name))
+}
+
+/// Collects the custom properties visible to the tail of the `branch`:
+/// every element inherits variables of its parent and overrides them with
+/// the ones defined by the matched `rules` and by its own styles.
+pub(crate) fn collect_variables<'a>(
+    rules: &[&'a StyleRule],
+    branch: &ElementsBranch<'a>,
+) -> Variables<'a> {
+    let mut variables = Variables::default();
+    for idx in (0..branch.len()).rev() {
+        let node = branch.node(idx);
+        let mut matched: Vec<_> = rules
+            .iter()
+            .filter_map(|rule| node.fits(&rule.selector.tail()).map(|depth| (*rule, depth)))
+            .collect();
+        // the most specific rules go last and override the rest
+        matched.sort_by_key(|(rule, depth)| (rule.selector.weight, -(*depth as i16)));
+        for (rule, _) in matched {
+            insert_variables(&mut variables, &rule.properties);
+        }
+        insert_variables(&mut variables, &node.element().styles);
+    }
+    variables
+}
+
+fn insert_variables<'a>(variables: &mut Variables<'a>, properties: &'a HashMap<Tag, PropertyValue>) {
+    for (name, value) in properties.iter() {
+        if let Some(CustomProperty(value)) = value.downcast_ref::<CustomProperty>() {
+            variables.insert(*name, value);
+        }
+    }
+}
+
+fn has_variables(token: &StylePropertyToken) -> bool {
+    match token {
+        StylePropertyToken::Function(f) => f.name == "var" || f.args.iter().any(has_variables),
+        StylePropertyToken::Tokens(tokens) => tokens.iter().any(has_variables),
+        _ => false,
+    }
+}
+
+/// Replaces `var()` functions in the `value` with the values of `variables`
+pub(crate) fn substitute(
+    value: &StyleProperty,
+    variables: &Variables,
+) -> Result<StyleProperty, ElementsError> {
+    let mut tokens = StylePropertyTokens::new();
+    for token in value.iter() {
+        substitute_token(token, variables, 0, &mut tokens)?;
+    }
+    Ok(StyleProperty(tokens))
+}
+
+fn substitute_token(
+    token: &StylePropertyToken,
+    variables: &Variables,
+    depth: usize,
+    output: &mut StylePropertyTokens,
+) -> Result<(), ElementsError> {
+    if depth > MAX_SUBSTITUTION_DEPTH {
+        return Err(ElementsError::InvalidPropertyValue(format!(
+            "Custom properties refer each other in cycle"
+        )));
+    }
+    match token {
+        StylePropertyToken::Function(f) if f.name == "var" => {
+            let Some(StylePropertyToken::Identifier(name)) = f.args.first() else {
+                return Err(ElementsError::InvalidPropertyValue(format!(
+                    "Expected custom property name as the first argument of `{}`",
+                    token.to_string()
+                )));
+            };
+            if let Some(value) = variables.get(&name.as_tag()) {
+                for token in value.iter() {
+                    substitute_token(token, variables, depth + 1, output)?;
+                }
+            } else if f.args.len() > 1 {
+                // the fallback is everything after the first comma
+                for (idx, arg) in f.args.iter().skip(1).enumerate() {
+                    if idx > 0 {
+                        output.push(StylePropertyToken::Comma);
+                    }
+                    substitute_arg(arg, variables, depth + 1, output)?;
+                }
+            } else {
+                return Err(ElementsError::InvalidPropertyValue(format!(
+                    "Custom property {name} is not defined"
+                )));
+            }
+        }
+        StylePropertyToken::Function(f) => {
+            let mut args = vec![];
+            for arg in f.args.iter() {
+                let mut tokens = StylePropertyTokens::new();
+                substitute_arg(arg, variables, depth, &mut tokens)?;
+                // substituted values may contain several comma-separated arguments
+                for arg in tokens.split(|t| *t == StylePropertyToken::Comma) {
+                    let mut arg: SmallVec<[StylePropertyToken; 8]> = arg.iter().cloned().collect();
+                    if arg.len() == 1 {
+                        args.push(arg.pop().unwrap());
+                    } else {
+                        args.push(StylePropertyToken::Tokens(arg.into_vec()));
+                    }
+                }
+            }
+            output.push(StylePropertyToken::Function(StylePropertyFunction {
+                name: f.name.clone(),
+                args,
+            }));
+        }
+        StylePropertyToken::Tokens(tokens) => {
+            let mut substituted = StylePropertyTokens::new();
+            for token in tokens.iter() {
+                substitute_token(token, variables, depth, &mut substituted)?;
+            }
+            output.push(StylePropertyToken::Tokens(substituted.into_vec()));
+        }
+        token => output.push(token.clone()),
+    }
+    Ok(())
+}
+
+/// Substitutes the function argument, flattening multi-token arguments
+fn substitute_arg(
+    arg: &StylePropertyToken,
+    variables: &Variables,
+    depth: usize,
+    output: &mut StylePropertyTokens,
+) -> Result<(), ElementsError> {
+    match arg {
+        StylePropertyToken::Tokens(tokens) => {
+            for token in tokens.iter() {
+                substitute_token(token, variables, depth, output)?;
+            }
+            Ok(())
+        }
+        arg => substitute_token(arg, variables, depth, output),
+    }
+}
+
+/// Invalidates the elements which custom properties were changed at
+/// runtime (via [`Element::styles`]), so their children re-apply the
+/// properties referring these custom properties with `var()`.
+pub fn invalidate_custom_properties(
+    changed: Query<(Entity, Ref<Element>), Changed<Element>>,
+    mut removed: RemovedComponents<Element>,
+    mut known: Local<HashMap<Entity, HashMap<Tag, StyleProperty>>>,
+    mut elements: Elements,
+) {
+    for entity in removed.iter() {
+        known.remove(&entity);
+    }
+    for (entity, element) in changed.iter() {
+        let custom: HashMap<Tag, StyleProperty> = element
+            .styles
+            .iter()
+            .filter_map(|(name, value)| {
+                value
+                    .downcast_ref::<CustomProperty>()
+                    .map(|custom| (*name, custom.0.clone()))
+            })
+            .collect();
+        let unchanged = match known.get(&entity) {
+            Some(previous) => previous == &custom,
+            None => custom.is_empty(),
+        };
+        if custom.is_empty() {
+            known.remove(&entity);
+        } else {
+            known.insert(entity, custom);
+        }
+        // new elements are styled with their children anyway
+        if !unchanged && !element.is_added() {
+            elements.invalidate(entity);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn property(value: &str) -> StyleProperty {
+        StyleProperty::try_from(value).unwrap()
+    }
+
+    #[test]
+    fn substitute_variables() {
+        let accent = property("#ff7f00");
+        let gap = property("5px 10px");
+        let nested = property("var(--gap)");
+        let mut variables = Variables::default();
+        variables.insert("--accent".as_tag(), &accent);
+        variables.insert("--gap".as_tag(), &gap);
+        variables.insert("--nested".as_tag(), &nested);
+
+        let cases = &[
+            ("var(--accent)", "#ff7f00"),
+            ("1px solid var(--accent)", "1px solid #ff7f00"),
+            ("var(--gap)", "5px 10px"),
+            ("var(--nested) 3px", "5px 10px 3px"),
+            ("var(--missing, 2px)", "2px"),
+            ("var(--missing, var(--accent))", "#ff7f00"),
+            ("var(--accent, 2px)", "#ff7f00"),
+        ];
+        for (source, expected) in cases {
+            let substituted = substitute(&property(source), &variables).unwrap();
+            assert_eq!(substituted, property(expected), "Substituting '{}'", source);
+        }
+    }
+
+    #[test]
+    fn substitute_variables_in_functions() {
+        let channels = property("255, 127, 0");
+        let mut variables = Variables::default();
+        variables.insert("--channels".as_tag(), &channels);
+        let substituted = substitute(&property("rgb(var(--channels))"), &variables).unwrap();
+        assert_eq!(substituted, property("rgb(255, 127, 0)"));
+    }
+
+    #[test]
+    fn substitute_invalid_variables() {
+        let a = property("var(--b)");
+        let b = property("var(--a)");
+        let mut variables = Variables::default();
+        variables.insert("--a".as_tag(), &a);
+        variables.insert("--b".as_tag(), &b);
+        assert!(substitute(&property("var(--missing)"), &variables).is_err());
+        assert!(substitute(&property("var(--a)"), &variables).is_err());
+    }
+
+    #[test]
+    fn defer_properties_with_variables() {
+        let name = "color".as_tag();
+        let deferred = Variant::style(property("var(--accent)"));
+        assert!(UnresolvedProperty::defer(name, false, &deferred).is_some());
+        let deferred = Variant::String("rgb(var(--r), 0, 0)".to_string());
+        assert!(UnresolvedProperty::defer(name, false, &deferred).is_some());
+        let plain = Variant::style(property("#ff7f00"));
+        assert!(UnresolvedProperty::defer(name, false, &plain).is_none());
+    }
+}
@@ -1,3 +1,5 @@
+use super::animation::{AnimationDirection, FillMode};
+use super::text::{TextTransform, VerticalAlign, WhiteSpace};
 use super::StyleProperty;
 use super::StylePropertyMethods;
 use super::StylePropertyToken;
@@ -134,4 +136,48 @@ prop_to_enum! { GridAutoFlow,
     "column" => Column,
     "row-dense" => RowDense,
     "column-dense" => ColumnDense,
-}
\ No newline at end of file
+}
+
+prop_to_enum! { AnimationDirection,
+    "normal" => Normal,
+    "reverse" => Reverse,
+    "alternate" => Alternate,
+    "alternate-reverse" => AlternateReverse,
+}
+
+prop_to_enum! { FillMode,
+    "none" => None,
+    "forwards" => Forwards,
+    "backwards" => Backwards,
+    "both" => Both,
+}
+
+prop_to_enum! { TextAlignment,
+    "left" => Left,
+    "center" => Center,
+    "right" => Right,
+}
+
+prop_to_enum! { WhiteSpace,
+    "normal" => Normal,
+    "wrap" => Normal,
+    "nowrap" => NoWrap,
+}
+
+prop_to_enum! { VerticalAlign,
+    "top" => Top,
+    "middle" => Middle,
+    "bottom" => Bottom,
+}
+
+prop_to_enum! { TextTransform,
+    "none" => None,
+    "uppercase" => Uppercase,
+    "lowercase" => Lowercase,
+    "capitalize" => Capitalize,
+}
+
+prop_to_enum! { Visibility,
+    "visible" => Inherited,
+    "hidden" => Hidden,
+}
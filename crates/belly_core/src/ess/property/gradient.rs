@@ -0,0 +1,689 @@
+use super::{
+    colors,
+    parse::{self, length_val, resolve_val},
+    StyleProperty, StylePropertyFunction, StylePropertyToken,
+};
+use crate::ElementsError;
+use bevy::{
+    prelude::*,
+    render::{
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+        Extract,
+    },
+    ui::{ExtractedUiNode, ExtractedUiNodes, UiStack},
+    utils::{HashMap, HashSet},
+};
+
+/// The largest side of the generated gradient texture. Gradients are
+/// smooth, so the bigger nodes just stretch the texture.
+const MAX_TEXTURE_SIZE: f32 = 512.;
+const EPSILON: f32 = 0.0001;
+const PI: f32 = std::f32::consts::PI;
+
+/// The direction of the `linear-gradient()`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LinearDirection {
+    /// Clockwise angle in radians, `0` points to the top
+    Angle(f32),
+    /// The corner the gradient goes to: `-1` or `1` for the
+    /// left/right and top/bottom sides
+    Corner(Vec2),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RadialShape {
+    Circle,
+    Ellipse,
+}
+
+/// How far the last color stop of the `radial-gradient()` is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RadialExtent {
+    ClosestSide,
+    ClosestCorner,
+    FarthestSide,
+    FarthestCorner,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GradientKind {
+    Linear(LinearDirection),
+    Radial {
+        shape: RadialShape,
+        extent: RadialExtent,
+        center: (Val, Val),
+    },
+}
+
+/// The color of the gradient at the position along the gradient ray.
+/// Stops without the position are spread evenly between the neighbours.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorStop {
+    pub color: Color,
+    pub position: Option<Val>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gradient {
+    pub kind: GradientKind,
+    pub stops: Vec<ColorStop>,
+}
+
+impl Gradient {
+    /// Parses `none`, `linear-gradient()` or `radial-gradient()`
+    pub fn parse(prop: &StyleProperty) -> Result<Option<Gradient>, ElementsError> {
+        match prop.as_slice() {
+            [StylePropertyToken::Identifier(ident)] if ident == "none" => Ok(None),
+            [StylePropertyToken::Function(func)] => Gradient::parse_function(func).map(Some),
+            _ => Err(ElementsError::InvalidPropertyValue(format!(
+                "Expected none|$gradient, got `{}`",
+                prop.to_string()
+            ))),
+        }
+    }
+
+    fn parse_function(func: &StylePropertyFunction) -> Result<Gradient, ElementsError> {
+        let (kind, stops) = match (func.name.as_str(), func.args.as_slice()) {
+            ("linear-gradient", [first, rest @ ..]) => match parse_direction(arg_tokens(first))? {
+                Some(direction) => (GradientKind::Linear(direction), rest),
+                None => (
+                    GradientKind::Linear(LinearDirection::Angle(PI)),
+                    &func.args[..],
+                ),
+            },
+            ("radial-gradient", [first, rest @ ..]) => match parse_radial(arg_tokens(first))? {
+                Some(kind) => (kind, rest),
+                None => (
+                    GradientKind::Radial {
+                        shape: RadialShape::Ellipse,
+                        extent: RadialExtent::FarthestCorner,
+                        center: (Val::Percent(50.), Val::Percent(50.)),
+                    },
+                    &func.args[..],
+                ),
+            },
+            ("linear-gradient" | "radial-gradient", []) => {
+                return Err(ElementsError::InvalidPropertyValue(format!(
+                    "Expected color stops in `{}()`",
+                    func.name
+                )))
+            }
+            (name, _) => {
+                return Err(ElementsError::InvalidPropertyValue(format!(
+                    "Unknown gradient function `{name}()`"
+                )))
+            }
+        };
+        let mut result = Gradient {
+            kind,
+            stops: vec![],
+        };
+        for stop in stops {
+            match arg_tokens(stop) {
+                [color] => result.stops.push(ColorStop {
+                    color: colors::parse_color(color)?,
+                    position: None,
+                }),
+                [color, positions @ ..] if positions.len() <= 2 => {
+                    let color = colors::parse_color(color)?;
+                    for position in positions {
+                        result.stops.push(ColorStop {
+                            color,
+                            position: Some(length_val(position)?),
+                        });
+                    }
+                }
+                _ => {
+                    return Err(ElementsError::InvalidPropertyValue(format!(
+                        "Invalid color stop `{}`",
+                        stop.to_string()
+                    )))
+                }
+            }
+        }
+        if result.stops.len() < 2 {
+            return Err(ElementsError::InvalidPropertyValue(format!(
+                "Expected at least two color stops in `{}`",
+                StylePropertyToken::Function(func.clone()).to_string()
+            )));
+        }
+        Ok(result)
+    }
+
+    fn geometry(&self, size: Vec2, scale: f32) -> Geometry {
+        match self.kind {
+            GradientKind::Linear(direction) => {
+                let angle = match direction {
+                    LinearDirection::Angle(angle) => angle,
+                    // perpendicular to the diagonal between the other corners
+                    LinearDirection::Corner(corner) => {
+                        (corner.x * size.y).atan2(-corner.y * size.x)
+                    }
+                };
+                let direction = Vec2::new(angle.sin(), -angle.cos());
+                let length = (size.x * direction.x).abs() + (size.y * direction.y).abs();
+                Geometry::Linear {
+                    center: size * 0.5,
+                    direction,
+                    length: length.max(EPSILON),
+                }
+            }
+            GradientKind::Radial {
+                shape,
+                extent,
+                center: (x, y),
+            } => {
+                let center =
+                    Vec2::new(resolve_val(x, size.x, scale), resolve_val(y, size.y, scale));
+                let near = center.abs().min((size - center).abs());
+                let far = center.abs().max((size - center).abs());
+                let corners = [
+                    center,
+                    Vec2::new(size.x - center.x, center.y),
+                    Vec2::new(center.x, size.y - center.y),
+                    size - center,
+                ]
+                .map(|corner| corner.length());
+                let radii = match (shape, extent) {
+                    (RadialShape::Circle, RadialExtent::ClosestSide) => {
+                        Vec2::splat(near.min_element())
+                    }
+                    (RadialShape::Circle, RadialExtent::FarthestSide) => {
+                        Vec2::splat(far.max_element())
+                    }
+                    (RadialShape::Circle, RadialExtent::ClosestCorner) => {
+                        Vec2::splat(corners.into_iter().fold(f32::INFINITY, f32::min))
+                    }
+                    (RadialShape::Circle, RadialExtent::FarthestCorner) => {
+                        Vec2::splat(corners.into_iter().fold(0., f32::max))
+                    }
+                    (RadialShape::Ellipse, RadialExtent::ClosestSide) => near,
+                    (RadialShape::Ellipse, RadialExtent::FarthestSide) => far,
+                    (RadialShape::Ellipse, RadialExtent::ClosestCorner) => {
+                        near * std::f32::consts::SQRT_2
+                    }
+                    (RadialShape::Ellipse, RadialExtent::FarthestCorner) => {
+                        far * std::f32::consts::SQRT_2
+                    }
+                };
+                Geometry::Radial {
+                    center,
+                    radii: radii.max(Vec2::splat(EPSILON)),
+                }
+            }
+        }
+    }
+
+    /// Resolves the positions of the stops into the fractions of the
+    /// gradient ray and premultiplies the colors
+    fn resolve_stops(&self, length: f32, scale: f32) -> Vec<(f32, Vec4)> {
+        let mut positions: Vec<Option<f32>> = self
+            .stops
+            .iter()
+            .map(|stop| {
+                stop.position
+                    .map(|p| resolve_val(p, length, scale) / length)
+            })
+            .collect();
+        let last = positions.len() - 1;
+        positions[0] = positions[0].or(Some(0.));
+        positions[last] = positions[last].or(Some(1.));
+        // a stop can't be placed before the previous one
+        let mut max = f32::NEG_INFINITY;
+        for position in positions.iter_mut().flatten() {
+            max = max.max(*position);
+            *position = max;
+        }
+        let mut resolved = Vec::with_capacity(positions.len());
+        let mut from = 0;
+        for (idx, position) in positions.iter().enumerate() {
+            let Some(position) = position else {
+                continue;
+            };
+            let start = resolved.last().map_or(*position, |(p, _)| *p);
+            let steps = (idx - from) as f32;
+            for (step, _) in (from + 1..idx).enumerate() {
+                let t = (step + 1) as f32 / steps;
+                resolved.push((start + (position - start) * t, Vec4::ZERO));
+            }
+            resolved.push((*position, Vec4::ZERO));
+            from = idx;
+        }
+        for ((_, color), stop) in resolved.iter_mut().zip(self.stops.iter()) {
+            let [r, g, b, a] = stop.color.as_rgba_f32();
+            *color = Vec4::new(r * a, g * a, b * a, a);
+        }
+        resolved
+    }
+
+    /// Renders the gradient into the texture for the node of the `size`
+    pub fn render(&self, size: Vec2, scale: f32) -> Image {
+        let factor = (MAX_TEXTURE_SIZE / size.max_element()).min(1.);
+        let texture = (size * factor).ceil().max(Vec2::ONE).as_uvec2();
+        let geometry = self.geometry(size, scale);
+        let stops = self.resolve_stops(geometry.ray_length(), scale);
+        let mut data = Vec::with_capacity((texture.x * texture.y * 4) as usize);
+        for y in 0..texture.y {
+            for x in 0..texture.x {
+                let point = (Vec2::new(x as f32, y as f32) + 0.5) / texture.as_vec2() * size;
+                let color = sample(&stops, geometry.position(point));
+                let color = if color.w > 0. {
+                    (color.truncate() / color.w).extend(color.w)
+                } else {
+                    Vec4::ZERO
+                };
+                let color = (color.clamp(Vec4::ZERO, Vec4::ONE) * 255.).round();
+                data.extend([color.x as u8, color.y as u8, color.z as u8, color.w as u8]);
+            }
+        }
+        Image::new(
+            Extent3d {
+                width: texture.x,
+                height: texture.y,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            data,
+            TextureFormat::Rgba8UnormSrgb,
+        )
+    }
+
+    fn encode(&self, key: &mut Vec<u32>) {
+        let val = |key: &mut Vec<u32>, value: Val| match value {
+            Val::Px(px) => key.extend([0, px.to_bits()]),
+            Val::Percent(percent) => key.extend([1, percent.to_bits()]),
+            _ => key.push(2),
+        };
+        match self.kind {
+            GradientKind::Linear(LinearDirection::Angle(angle)) => key.extend([0, angle.to_bits()]),
+            GradientKind::Linear(LinearDirection::Corner(corner)) => {
+                key.extend([1, corner.x.to_bits(), corner.y.to_bits()])
+            }
+            GradientKind::Radial {
+                shape,
+                extent,
+                center,
+            } => {
+                key.extend([2, shape as u32, extent as u32]);
+                val(key, center.0);
+                val(key, center.1);
+            }
+        }
+        for stop in self.stops.iter() {
+            key.extend(stop.color.as_rgba_f32().map(f32::to_bits));
+            match stop.position {
+                Some(position) => val(key, position),
+                None => key.push(3),
+            }
+        }
+    }
+}
+
+/// Returns the tokens of the comma separated function argument
+fn arg_tokens(arg: &StylePropertyToken) -> &[StylePropertyToken] {
+    match arg {
+        StylePropertyToken::Tokens(tokens) => tokens.as_slice(),
+        token => std::slice::from_ref(token),
+    }
+}
+
+/// `$angle` or `to $side [$side]`, `None` if the argument is the color stop
+fn parse_direction(
+    tokens: &[StylePropertyToken],
+) -> Result<Option<LinearDirection>, ElementsError> {
+    let side = |token: &StylePropertyToken| match token {
+        StylePropertyToken::Identifier(ident) => match ident.as_str() {
+            "left" => Some(Vec2::new(-1., 0.)),
+            "right" => Some(Vec2::new(1., 0.)),
+            "top" => Some(Vec2::new(0., -1.)),
+            "bottom" => Some(Vec2::new(0., 1.)),
+            _ => None,
+        },
+        _ => None,
+    };
+    let invalid = || {
+        ElementsError::InvalidPropertyValue(format!(
+            "Invalid gradient direction `{}`",
+            tokens
+                .iter()
+                .map(|t| t.to_string())
+                .collect::<Vec<_>>()
+                .join(" ")
+        ))
+    };
+    match tokens {
+        [to, sides @ ..] if to.is_ident("to") => {
+            let sides = sides.iter().map(side).collect::<Option<Vec<_>>>();
+            match sides.as_deref() {
+                Some([side]) => Ok(Some(LinearDirection::Angle(side.x.atan2(-side.y)))),
+                Some([a, b]) if (*a + *b).abs() == Vec2::ONE => {
+                    Ok(Some(LinearDirection::Corner(*a + *b)))
+                }
+                _ => Err(invalid()),
+            }
+        }
+        [StylePropertyToken::Dimension(_, _) | StylePropertyToken::Number(_)] => {
+            parse::angle(&tokens[0]).map(|angle| Some(LinearDirection::Angle(angle)))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// `[circle|ellipse] [$extent] [at $position]`, `None` if the argument is the color stop
+fn parse_radial(tokens: &[StylePropertyToken]) -> Result<Option<GradientKind>, ElementsError> {
+    let mut shape = None;
+    let mut extent = None;
+    let mut center = (Val::Percent(50.), Val::Percent(50.));
+    for (idx, token) in tokens.iter().enumerate() {
+        let StylePropertyToken::Identifier(ident) = token else {
+            return Ok(None);
+        };
+        match ident.as_str() {
+            "circle" if shape.is_none() => shape = Some(RadialShape::Circle),
+            "ellipse" if shape.is_none() => shape = Some(RadialShape::Ellipse),
+            "closest-side" if extent.is_none() => extent = Some(RadialExtent::ClosestSide),
+            "closest-corner" if extent.is_none() => extent = Some(RadialExtent::ClosestCorner),
+            "farthest-side" if extent.is_none() => extent = Some(RadialExtent::FarthestSide),
+            "farthest-corner" if extent.is_none() => extent = Some(RadialExtent::FarthestCorner),
+            "at" => {
+                center = parse::position(&tokens[idx + 1..], "radial-gradient")?;
+                break;
+            }
+            _ if idx == 0 => return Ok(None),
+            ident => {
+                return Err(ElementsError::InvalidPropertyValue(format!(
+                    "Unexpected `{ident}` in radial-gradient"
+                )))
+            }
+        }
+    }
+    Ok(Some(GradientKind::Radial {
+        shape: shape.unwrap_or(RadialShape::Ellipse),
+        extent: extent.unwrap_or(RadialExtent::FarthestCorner),
+        center,
+    }))
+}
+
+enum Geometry {
+    Linear {
+        center: Vec2,
+        direction: Vec2,
+        length: f32,
+    },
+    Radial {
+        center: Vec2,
+        radii: Vec2,
+    },
+}
+
+impl Geometry {
+    /// The length of the gradient ray the stop lengths are relative to
+    fn ray_length(&self) -> f32 {
+        match self {
+            Geometry::Linear { length, .. } => *length,
+            Geometry::Radial { radii, .. } => radii.x,
+        }
+    }
+
+    /// The position of the point along the gradient ray: `0` for the
+    /// start of the ray, `1` for the end of the ray
+    fn position(&self, point: Vec2) -> f32 {
+        match self {
+            Geometry::Linear {
+                center,
+                direction,
+                length,
+            } => (point - *center).dot(*direction) / length + 0.5,
+            Geometry::Radial { center, radii } => ((point - *center) / *radii).length(),
+        }
+    }
+}
+
+fn sample(stops: &[(f32, Vec4)], t: f32) -> Vec4 {
+    let (first, last) = (stops[0], stops[stops.len() - 1]);
+    if t <= first.0 {
+        return first.1;
+    }
+    if t >= last.0 {
+        return last.1;
+    }
+    for segment in stops.windows(2) {
+        let ((from, from_color), (to, to_color)) = (segment[0], segment[1]);
+        if t >= from && t < to {
+            return from_color.lerp(to_color, (t - from) / (to - from));
+        }
+    }
+    last.1
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct GradientKey(Vec<u32>);
+
+impl GradientKey {
+    fn new(gradient: &Gradient, size: Vec2, scale: f32) -> GradientKey {
+        let mut key = vec![size.x.to_bits(), size.y.to_bits(), scale.to_bits()];
+        gradient.encode(&mut key);
+        GradientKey(key)
+    }
+}
+
+/// The gradient of the element background, drawn over the
+/// `background-color` and under the stylebox.
+#[derive(Component, Debug)]
+pub struct BackgroundGradient {
+    pub gradient: Gradient,
+    image: Handle<Image>,
+    key: Option<GradientKey>,
+}
+
+impl BackgroundGradient {
+    pub fn new(gradient: Gradient) -> BackgroundGradient {
+        BackgroundGradient {
+            gradient,
+            image: Handle::default(),
+            key: None,
+        }
+    }
+
+    /// The texture generated for the current size of the node
+    pub fn image(&self) -> &Handle<Image> {
+        &self.image
+    }
+}
+
+/// Generated gradient textures shared by the nodes with the same
+/// gradient and size.
+#[derive(Resource, Default)]
+pub(crate) struct GradientImages(HashMap<GradientKey, Handle<Image>>);
+
+/// Rounds the side of the node to the step of 1/8 of the power of two
+/// below it (at least 4px), so the nodes of the close sizes share the
+/// texture instead of rendering a new one for every pixel of the resize.
+/// The texture is stretched to the actual size of the node.
+fn quantize_size(size: Vec2) -> Vec2 {
+    let quantize = |side: f32| {
+        let step = (side.log2().floor().exp2() / 8.).max(4.);
+        (side / step).round().max(1.) * step
+    };
+    Vec2::new(quantize(size.x), quantize(size.y))
+}
+
+/// Generates the textures for the new, changed and resized gradients.
+/// The textures not used by any node are dropped from the cache.
+pub(crate) fn update_gradient_images(
+    mut cache: ResMut<GradientImages>,
+    mut images: ResMut<Assets<Image>>,
+    mut removed: RemovedComponents<BackgroundGradient>,
+    mut gradients: Query<(&Node, &mut BackgroundGradient)>,
+    ui_scale: Res<UiScale>,
+) {
+    let scale = ui_scale.scale as f32;
+    let mut changed = removed.iter().count() > 0;
+    for (node, mut background) in gradients.iter_mut() {
+        if node.size().cmple(Vec2::ZERO).any() {
+            continue;
+        }
+        let size = quantize_size(node.size());
+        let key = GradientKey::new(&background.gradient, size, scale);
+        if background.key.as_ref() == Some(&key) {
+            continue;
+        }
+        let image = cache
+            .0
+            .entry(key.clone())
+            .or_insert_with(|| images.add(background.gradient.render(size, scale)))
+            .clone();
+        background.image = image;
+        background.key = Some(key);
+        changed = true;
+    }
+    if changed {
+        let used: HashSet<_> = gradients
+            .iter()
+            .filter_map(|(_, b)| b.key.as_ref())
+            .collect();
+        cache.0.retain(|key, _| used.contains(key));
+    }
+}
+
+/// Extracts the gradient right after the background color of the node
+pub(crate) fn extract_gradients(
+    mut extracted_uinodes: ResMut<ExtractedUiNodes>,
+    ui_stack: Extract<Res<UiStack>>,
+    nodes: Extract<
+        Query<(
+            &Node,
+            &GlobalTransform,
+            &BackgroundGradient,
+            &ComputedVisibility,
+            Option<&CalculatedClip>,
+        )>,
+    >,
+) {
+    for (stack_index, entity) in ui_stack.uinodes.iter().enumerate() {
+        let Ok((node, transform, background, visibility, clip)) = nodes.get(*entity) else {
+            continue;
+        };
+        if !visibility.is_visible() || background.key.is_none() || node.size() == Vec2::ZERO {
+            continue;
+        }
+        extracted_uinodes.uinodes.push(ExtractedUiNode {
+            transform: transform.compute_matrix(),
+            color: Color::WHITE,
+            rect: Rect {
+                max: node.size(),
+                ..default()
+            },
+            image: background.image.clone_weak(),
+            atlas_size: None,
+            clip: clip.map(|clip| clip.clip),
+            stack_index,
+            flip_x: false,
+            flip_y: false,
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn gradient(value: &str) -> Result<Option<Gradient>, ElementsError> {
+        Gradient::parse(&StyleProperty::try_from(value).unwrap())
+    }
+
+    fn pixel(image: &Image, x: u32, y: u32) -> [u8; 4] {
+        let idx = ((y * image.size().x as u32 + x) * 4) as usize;
+        [
+            image.data[idx],
+            image.data[idx + 1],
+            image.data[idx + 2],
+            image.data[idx + 3],
+        ]
+    }
+
+    #[test]
+    fn parse_gradients() {
+        assert_eq!(gradient("none"), Ok(None));
+        let linear = gradient("linear-gradient(red, blue)").unwrap().unwrap();
+        assert_eq!(
+            linear.kind,
+            GradientKind::Linear(LinearDirection::Angle(PI))
+        );
+        assert_eq!(linear.stops.len(), 2);
+        let linear = gradient("linear-gradient(to top right, red 10%, white, blue 20px 40px)");
+        let linear = linear.unwrap().unwrap();
+        assert_eq!(
+            linear.kind,
+            GradientKind::Linear(LinearDirection::Corner(Vec2::new(1., -1.)))
+        );
+        assert_eq!(linear.stops.len(), 4);
+        assert_eq!(linear.stops[0].position, Some(Val::Percent(10.)));
+        assert_eq!(linear.stops[1].position, None);
+        assert_eq!(linear.stops[3].position, Some(Val::Px(40.)));
+        let linear = gradient("linear-gradient(0.25turn, red, blue)")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            linear.kind,
+            GradientKind::Linear(LinearDirection::Angle(PI / 2.))
+        );
+        let radial = gradient("radial-gradient(circle closest-side at left top, red, blue)");
+        assert_eq!(
+            radial.unwrap().unwrap().kind,
+            GradientKind::Radial {
+                shape: RadialShape::Circle,
+                extent: RadialExtent::ClosestSide,
+                center: (Val::Percent(0.), Val::Percent(0.)),
+            }
+        );
+        assert!(gradient("linear-gradient(red)").is_err());
+        assert!(gradient("linear-gradient(to middle, red, blue)").is_err());
+        assert!(gradient("linear-gradient(to left right, red, blue)").is_err());
+        assert!(gradient("radial-gradient(circle square, red, blue)").is_err());
+        assert!(gradient("conic-gradient(red, blue)").is_err());
+    }
+
+    #[test]
+    fn spread_color_stops() {
+        let gradient = gradient("linear-gradient(red, red, red 60%, red 40%, red)");
+        let stops = gradient.unwrap().unwrap().resolve_stops(100., 1.);
+        let positions: Vec<_> = stops.iter().map(|(p, _)| *p).collect();
+        assert_eq!(positions, vec![0., 0.3, 0.6, 0.6, 1.]);
+    }
+
+    #[test]
+    fn render_gradients() {
+        let linear = gradient("linear-gradient(to right, red, blue)")
+            .unwrap()
+            .unwrap();
+        let image = linear.render(Vec2::new(100., 10.), 1.);
+        assert_eq!(image.size(), Vec2::new(100., 10.));
+        assert_eq!(pixel(&image, 0, 5), [254, 0, 1, 255]);
+        assert_eq!(pixel(&image, 99, 0), [1, 0, 254, 255]);
+
+        let radial = gradient("radial-gradient(circle closest-side, white, transparent)");
+        let image = radial.unwrap().unwrap().render(Vec2::new(2048., 1024.), 1.);
+        assert_eq!(image.size(), Vec2::new(512., 256.));
+        assert!(pixel(&image, 256, 128)[3] > 250);
+        assert_eq!(pixel(&image, 0, 128)[3], 0);
+        // the transparent color doesn't darken the gradient
+        let [r, g, b, a] = pixel(&image, 192, 128);
+        assert_eq!([r, g, b], [255, 255, 255]);
+        assert!(a > 120 && a < 136);
+    }
+
+    #[test]
+    fn share_textures_of_close_sizes() {
+        assert_eq!(quantize_size(Vec2::new(3., 10.)), Vec2::new(4., 12.));
+        assert_eq!(quantize_size(Vec2::new(100., 101.)), Vec2::new(104., 104.));
+        assert_eq!(
+            quantize_size(Vec2::new(130., 1000.)),
+            Vec2::new(128., 1024.)
+        );
+        assert_eq!(
+            quantize_size(Vec2::new(600., 601.)),
+            quantize_size(Vec2::new(590., 605.))
+        );
+    }
+}
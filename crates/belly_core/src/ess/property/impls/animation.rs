@@ -0,0 +1,398 @@
+use super::transition::{split_list, update_or_insert, EasingListParser, TimeListParser};
+use crate::compound_style_property;
+use crate::eml::Variant;
+use crate::ess::property::animation::{AnimationDirection, Animations, FillMode, IterationCount};
+use crate::ess::property::transition::{self, EasingFunction};
+use crate::ess::{PropertyParser, PropertyValue, StyleProperty, StylePropertyToken};
+use crate::style_property;
+use crate::ElementsError;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use tagstr::*;
+
+fn item_to_property(item: &[StylePropertyToken]) -> StyleProperty {
+    StyleProperty(item.iter().cloned().collect())
+}
+
+fn animation_name(item: &[StylePropertyToken]) -> Result<Tag, ElementsError> {
+    match item {
+        [StylePropertyToken::Identifier(name)] | [StylePropertyToken::String(name)] => {
+            Ok(Tag::new(name))
+        }
+        item => Err(ElementsError::InvalidPropertyValue(format!(
+            "Expected animation name, got `{}`",
+            item_to_property(item).to_string()
+        ))),
+    }
+}
+
+fn iteration_count(item: &[StylePropertyToken]) -> Result<IterationCount, ElementsError> {
+    match item {
+        [StylePropertyToken::Number(count)] if count.to_float() >= 0.0 => {
+            Ok(IterationCount::Count(count.to_float()))
+        }
+        [token] if token.is_ident("infinite") => Ok(IterationCount::Infinite),
+        item => Err(ElementsError::InvalidPropertyValue(format!(
+            "Expected infinite|$num, got `{}`",
+            item_to_property(item).to_string()
+        ))),
+    }
+}
+
+/// <!-- @property-type=none|$ident, ... -->
+pub struct AnimationNameParser;
+impl PropertyParser<Vec<Tag>> for AnimationNameParser {
+    fn parse(value: &StyleProperty) -> Result<Vec<Tag>, ElementsError> {
+        split_list(value).map(animation_name).collect()
+    }
+}
+
+/// <!-- @property-type=infinite|$num, ... -->
+pub struct IterationCountParser;
+impl PropertyParser<Vec<IterationCount>> for IterationCountParser {
+    fn parse(value: &StyleProperty) -> Result<Vec<IterationCount>, ElementsError> {
+        split_list(value).map(iteration_count).collect()
+    }
+}
+
+/// <!-- @property-type=normal|reverse|alternate|alternate-reverse, ... -->
+pub struct AnimationDirectionParser;
+impl PropertyParser<Vec<AnimationDirection>> for AnimationDirectionParser {
+    fn parse(value: &StyleProperty) -> Result<Vec<AnimationDirection>, ElementsError> {
+        split_list(value)
+            .map(|item| AnimationDirection::try_from(&item_to_property(item)))
+            .collect()
+    }
+}
+
+/// <!-- @property-type=none|forwards|backwards|both, ... -->
+pub struct FillModeParser;
+impl PropertyParser<Vec<FillMode>> for FillModeParser {
+    fn parse(value: &StyleProperty) -> Result<Vec<FillMode>, ElementsError> {
+        split_list(value)
+            .map(|item| FillMode::try_from(&item_to_property(item)))
+            .collect()
+    }
+}
+
+compound_style_property! {
+    #[doc = " Plays the `@keyframes` animation on the element. The `animation`"]
+    #[doc = " property is shorthand property for:"]
+    #[doc = " - `animation-name` specifies the name of `@keyframes` rule"]
+    #[doc = " - `animation-duration` specifies how long the single iteration takes"]
+    #[doc = " - `animation-timing-function` specifies the easing between keyframes"]
+    #[doc = " - `animation-delay` specifies when the animation starts"]
+    #[doc = " - `animation-iteration-count` specifies how many times the animation is played"]
+    #[doc = " - `animation-direction` specifies whether the animation is played backwards"]
+    #[doc = " - `animation-fill-mode` specifies whether the values are applied before"]
+    #[doc = "   the start and after the end of the animation"]
+    #[doc = " "]
+    #[doc = " The format of property is comma separated list of:"]
+    #[doc = " ```css"]
+    #[doc = " name duration timing-function delay iteration-count direction fill-mode"]
+    #[doc = " ```"]
+    #[doc = " Every part is optional and the order doesn't matter, except that"]
+    #[doc = " the first time value is the duration, the second one is the delay."]
+    #[doc = " A unitless `0` is the iteration count unless the count is already given."]
+    #[doc = " "]
+    #[doc = " Example:"]
+    #[doc = " ```css"]
+    #[doc = "   animation: pulse 1s ease-in-out infinite alternate"]
+    #[doc = "   animation: shake 300ms linear 3, fade-in 1s 0.5s both"]
+    #[doc = " ```"]
+    #[doc = " <!-- @property-type=[name] [duration] [timing-function] [delay] [iteration-count] [direction] [fill-mode], ... -->"]
+    #[doc = " <!-- @property-category=Animations -->"]
+    AnimationProperty("animation", value) {
+        Longhands = [
+            "animation-name",
+            "animation-duration",
+            "animation-timing-function",
+            "animation-delay",
+            "animation-iteration-count",
+            "animation-direction",
+            "animation-fill-mode",
+        ];
+        let props = match value {
+            Variant::String(unparsed) => StyleProperty::try_from(unparsed)?,
+            Variant::Style(prop) => prop,
+            v => return Self::error(format!("Don't know how to extract animation from {v:?}"))
+        };
+        let mut names = vec![];
+        let mut durations = vec![];
+        let mut easings = vec![];
+        let mut delays = vec![];
+        let mut iterations = vec![];
+        let mut directions = vec![];
+        let mut fill_modes = vec![];
+        for item in split_list(&props) {
+            let mut name = None;
+            let mut duration = None;
+            let mut easing = None;
+            let mut delay = None;
+            let mut iteration = None;
+            let mut direction = None;
+            let mut fill_mode = None;
+            for token in item.iter() {
+                let token_slice = std::slice::from_ref(token);
+                let token_property = item_to_property(token_slice);
+                // a unitless `0` is the iteration count if it isn't given yet
+                let count = iteration.is_none() && matches!(token, StylePropertyToken::Number(_));
+                if let (false, Ok(time)) = (count, transition::time(token_slice)) {
+                    if duration.is_none() {
+                        duration = Some(time);
+                    } else if delay.is_none() {
+                        delay = Some(time);
+                    } else {
+                        return Self::error(format!("Unexpected `{}` in animation", token.to_string()));
+                    }
+                } else if let (None, Ok(value)) = (easing, EasingFunction::try_from(token_slice)) {
+                    easing = Some(value);
+                } else if let (None, Ok(value)) = (iteration, iteration_count(token_slice)) {
+                    iteration = Some(value);
+                } else if let (None, Ok(value)) = (direction, AnimationDirection::try_from(&token_property)) {
+                    direction = Some(value);
+                } else if let (None, Ok(value)) = (fill_mode, FillMode::try_from(&token_property)) {
+                    fill_mode = Some(value);
+                } else if let (None, Ok(value)) = (name, animation_name(token_slice)) {
+                    name = Some(value);
+                } else {
+                    return Self::error(format!("Unexpected `{}` in animation", token.to_string()));
+                }
+            }
+            names.push(name.unwrap_or(tag!("none")));
+            durations.push(duration.unwrap_or_default());
+            easings.push(easing.unwrap_or_default());
+            delays.push(delay.unwrap_or_default());
+            iterations.push(iteration.unwrap_or_default());
+            directions.push(direction.unwrap_or_default());
+            fill_modes.push(fill_mode.unwrap_or_default());
+        }
+        let mut result = HashMap::default();
+        result.insert(tag!("animation-name"), PropertyValue::new(names));
+        result.insert(tag!("animation-duration"), PropertyValue::new(durations));
+        result.insert(tag!("animation-timing-function"), PropertyValue::new(easings));
+        result.insert(tag!("animation-delay"), PropertyValue::new(delays));
+        result.insert(tag!("animation-iteration-count"), PropertyValue::new(iterations));
+        result.insert(tag!("animation-direction"), PropertyValue::new(directions));
+        result.insert(tag!("animation-fill-mode"), PropertyValue::new(fill_modes));
+        Ok(result)
+    }
+}
+
+style_property! {
+    #[doc = " The `animation-name` property specifies the names of the `@keyframes`"]
+    #[doc = " rules played on the element. `none` stops the animation, so the same"]
+    #[doc = " animation is played again when the name is set next time."]
+    #[doc = " <!-- @property-category=Animations -->"]
+    AnimationNameProperty("animation-name") {
+        Default = "none";
+        Item = Vec<Tag>;
+        Components = ();
+        Filters = With<Node>;
+        Parser = AnimationNameParser;
+        Apply = |value, _components, _assets, commands, entity| {
+            let value = value.clone();
+            commands.add(update_or_insert(entity, move |a: &mut Animations| a.names = value));
+        };
+    }
+}
+
+style_property! {
+    #[doc = " The `animation-duration` property specifies how long the single"]
+    #[doc = " iteration of the animation takes."]
+    #[doc = " <!-- @property-category=Animations -->"]
+    AnimationDurationProperty("animation-duration") {
+        Default = "0s";
+        Item = Vec<f32>;
+        Components = ();
+        Filters = With<Node>;
+        Parser = TimeListParser;
+        Apply = |value, _components, _assets, commands, entity| {
+            let value = value.clone();
+            commands.add(update_or_insert(entity, move |a: &mut Animations| a.durations = value));
+        };
+    }
+}
+
+style_property! {
+    #[doc = " The `animation-timing-function` property specifies how the values"]
+    #[doc = " between the keyframes are calculated."]
+    #[doc = " <!-- @property-category=Animations -->"]
+    AnimationTimingFunctionProperty("animation-timing-function") {
+        Default = "ease";
+        Item = Vec<EasingFunction>;
+        Components = ();
+        Filters = With<Node>;
+        Parser = EasingListParser;
+        Apply = |value, _components, _assets, commands, entity| {
+            let value = value.clone();
+            commands.add(update_or_insert(entity, move |a: &mut Animations| a.easings = value));
+        };
+    }
+}
+
+style_property! {
+    #[doc = " The `animation-delay` property specifies how long to wait before"]
+    #[doc = " the animation starts."]
+    #[doc = " <!-- @property-category=Animations -->"]
+    AnimationDelayProperty("animation-delay") {
+        Default = "0s";
+        Item = Vec<f32>;
+        Components = ();
+        Filters = With<Node>;
+        Parser = TimeListParser;
+        Apply = |value, _components, _assets, commands, entity| {
+            let value = value.clone();
+            commands.add(update_or_insert(entity, move |a: &mut Animations| a.delays = value));
+        };
+    }
+}
+
+style_property! {
+    #[doc = " The `animation-iteration-count` property specifies how many times"]
+    #[doc = " the animation is played."]
+    #[doc = " <!-- @property-category=Animations -->"]
+    AnimationIterationCountProperty("animation-iteration-count") {
+        Default = "1";
+        Item = Vec<IterationCount>;
+        Components = ();
+        Filters = With<Node>;
+        Parser = IterationCountParser;
+        Apply = |value, _components, _assets, commands, entity| {
+            let value = value.clone();
+            commands.add(update_or_insert(entity, move |a: &mut Animations| a.iterations = value));
+        };
+    }
+}
+
+style_property! {
+    #[doc = " The `animation-direction` property specifies whether the animation"]
+    #[doc = " is played forwards (`normal`), backwards (`reverse`) or alternates"]
+    #[doc = " the direction each iteration (`alternate`, `alternate-reverse`)."]
+    #[doc = " <!-- @property-category=Animations -->"]
+    AnimationDirectionProperty("animation-direction") {
+        Default = "normal";
+        Item = Vec<AnimationDirection>;
+        Components = ();
+        Filters = With<Node>;
+        Parser = AnimationDirectionParser;
+        Apply = |value, _components, _assets, commands, entity| {
+            let value = value.clone();
+            commands.add(update_or_insert(entity, move |a: &mut Animations| a.directions = value));
+        };
+    }
+}
+
+style_property! {
+    #[doc = " The `animation-fill-mode` property specifies whether the values of the"]
+    #[doc = " first keyframe are applied while the animation is delayed (`backwards`),"]
+    #[doc = " the values of the last keyframe are kept after the animation ends"]
+    #[doc = " (`forwards`), or both."]
+    #[doc = " <!-- @property-category=Animations -->"]
+    AnimationFillModeProperty("animation-fill-mode") {
+        Default = "none";
+        Item = Vec<FillMode>;
+        Components = ();
+        Filters = With<Node>;
+        Parser = FillModeParser;
+        Apply = |value, _components, _assets, commands, entity| {
+            let value = value.clone();
+            commands.add(update_or_insert(entity, move |a: &mut Animations| a.fill_modes = value));
+        };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ess::CompoundProperty;
+
+    fn get<T: Clone + 'static>(props: &HashMap<Tag, PropertyValue>, name: &str) -> T {
+        props
+            .get(&name.as_tag())
+            .unwrap()
+            .downcast_ref::<T>()
+            .unwrap()
+            .clone()
+    }
+
+    #[test]
+    fn extract_animation() {
+        let props = AnimationProperty::extract(Variant::String(
+            "pulse 1s ease-in-out infinite alternate, shake 300ms linear 0.1s 3 both".into(),
+        ))
+        .unwrap();
+        assert_eq!(
+            get::<Vec<Tag>>(&props, "animation-name"),
+            vec![tag!("pulse"), tag!("shake")]
+        );
+        assert_eq!(
+            get::<Vec<f32>>(&props, "animation-duration"),
+            vec![1.0, 0.3]
+        );
+        assert_eq!(get::<Vec<f32>>(&props, "animation-delay"), vec![0.0, 0.1]);
+        assert_eq!(
+            get::<Vec<EasingFunction>>(&props, "animation-timing-function"),
+            vec![EasingFunction::EaseInOut, EasingFunction::Linear]
+        );
+        assert_eq!(
+            get::<Vec<IterationCount>>(&props, "animation-iteration-count"),
+            vec![IterationCount::Infinite, IterationCount::Count(3.0)]
+        );
+        assert_eq!(
+            get::<Vec<AnimationDirection>>(&props, "animation-direction"),
+            vec![AnimationDirection::Alternate, AnimationDirection::Normal]
+        );
+        assert_eq!(
+            get::<Vec<FillMode>>(&props, "animation-fill-mode"),
+            vec![FillMode::None, FillMode::Both]
+        );
+
+        let props = AnimationProperty::extract(Variant::String("none".into())).unwrap();
+        assert_eq!(
+            get::<Vec<Tag>>(&props, "animation-name"),
+            vec![tag!("none")]
+        );
+        assert!(AnimationProperty::extract(Variant::String("a b 1s".into())).is_err());
+
+        let props = AnimationProperty::extract(Variant::String("fade 1s 0".into())).unwrap();
+        assert_eq!(get::<Vec<f32>>(&props, "animation-duration"), vec![1.0]);
+        assert_eq!(get::<Vec<f32>>(&props, "animation-delay"), vec![0.0]);
+        assert_eq!(
+            get::<Vec<IterationCount>>(&props, "animation-iteration-count"),
+            vec![IterationCount::Count(0.0)]
+        );
+        let props = AnimationProperty::extract(Variant::String("fade 2 0 1s".into())).unwrap();
+        assert_eq!(get::<Vec<f32>>(&props, "animation-duration"), vec![0.0]);
+        assert_eq!(get::<Vec<f32>>(&props, "animation-delay"), vec![1.0]);
+        assert_eq!(
+            get::<Vec<IterationCount>>(&props, "animation-iteration-count"),
+            vec![IterationCount::Count(2.0)]
+        );
+    }
+
+    #[test]
+    fn parse_animation_lists() {
+        let prop = |v: &str| StyleProperty::try_from(v).unwrap();
+        assert_eq!(
+            AnimationNameParser::parse(&prop("pulse, \"fade-in\"")),
+            Ok(vec![tag!("pulse"), tag!("fade-in")])
+        );
+        assert_eq!(
+            IterationCountParser::parse(&prop("infinite, 2.5")),
+            Ok(vec![IterationCount::Infinite, IterationCount::Count(2.5)])
+        );
+        assert_eq!(
+            AnimationDirectionParser::parse(&prop("reverse, alternate-reverse")),
+            Ok(vec![
+                AnimationDirection::Reverse,
+                AnimationDirection::AlternateReverse
+            ])
+        );
+        assert_eq!(
+            FillModeParser::parse(&prop("forwards")),
+            Ok(vec![FillMode::Forwards])
+        );
+        assert!(FillModeParser::parse(&prop("sideways")).is_err());
+    }
+}
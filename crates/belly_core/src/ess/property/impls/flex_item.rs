@@ -1,4 +1,5 @@
 use super::parse;
+use crate::ess::property::calc::{CalcLengths, Length, LengthField};
 use crate::style_property;
 use bevy::prelude::*;
 
@@ -80,14 +81,13 @@ style_property! {
     #[doc = " <!-- @property-category=Flex Item -->"]
     FlexBasisProperty("flex-basis") {
         Default = "auto";
-        Item = Val;
-        Components = &'static mut Style;
+        Item = Length;
+        Components = (&'static mut Style, Option<&'static mut CalcLengths>);
         Filters = With<Node>;
-        Parser = parse::ValParser;
-        Apply = |value, style, _assets, _commands, _entity| {
-            if &style.flex_basis != value {
-                style.flex_basis = *value;
-            }
+        Parser = parse::LengthParser;
+        Apply = |value, components, _assets, commands, entity| {
+            let (style, calc) = components;
+            value.apply(LengthField::FlexBasis, style, calc, commands, entity);
         };
     }
 }
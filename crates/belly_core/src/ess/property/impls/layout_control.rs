@@ -1,5 +1,6 @@
 use super::parse;
 use crate::compound_style_property;
+use crate::ess::property::calc::{CalcLengths, Length, LengthField, LengthRect};
 use crate::ess::ToRectMap;
 use crate::style_property;
 use bevy::prelude::*;
@@ -42,7 +43,8 @@ compound_style_property! {
     #[doc = " <!-- @property-type=$rect -->"]
     #[doc = " <!-- @property-category=Layout Control -->"]
     PositionProperty("position", value) {
-        let rect = UiRect::try_from(value)?;
+        Longhands = ["left", "right", "top", "bottom"];
+        let rect = LengthRect::try_from(value)?;
         Ok(rect.to_rect_map(""))
     }
 }
@@ -55,14 +57,13 @@ style_property! {
     #[doc = " <!-- @property-category=Layout Control Positioning -->"]
     LeftProperty("left") {
         Default = "undefined";
-        Item = Val;
-        Components = &'static mut Style;
+        Item = Length;
+        Components = (&'static mut Style, Option<&'static mut CalcLengths>);
         Filters = With<Node>;
-        Parser = parse::ValParser;
-        Apply = |value, style, _assets, _commands, _entity| {
-            if &style.left != value {
-                style.left = *value;
-            }
+        Parser = parse::LengthParser;
+        Apply = |value, components, _assets, commands, entity| {
+            let (style, calc) = components;
+            value.apply(LengthField::Left, style, calc, commands, entity);
         };
     }
 }
@@ -75,14 +76,13 @@ style_property! {
     #[doc = " <!-- @property-category=Layout Control Positioning -->"]
     RightProperty("right") {
         Default = "undefined";
-        Item = Val;
-        Components = &'static mut Style;
+        Item = Length;
+        Components = (&'static mut Style, Option<&'static mut CalcLengths>);
         Filters = With<Node>;
-        Parser = parse::ValParser;
-        Apply = |value, style, _assets, _commands, _entity| {
-            if &style.right != value {
-                style.right = *value;
-            }
+        Parser = parse::LengthParser;
+        Apply = |value, components, _assets, commands, entity| {
+            let (style, calc) = components;
+            value.apply(LengthField::Right, style, calc, commands, entity);
         };
     }
 }
@@ -95,14 +95,13 @@ style_property! {
     #[doc = " <!-- @property-category=Layout Control Positioning -->"]
     TopProperty("top") {
         Default = "undefined";
-        Item = Val;
-        Components = &'static mut Style;
+        Item = Length;
+        Components = (&'static mut Style, Option<&'static mut CalcLengths>);
         Filters = With<Node>;
-        Parser = parse::ValParser;
-        Apply = |value, style, _assets, _commands, _entity| {
-            if &style.top != value {
-                style.top = *value;
-            }
+        Parser = parse::LengthParser;
+        Apply = |value, components, _assets, commands, entity| {
+            let (style, calc) = components;
+            value.apply(LengthField::Top, style, calc, commands, entity);
         };
     }
 }
@@ -115,14 +114,13 @@ style_property! {
     #[doc = " <!-- @property-category=Layout Control Positioning -->"]
     BottomProperty("bottom") {
         Default = "undefined";
-        Item = Val;
-        Components = &'static mut Style;
+        Item = Length;
+        Components = (&'static mut Style, Option<&'static mut CalcLengths>);
         Filters = With<Node>;
-        Parser = parse::ValParser;
-        Apply = |value, style, _assets, _commands, _entity| {
-            if &style.bottom != value {
-                style.bottom = *value;
-            }
+        Parser = parse::LengthParser;
+        Apply = |value, components, _assets, commands, entity| {
+            let (style, calc) = components;
+            value.apply(LengthField::Bottom, style, calc, commands, entity);
         };
     }
 }
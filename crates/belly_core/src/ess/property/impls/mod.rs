@@ -1,3 +1,4 @@
+pub mod animation;
 pub mod flex_container;
 pub mod flex_item;
 pub mod grid;
@@ -6,11 +7,20 @@ pub mod size_constraints;
 pub mod spacing;
 pub mod stylebox;
 pub mod text;
+pub mod transition;
 
+use super::border::{BorderColors, BorderSide};
+use super::gradient::{BackgroundGradient, Gradient};
+use super::opacity::Opacity;
 use super::parse;
+use super::shadow::BoxShadows;
+use super::transform::{ElementTransform, TransformOrigin, UiTransform};
 use super::PropertyParser;
 use super::StyleProperty;
 use super::StylePropertyToken;
+use crate::compound_style_property;
+use crate::eml::Variant;
+use crate::input::ElementCursor;
 use crate::style_property;
 use crate::ElementsError;
 use bevy::prelude::*;
@@ -32,6 +42,51 @@ style_property! {
     }
 }
 
+/// none|$gradient
+pub struct GradientParser;
+impl PropertyParser<Option<Gradient>> for GradientParser {
+    fn parse(value: &StyleProperty) -> Result<Option<Gradient>, ElementsError> {
+        Gradient::parse(value)
+    }
+}
+
+style_property! {
+    #[doc = " Specify the gradient drawn over the `background-color` of the element:"]
+    #[doc = " ```css"]
+    #[doc = " background-image: linear-gradient(to right, #2f2f2f, #4f4f4f 80%);"]
+    #[doc = " background-image: linear-gradient(45deg, red, transparent);"]
+    #[doc = " background-image: radial-gradient(circle at left top, white, black);"]
+    #[doc = " ```"]
+    #[doc = " `linear-gradient()` takes an optional angle (`180deg` by default) or `to $side`"]
+    #[doc = " direction, `radial-gradient()` takes an optional `circle` or `ellipse` shape, the"]
+    #[doc = " extent (`closest-side`, `closest-corner`, `farthest-side`, `farthest-corner`)"]
+    #[doc = " and the `at $position` center. The rest of the arguments are color stops with"]
+    #[doc = " optional positions in pixels or percents. The gradient texture is generated"]
+    #[doc = " for the size of the element and regenerated when the element is resized."]
+    #[doc = " <!-- @property-type=none|$gradient -->"]
+    #[doc = " <!-- @property-category=General -->"]
+    BackgroundImageProperty("background-image") {
+        Default = "none";
+        Item = Option<Gradient>;
+        Components = Option<&'static mut BackgroundGradient>;
+        Filters = With<Node>;
+        Parser = GradientParser;
+        Apply = |value, background, _assets, commands, entity| {
+            match (background, value) {
+                (Some(mut background), Some(gradient)) if &background.gradient != gradient => {
+                    background.gradient = gradient.clone();
+                }
+                (Some(_), Some(_)) => { }
+                (Some(_), None) => { commands.entity(entity).remove::<BackgroundGradient>(); }
+                (None, Some(gradient)) => {
+                    commands.entity(entity).insert(BackgroundGradient::new(gradient.clone()));
+                }
+                (None, None) => { }
+            }
+        };
+    }
+}
+
 /// auto|$local|$global
 pub struct OptionalZIndexParser;
 impl PropertyParser<Option<ZIndex>> for OptionalZIndexParser {
@@ -87,3 +142,374 @@ style_property! {
         };
     }
 }
+
+style_property! {
+    #[doc = " Specify whether the element is rendered by providing value to `Visibility`:"]
+    #[doc = " ```css"]
+    #[doc = " visibility: hidden;"]
+    #[doc = " ```"]
+    #[doc = " <!-- @property-type=$ident -->"]
+    #[doc = " - `visible`: the element is visible unless one of its ancestors is hidden"]
+    #[doc = " - `hidden`: the element and all of its descendants are not rendered, but"]
+    #[doc = "   still take their space in the layout. Hidden elements don't receive"]
+    #[doc = "   the pointer input and can't be focused."]
+    #[doc = " <!-- @property-category=General -->"]
+    VisibilityProperty("visibility") {
+        Default = "visible";
+        Item = Visibility;
+        Components = &'static mut Visibility;
+        Filters = With<Node>;
+        Parser = parse::IdentifierParser<Visibility>;
+        Apply = |value, visibility, _assets, _commands, _entity| {
+            if &*visibility != value {
+                *visibility = *value;
+            }
+        };
+    }
+}
+
+/// $ident
+pub struct CursorParser;
+impl PropertyParser<ElementCursor> for CursorParser {
+    fn parse(value: &StyleProperty) -> Result<ElementCursor, ElementsError> {
+        let Some(StylePropertyToken::Identifier(ident)) = value.first() else {
+            return Err(ElementsError::InvalidPropertyValue(format!(
+                "Expected $ident, got `{}`",
+                value.to_string()
+            )));
+        };
+        let icon = match ident.as_str() {
+            "auto" | "default" => CursorIcon::Default,
+            "pointer" => CursorIcon::Hand,
+            "text" => CursorIcon::Text,
+            "vertical-text" => CursorIcon::VerticalText,
+            "grab" => CursorIcon::Grab,
+            "grabbing" => CursorIcon::Grabbing,
+            "move" => CursorIcon::Move,
+            "all-scroll" => CursorIcon::AllScroll,
+            "not-allowed" => CursorIcon::NotAllowed,
+            "no-drop" => CursorIcon::NoDrop,
+            "crosshair" => CursorIcon::Crosshair,
+            "wait" => CursorIcon::Wait,
+            "progress" => CursorIcon::Progress,
+            "help" => CursorIcon::Help,
+            "context-menu" => CursorIcon::ContextMenu,
+            "cell" => CursorIcon::Cell,
+            "alias" => CursorIcon::Alias,
+            "copy" => CursorIcon::Copy,
+            "zoom-in" => CursorIcon::ZoomIn,
+            "zoom-out" => CursorIcon::ZoomOut,
+            "e-resize" => CursorIcon::EResize,
+            "n-resize" => CursorIcon::NResize,
+            "ne-resize" => CursorIcon::NeResize,
+            "nw-resize" => CursorIcon::NwResize,
+            "s-resize" => CursorIcon::SResize,
+            "se-resize" => CursorIcon::SeResize,
+            "sw-resize" => CursorIcon::SwResize,
+            "w-resize" => CursorIcon::WResize,
+            "ew-resize" => CursorIcon::EwResize,
+            "ns-resize" => CursorIcon::NsResize,
+            "nesw-resize" => CursorIcon::NeswResize,
+            "nwse-resize" => CursorIcon::NwseResize,
+            "col-resize" => CursorIcon::ColResize,
+            "row-resize" => CursorIcon::RowResize,
+            ident => {
+                return Err(ElementsError::InvalidPropertyValue(format!(
+                    "Unknown cursor `{ident}`"
+                )))
+            }
+        };
+        Ok(ElementCursor(icon))
+    }
+}
+
+style_property! {
+    #[doc = " Specify the icon of the window cursor shown while the pointer is over the element:"]
+    #[doc = " ```css"]
+    #[doc = " cursor: pointer;"]
+    #[doc = " ```"]
+    #[doc = " The property accepts the css cursor names: `default`, `pointer`, `text`, `grab`,"]
+    #[doc = " `grabbing`, `move`, `not-allowed`, `wait`, `crosshair`, the resize cursors like"]
+    #[doc = " `ew-resize` or `col-resize` and so on. The cursor of the top-most hovered"]
+    #[doc = " interactable element is applied to the window, `default` keeps the cursor"]
+    #[doc = " of the window set by the app."]
+    #[doc = " <!-- @property-type=$ident -->"]
+    #[doc = " <!-- @property-category=General -->"]
+    CursorProperty("cursor") {
+        Default = "default";
+        Item = ElementCursor;
+        Components = Option<&'static mut ElementCursor>;
+        Filters = With<Node>;
+        Inherited = true;
+        Parser = CursorParser;
+        Apply = |value, cursor, _assets, commands, entity| {
+            match cursor {
+                Some(mut cursor) if &*cursor != value => { *cursor = *value; }
+                Some(_) => { }
+                None => { commands.entity(entity).insert(*value); }
+            }
+        };
+    }
+}
+
+/// $num|$percent
+pub struct OpacityParser;
+impl PropertyParser<f32> for OpacityParser {
+    fn parse(value: &StyleProperty) -> Result<f32, ElementsError> {
+        match value.first() {
+            Some(StylePropertyToken::Number(num)) => Ok(num.to_float().clamp(0., 1.)),
+            Some(StylePropertyToken::Percentage(num)) => Ok((num.to_float() / 100.).clamp(0., 1.)),
+            Some(token) => Err(ElementsError::InvalidPropertyValue(format!(
+                "Expected $num|$percent, got `{}`",
+                token.to_string()
+            ))),
+            None => Err(ElementsError::InvalidPropertyValue(format!(
+                "Expected $num|$percent, got nothing"
+            ))),
+        }
+    }
+}
+
+style_property! {
+    #[doc = " Specify the opacity of the element and all of its descendants:"]
+    #[doc = " ```css"]
+    #[doc = " opacity: 0.5;"]
+    #[doc = " opacity: 50%;"]
+    #[doc = " ```"]
+    #[doc = " The opacity multiplies the alpha of the backgrounds, images, text,"]
+    #[doc = " borders and styleboxes. The opacities of the nested elements are"]
+    #[doc = " multiplied: `0.5` inside of `0.5` results into `0.25`."]
+    #[doc = " <!-- @property-type=$num|$percent -->"]
+    #[doc = " <!-- @property-category=General -->"]
+    OpacityProperty("opacity") {
+        Default = "1";
+        Item = f32;
+        Components = Option<&'static mut Opacity>;
+        Filters = With<Node>;
+        Parser = OpacityParser;
+        Apply = |value, opacity, _assets, commands, entity| {
+            match opacity {
+                Some(mut opacity) if opacity.0 != *value => { opacity.0 = *value; }
+                Some(_) => { }
+                None if *value >= 1. => { }
+                None => { commands.entity(entity).insert(Opacity(*value)); }
+            }
+        };
+    }
+}
+
+/// none|$shadow, ...
+pub struct BoxShadowParser;
+impl PropertyParser<BoxShadows> for BoxShadowParser {
+    fn parse(value: &StyleProperty) -> Result<BoxShadows, ElementsError> {
+        BoxShadows::parse(value)
+    }
+}
+
+style_property! {
+    #[doc = " Specify the shadows rendered behind the element:"]
+    #[doc = " ```css"]
+    #[doc = " box-shadow: 2px 2px #4f4f4fb8;"]
+    #[doc = " box-shadow: 0 4px 10px 2px rgba(0, 0, 0, 0.5), 1px 1px black;"]
+    #[doc = " ```"]
+    #[doc = " Every shadow is `offset-x offset-y [blur-radius [spread-radius]] [color]`,"]
+    #[doc = " the lengths are in pixels. The blur radius softens the edges of the"]
+    #[doc = " shadow, the spread radius grows (or shrinks when negative) the shadow."]
+    #[doc = " The first shadow of the list is drawn on top."]
+    #[doc = " <!-- @property-type=none|$shadow, ... -->"]
+    #[doc = " <!-- @property-category=General -->"]
+    BoxShadowProperty("box-shadow") {
+        Default = "none";
+        Item = BoxShadows;
+        Components = Option<&'static mut BoxShadows>;
+        Filters = With<Node>;
+        Parser = BoxShadowParser;
+        Apply = |value, shadows, _assets, commands, entity| {
+            match shadows {
+                Some(mut shadows) if &*shadows != value => { *shadows = value.clone(); }
+                Some(_) => { }
+                None if value.0.is_empty() => { }
+                None => { commands.entity(entity).insert(value.clone()); }
+            }
+        };
+    }
+}
+
+/// none|$transform-function ...
+pub struct TransformParser;
+impl PropertyParser<UiTransform> for TransformParser {
+    fn parse(value: &StyleProperty) -> Result<UiTransform, ElementsError> {
+        UiTransform::parse(value)
+    }
+}
+
+style_property! {
+    #[doc = " Specify the transformation applied on top of the position computed by the layout:"]
+    #[doc = " ```css"]
+    #[doc = " transform: scale(1.1);"]
+    #[doc = " transform: translate(10px, -50%) rotate(45deg);"]
+    #[doc = " ```"]
+    #[doc = " Supported functions are `translate(x[, y])`, `translateX(x)`, `translateY(y)`,"]
+    #[doc = " `scale(s[, sy])`, `scaleX(s)`, `scaleY(s)` and `rotate(angle)`. The translation"]
+    #[doc = " accepts pixels or percents of the element size, the angle accepts `deg`, `rad`"]
+    #[doc = " and `turn` units. The functions are combined into the single translation,"]
+    #[doc = " rotation and scale. The transformation doesn't affect the layout, but is"]
+    #[doc = " inherited by the descendants and respected by the pointer input."]
+    #[doc = " <!-- @property-type=none|$transform-function ... -->"]
+    #[doc = " <!-- @property-category=General -->"]
+    TransformProperty("transform") {
+        Default = "none";
+        Item = UiTransform;
+        Components = Option<&'static mut ElementTransform>;
+        Filters = With<Node>;
+        Parser = TransformParser;
+        Apply = |value, element, _assets, commands, entity| {
+            let unchanged = element
+                .as_ref()
+                .map_or(value == &UiTransform::default(), |e| &e.transform == value);
+            if !unchanged {
+                let value = *value;
+                ElementTransform::modify(element, commands, entity, move |e| e.transform = value);
+            }
+        };
+    }
+}
+
+/// $position [$position]
+pub struct TransformOriginParser;
+impl PropertyParser<TransformOrigin> for TransformOriginParser {
+    fn parse(value: &StyleProperty) -> Result<TransformOrigin, ElementsError> {
+        TransformOrigin::parse(value)
+    }
+}
+
+style_property! {
+    #[doc = " Specify the point the `transform` rotates and scales the element around:"]
+    #[doc = " ```css"]
+    #[doc = " transform-origin: center;"]
+    #[doc = " transform-origin: left top;"]
+    #[doc = " transform-origin: 10px 50%;"]
+    #[doc = " ```"]
+    #[doc = " The property accepts one or two of `left`, `center`, `right`, `top`, `bottom`"]
+    #[doc = " keywords or the lengths from the top left corner of the element."]
+    #[doc = " <!-- @property-type=$position [$position] -->"]
+    #[doc = " <!-- @property-category=General -->"]
+    TransformOriginProperty("transform-origin") {
+        Default = "center";
+        Item = TransformOrigin;
+        Components = Option<&'static mut ElementTransform>;
+        Filters = With<Node>;
+        Parser = TransformOriginParser;
+        Apply = |value, element, _assets, commands, entity| {
+            let unchanged = element
+                .as_ref()
+                .map_or(value == &TransformOrigin::default(), |e| &e.origin == value);
+            if !unchanged {
+                let value = *value;
+                ElementTransform::modify(element, commands, entity, move |e| e.origin = value);
+            }
+        };
+    }
+}
+
+compound_style_property! {
+    #[doc = " Specify element border colors:"]
+    #[doc = " ```css"]
+    #[doc = " border-color: red;"]
+    #[doc = " border-color: #2f2f2f #4f4f4f;"]
+    #[doc = " border-color: white transparent gray transparent;"]
+    #[doc = " ```"]
+    #[doc = " The `border-color` property accepts one to four colors in the"]
+    #[doc = " `top right bottom left` order and is a shorthand for the"]
+    #[doc = " `border-left-color`, `border-right-color`, `border-top-color` and"]
+    #[doc = " `border-bottom-color` properties. The borders are drawn only when"]
+    #[doc = " `border-width` is defined."]
+    #[doc = " <!-- @property-type=$color{1,4} -->"]
+    #[doc = " <!-- @property-category=General -->"]
+    BorderColorProperty("border-color", value) {
+        Longhands = [
+            "border-left-color",
+            "border-right-color",
+            "border-top-color",
+            "border-bottom-color",
+        ];
+        let props = match value {
+            Variant::String(unparsed) => StyleProperty::try_from(unparsed)?,
+            Variant::Style(prop) => prop,
+            v => return Self::error(format!("Don't know how to extract border colors from {v:?}"))
+        };
+        Ok(BorderColors::parse(&props)?.to_longhands())
+    }
+}
+
+style_property! {
+    #[doc = " Specify element left border color:"]
+    #[doc = " ```css"]
+    #[doc = " border-left-color: red;"]
+    #[doc = " ```"]
+    #[doc = " <!-- @property-category=General -->"]
+    BorderLeftColorProperty("border-left-color") {
+        Default = "transparent";
+        Item = Color;
+        Components = Option<&'static mut BorderColors>;
+        Filters = With<Node>;
+        Parser = parse::ColorParser;
+        Apply = |value, colors, _assets, commands, entity| {
+            BorderColors::apply(BorderSide::Left, *value, colors, commands, entity);
+        };
+    }
+}
+
+style_property! {
+    #[doc = " Specify element right border color:"]
+    #[doc = " ```css"]
+    #[doc = " border-right-color: red;"]
+    #[doc = " ```"]
+    #[doc = " <!-- @property-category=General -->"]
+    BorderRightColorProperty("border-right-color") {
+        Default = "transparent";
+        Item = Color;
+        Components = Option<&'static mut BorderColors>;
+        Filters = With<Node>;
+        Parser = parse::ColorParser;
+        Apply = |value, colors, _assets, commands, entity| {
+            BorderColors::apply(BorderSide::Right, *value, colors, commands, entity);
+        };
+    }
+}
+
+style_property! {
+    #[doc = " Specify element top border color:"]
+    #[doc = " ```css"]
+    #[doc = " border-top-color: red;"]
+    #[doc = " ```"]
+    #[doc = " <!-- @property-category=General -->"]
+    BorderTopColorProperty("border-top-color") {
+        Default = "transparent";
+        Item = Color;
+        Components = Option<&'static mut BorderColors>;
+        Filters = With<Node>;
+        Parser = parse::ColorParser;
+        Apply = |value, colors, _assets, commands, entity| {
+            BorderColors::apply(BorderSide::Top, *value, colors, commands, entity);
+        };
+    }
+}
+
+style_property! {
+    #[doc = " Specify element bottom border color:"]
+    #[doc = " ```css"]
+    #[doc = " border-bottom-color: red;"]
+    #[doc = " ```"]
+    #[doc = " <!-- @property-category=General -->"]
+    BorderBottomColorProperty("border-bottom-color") {
+        Default = "transparent";
+        Item = Color;
+        Components = Option<&'static mut BorderColors>;
+        Filters = With<Node>;
+        Parser = parse::ColorParser;
+        Apply = |value, colors, _assets, commands, entity| {
+            BorderColors::apply(BorderSide::Bottom, *value, colors, commands, entity);
+        };
+    }
+}
@@ -1,4 +1,5 @@
 use super::parse;
+use crate::ess::property::calc::{CalcLengths, Length, LengthField};
 use crate::style_property;
 use bevy::prelude::*;
 
@@ -11,14 +12,13 @@ style_property! {
     #[doc = " <!-- @property-category=Size Constraints -->"]
     WidthProperty("width") {
         Default = "undefined";
-        Item = Val;
-        Components = &'static mut Style;
+        Item = Length;
+        Components = (&'static mut Style, Option<&'static mut CalcLengths>);
         Filters = With<Node>;
-        Parser = parse::ValParser;
-        Apply = |value, style, _assets, _commands, _entity| {
-            if &style.width != value {
-                style.width = *value;
-            }
+        Parser = parse::LengthParser;
+        Apply = |value, components, _assets, commands, entity| {
+            let (style, calc) = components;
+            value.apply(LengthField::Width, style, calc, commands, entity);
         };
     }
 }
@@ -31,14 +31,13 @@ style_property! {
     #[doc = " <!-- @property-category=Size Constraints -->"]
     HeightProperty("height") {
         Default = "undefined";
-        Item = Val;
-        Components = &'static mut Style;
+        Item = Length;
+        Components = (&'static mut Style, Option<&'static mut CalcLengths>);
         Filters = With<Node>;
-        Parser = parse::ValParser;
-        Apply = |value, style, _assets, _commands, _entity| {
-            if &style.height != value {
-                style.height = *value;
-            }
+        Parser = parse::LengthParser;
+        Apply = |value, components, _assets, commands, entity| {
+            let (style, calc) = components;
+            value.apply(LengthField::Height, style, calc, commands, entity);
         };
     }
 }
@@ -52,14 +51,13 @@ style_property! {
     #[doc = " <!-- @property-category=Size Constraints -->"]
     MinWidthProperty("min-width") {
         Default = "undefined";
-        Item = Val;
-        Components = &'static mut Style;
+        Item = Length;
+        Components = (&'static mut Style, Option<&'static mut CalcLengths>);
         Filters = With<Node>;
-        Parser = parse::ValParser;
-        Apply = |value, style, _assets, _commands, _entity| {
-            if &style.min_width != value {
-                style.min_width = *value;
-            }
+        Parser = parse::LengthParser;
+        Apply = |value, components, _assets, commands, entity| {
+            let (style, calc) = components;
+            value.apply(LengthField::MinWidth, style, calc, commands, entity);
         };
     }
 }
@@ -72,14 +70,13 @@ style_property! {
     #[doc = " <!-- @property-category=Size Constraints -->"]
     MinHeightProperty("min-height") {
         Default = "undefined";
-        Item = Val;
-        Components = &'static mut Style;
+        Item = Length;
+        Components = (&'static mut Style, Option<&'static mut CalcLengths>);
         Filters = With<Node>;
-        Parser = parse::ValParser;
-        Apply = |value, style, _assets, _commands, _entity| {
-            if &style.min_height != value {
-                style.min_height = *value;
-            }
+        Parser = parse::LengthParser;
+        Apply = |value, components, _assets, commands, entity| {
+            let (style, calc) = components;
+            value.apply(LengthField::MinHeight, style, calc, commands, entity);
         };
     }
 }
@@ -93,14 +90,13 @@ style_property! {
     #[doc = " <!-- @property-category=Size Constraints -->"]
     MaxWidthProperty("max-width") {
         Default = "undefined";
-        Item = Val;
-        Components = &'static mut Style;
+        Item = Length;
+        Components = (&'static mut Style, Option<&'static mut CalcLengths>);
         Filters = With<Node>;
-        Parser = parse::ValParser;
-        Apply = |value, style, _assets, _commands, _entity| {
-            if &style.max_width != value {
-                style.max_width = *value;
-            }
+        Parser = parse::LengthParser;
+        Apply = |value, components, _assets, commands, entity| {
+            let (style, calc) = components;
+            value.apply(LengthField::MaxWidth, style, calc, commands, entity);
         };
     }
 }
@@ -113,14 +109,13 @@ style_property! {
     #[doc = " <!-- @property-category=Size Constraints -->"]
     MaxHeightProperty("max-height") {
         Default = "undefined";
-        Item = Val;
-        Components = &'static mut Style;
+        Item = Length;
+        Components = (&'static mut Style, Option<&'static mut CalcLengths>);
         Filters = With<Node>;
-        Parser = parse::ValParser;
-        Apply = |value, style, _assets, _commands, _entity| {
-            if &style.max_height != value {
-                style.max_height = *value;
-            }
+        Parser = parse::LengthParser;
+        Apply = |value, components, _assets, commands, entity| {
+            let (style, calc) = components;
+            value.apply(LengthField::MaxHeight, style, calc, commands, entity);
         };
     }
 }
@@ -1,5 +1,6 @@
 use super::parse;
 use crate::compound_style_property;
+use crate::ess::property::calc::{CalcLengths, Length, LengthField, LengthRect};
 use crate::ess::ToRectMap;
 use crate::style_property;
 use bevy::prelude::*;
@@ -15,7 +16,8 @@ compound_style_property! {
     #[doc = " <!-- @property-type=$rect -->"]
     #[doc = " <!-- @property-category=Spacing -->"]
     MarginProperty("margin", value) {
-        let rect = UiRect::try_from(value)?;
+        Longhands = ["margin-left", "margin-right", "margin-top", "margin-bottom"];
+        let rect = LengthRect::try_from(value)?;
         Ok(rect.to_rect_map("margin-"))
     }
 }
@@ -31,14 +33,13 @@ style_property! {
     #[doc = " <!-- @property-category=Spacing -->"]
     MarginLeftProperty("margin-left") {
         Default = "undefined";
-        Item = Val;
-        Components = &'static mut Style;
+        Item = Length;
+        Components = (&'static mut Style, Option<&'static mut CalcLengths>);
         Filters = With<Node>;
-        Parser = parse::ValParser;
-        Apply = |value, style, _assets, _commands, _entity| {
-            if &style.margin.left != value {
-                style.margin.left = *value;
-            }
+        Parser = parse::LengthParser;
+        Apply = |value, components, _assets, commands, entity| {
+            let (style, calc) = components;
+            value.apply(LengthField::MarginLeft, style, calc, commands, entity);
         };
     }
 }
@@ -54,14 +55,13 @@ style_property! {
     #[doc = " <!-- @property-category=Spacing -->"]
     MarginRightProperty("margin-right") {
         Default = "undefined";
-        Item = Val;
-        Components = &'static mut Style;
+        Item = Length;
+        Components = (&'static mut Style, Option<&'static mut CalcLengths>);
         Filters = With<Node>;
-        Parser = parse::ValParser;
-        Apply = |value, style, _assets, _commands, _entity| {
-            if &style.margin.right != value {
-                style.margin.right = *value;
-            }
+        Parser = parse::LengthParser;
+        Apply = |value, components, _assets, commands, entity| {
+            let (style, calc) = components;
+            value.apply(LengthField::MarginRight, style, calc, commands, entity);
         };
     }
 }
@@ -77,14 +77,13 @@ style_property! {
     #[doc = " <!-- @property-category=Spacing -->"]
     MarginTopProperty("margin-top") {
         Default = "undefined";
-        Item = Val;
-        Components = &'static mut Style;
+        Item = Length;
+        Components = (&'static mut Style, Option<&'static mut CalcLengths>);
         Filters = With<Node>;
-        Parser = parse::ValParser;
-        Apply = |value, style, _assets, _commands, _entity| {
-            if &style.margin.top != value {
-                style.margin.top = *value;
-            }
+        Parser = parse::LengthParser;
+        Apply = |value, components, _assets, commands, entity| {
+            let (style, calc) = components;
+            value.apply(LengthField::MarginTop, style, calc, commands, entity);
         };
     }
 }
@@ -100,14 +99,13 @@ style_property! {
     #[doc = " <!-- @property-category=Spacing -->"]
     MarginBottomProperty("margin-bottom") {
         Default = "undefined";
-        Item = Val;
-        Components = &'static mut Style;
+        Item = Length;
+        Components = (&'static mut Style, Option<&'static mut CalcLengths>);
         Filters = With<Node>;
-        Parser = parse::ValParser;
-        Apply = |value, style, _assets, _commands, _entity| {
-            if &style.margin.bottom != value {
-                style.margin.bottom = *value;
-            }
+        Parser = parse::LengthParser;
+        Apply = |value, components, _assets, commands, entity| {
+            let (style, calc) = components;
+            value.apply(LengthField::MarginBottom, style, calc, commands, entity);
         };
     }
 }
@@ -123,7 +121,8 @@ compound_style_property! {
     #[doc = " <!-- @property-type=$rect -->"]
     #[doc = " <!-- @property-category=Spacing -->"]
     PaddingProperty("padding", value) {
-        let rect = UiRect::try_from(value)?;
+        Longhands = ["padding-left", "padding-right", "padding-top", "padding-bottom"];
+        let rect = LengthRect::try_from(value)?;
         Ok(rect.to_rect_map("padding-"))
     }
 }
@@ -139,14 +138,13 @@ style_property! {
     #[doc = " <!-- @property-category=Spacing -->"]
     PaddingLeftProperty("padding-left") {
         Default = "undefined";
-        Item = Val;
-        Components = &'static mut Style;
+        Item = Length;
+        Components = (&'static mut Style, Option<&'static mut CalcLengths>);
         Filters = With<Node>;
-        Parser = parse::ValParser;
-        Apply = |value, style, _assets, _commands, _entity| {
-            if &style.padding.left != value {
-                style.padding.left = *value;
-            }
+        Parser = parse::LengthParser;
+        Apply = |value, components, _assets, commands, entity| {
+            let (style, calc) = components;
+            value.apply(LengthField::PaddingLeft, style, calc, commands, entity);
         };
     }
 }
@@ -162,14 +160,13 @@ style_property! {
     #[doc = " <!-- @property-category=Spacing -->"]
     PaddingRightProperty("padding-right") {
         Default = "undefined";
-        Item = Val;
-        Components = &'static mut Style;
+        Item = Length;
+        Components = (&'static mut Style, Option<&'static mut CalcLengths>);
         Filters = With<Node>;
-        Parser = parse::ValParser;
-        Apply = |value, style, _assets, _commands, _entity| {
-            if &style.padding.right != value {
-                style.padding.right = *value;
-            }
+        Parser = parse::LengthParser;
+        Apply = |value, components, _assets, commands, entity| {
+            let (style, calc) = components;
+            value.apply(LengthField::PaddingRight, style, calc, commands, entity);
         };
     }
 }
@@ -185,14 +182,13 @@ style_property! {
     #[doc = " <!-- @property-category=Spacing -->"]
     PaddingTopProperty("padding-top") {
         Default = "undefined";
-        Item = Val;
-        Components = &'static mut Style;
+        Item = Length;
+        Components = (&'static mut Style, Option<&'static mut CalcLengths>);
         Filters = With<Node>;
-        Parser = parse::ValParser;
-        Apply = |value, style, _assets, _commands, _entity| {
-            if &style.padding.top != value {
-                style.padding.top = *value;
-            }
+        Parser = parse::LengthParser;
+        Apply = |value, components, _assets, commands, entity| {
+            let (style, calc) = components;
+            value.apply(LengthField::PaddingTop, style, calc, commands, entity);
         };
     }
 }
@@ -208,14 +204,13 @@ style_property! {
     #[doc = " <!-- @property-category=Spacing -->"]
     PaddingBottomProperty("padding-bottom") {
         Default = "undefined";
-        Item = Val;
-        Components = &'static mut Style;
+        Item = Length;
+        Components = (&'static mut Style, Option<&'static mut CalcLengths>);
         Filters = With<Node>;
-        Parser = parse::ValParser;
-        Apply = |value, style, _assets, _commands, _entity| {
-            if &style.padding.bottom != value {
-                style.padding.bottom = *value;
-            }
+        Parser = parse::LengthParser;
+        Apply = |value, components, _assets, commands, entity| {
+            let (style, calc) = components;
+            value.apply(LengthField::PaddingBottom, style, calc, commands, entity);
         };
     }
 }
@@ -230,7 +225,13 @@ compound_style_property! {
     #[doc = " <!-- @property-type=$rect -->"]
     #[doc = " <!-- @property-category=Spacing -->"]
     BorderProperty("border-width", value) {
-        let rect = UiRect::try_from(value)?;
+        Longhands = [
+            "border-width-left",
+            "border-width-right",
+            "border-width-top",
+            "border-width-bottom",
+        ];
+        let rect = LengthRect::try_from(value)?;
         Ok(rect.to_rect_map("border-width-"))
     }
 }
@@ -243,14 +244,13 @@ style_property! {
     #[doc = " <!-- @property-category=Spacing -->"]
     BorderLeftProperty("border-width-left") {
         Default = "undefined";
-        Item = Val;
-        Components = &'static mut Style;
+        Item = Length;
+        Components = (&'static mut Style, Option<&'static mut CalcLengths>);
         Filters = With<Node>;
-        Parser = parse::ValParser;
-        Apply = |value, style, _assets, _commands, _entity| {
-            if &style.border.left != value {
-                style.border.left = *value;
-            }
+        Parser = parse::LengthParser;
+        Apply = |value, components, _assets, commands, entity| {
+            let (style, calc) = components;
+            value.apply(LengthField::BorderLeft, style, calc, commands, entity);
         };
     }
 }
@@ -264,14 +264,13 @@ style_property! {
     #[doc = " <!-- @property-category=Spacing -->"]
     BorderRightProperty("border-width-right") {
         Default = "undefined";
-        Item = Val;
-        Components = &'static mut Style;
+        Item = Length;
+        Components = (&'static mut Style, Option<&'static mut CalcLengths>);
         Filters = With<Node>;
-        Parser = parse::ValParser;
-        Apply = |value, style, _assets, _commands, _entity| {
-            if &style.border.right != value {
-                style.border.right = *value;
-            }
+        Parser = parse::LengthParser;
+        Apply = |value, components, _assets, commands, entity| {
+            let (style, calc) = components;
+            value.apply(LengthField::BorderRight, style, calc, commands, entity);
         };
     }
 }
@@ -284,14 +283,13 @@ style_property! {
     #[doc = " <!-- @property-category=Spacing -->"]
     BorderTopProperty("border-width-top") {
         Default = "undefined";
-        Item = Val;
-        Components = &'static mut Style;
+        Item = Length;
+        Components = (&'static mut Style, Option<&'static mut CalcLengths>);
         Filters = With<Node>;
-        Parser = parse::ValParser;
-        Apply = |value, style, _assets, _commands, _entity| {
-            if &style.border.top != value {
-                style.border.top = *value;
-            }
+        Parser = parse::LengthParser;
+        Apply = |value, components, _assets, commands, entity| {
+            let (style, calc) = components;
+            value.apply(LengthField::BorderTop, style, calc, commands, entity);
         };
     }
 }
@@ -304,14 +302,13 @@ style_property! {
     #[doc = " <!-- @property-category=Spacing -->"]
     BorderBottomProperty("border-width-bottom") {
         Default = "undefined";
-        Item = Val;
-        Components = &'static mut Style;
+        Item = Length;
+        Components = (&'static mut Style, Option<&'static mut CalcLengths>);
         Filters = With<Node>;
-        Parser = parse::ValParser;
-        Apply = |value, style, _assets, _commands, _entity| {
-            if &style.border.bottom != value {
-                style.border.bottom = *value;
-            }
+        Parser = parse::LengthParser;
+        Apply = |value, components, _assets, commands, entity| {
+            let (style, calc) = components;
+            value.apply(LengthField::BorderBottom, style, calc, commands, entity);
         };
     }
 }
@@ -327,14 +324,13 @@ style_property! {
     #[doc = " <!-- @property-category=Spacing -->"]
     ColumnGapProperty("column-gap") {
         Default = "0px";
-        Item = Val;
-        Components = &'static mut Style;
+        Item = Length;
+        Components = (&'static mut Style, Option<&'static mut CalcLengths>);
         Filters = With<Node>;
-        Parser = parse::ValParser;
-        Apply = |value, style, _assets, _commands, _entity| {
-            if &style.column_gap != value {
-                style.column_gap = *value;
-            }
+        Parser = parse::LengthParser;
+        Apply = |value, components, _assets, commands, entity| {
+            let (style, calc) = components;
+            value.apply(LengthField::ColumnGap, style, calc, commands, entity);
         };
     }
 }
@@ -350,14 +346,13 @@ style_property! {
     #[doc = " <!-- @property-category=Spacing -->"]
     RowGapProperty("row-gap") {
         Default = "0px";
-        Item = Val;
-        Components = &'static mut Style;
+        Item = Length;
+        Components = (&'static mut Style, Option<&'static mut CalcLengths>);
         Filters = With<Node>;
-        Parser = parse::ValParser;
-        Apply = |value, style, _assets, _commands, _entity| {
-            if &style.row_gap != value {
-                style.row_gap = *value;
-            }
+        Parser = parse::LengthParser;
+        Apply = |value, components, _assets, commands, entity| {
+            let (style, calc) = components;
+            value.apply(LengthField::RowGap, style, calc, commands, entity);
         };
     }
 }
@@ -2,10 +2,13 @@ use super::parse;
 use crate::compound_style_property;
 use crate::element::Element;
 use crate::eml::Variant;
+use crate::ess::PropertyParser;
 use crate::ess::PropertyValue;
 use crate::ess::StyleProperty;
 use crate::ess::StylePropertyMethods;
+use crate::ess::StylePropertyToken;
 use crate::style_property;
+use crate::ElementsError;
 use bevy::prelude::*;
 use bevy::utils::HashMap;
 use bevy_stylebox::*;
@@ -31,10 +34,18 @@ compound_style_property! {
     #[doc = " ```css"]
     #[doc = "   stylebox: \"background.png\", 16px 12px, 100%, 0px, blue"]
     #[doc = "   stylebox: \"background.png\", 5px 20%"]
+    #[doc = "   stylebox: atlas(\"ui.atlas\", \"panel_blue\"), 16px"]
     #[doc = " ```"]
     #[doc = " <!-- @property-type=source, slice, region, width, modulate -->"]
     #[doc = " <!-- @property-category=Stylebox -->"]
     StyleboxProperty("stylebox", value) {
+        Longhands = [
+            "stylebox-source",
+            "stylebox-slice",
+            "stylebox-width",
+            "stylebox-region",
+            "stylebox-modulate",
+        ];
         let props = match value {
             Variant::String(unparsed) => StyleProperty::try_from(unparsed)?,
             Variant::Style(prop) => prop,
@@ -42,8 +53,8 @@ compound_style_property! {
         };
         let mut stream = props.as_stream();
         let mut result = HashMap::default();
-        if let Some(path) = stream.single() {
-            result.insert(tag!("stylebox-source"), PropertyValue::new(path.option_string()?));
+        if let Some(source) = stream.single() {
+            result.insert(tag!("stylebox-source"), PropertyValue::new(stylebox_source(source)?));
         }
         if let Some(slice) = stream.compound() {
             result.insert(tag!("stylebox-slice"), PropertyValue::new(slice.rect()?));
@@ -61,18 +72,100 @@ compound_style_property! {
     }
 }
 
+/// The source of the stylebox image: the path to the image, the region
+/// of the atlas loaded from the `.atlas` file or the region of the bevy
+/// `TextureAtlas` registered under the name.
+#[derive(Clone, Debug, PartialEq)]
+pub enum StyleboxSource {
+    Image(String),
+    Atlas(String, AtlasRegion),
+    TextureAtlas(String, usize),
+}
+
+fn stylebox_source(tokens: &[StylePropertyToken]) -> Result<Option<StyleboxSource>, ElementsError> {
+    match tokens.first() {
+        Some(StylePropertyToken::Function(func)) if func.name == "atlas" => {
+            let [StylePropertyToken::String(path), region] = &func.args[..] else {
+                return Err(ElementsError::InvalidPropertyValue(format!(
+                    "Expected atlas($string, $string|$num), got `{}`",
+                    tokens.to_string()
+                )));
+            };
+            let region = match region {
+                StylePropertyToken::String(name) => AtlasRegion::Name(name.clone()),
+                other => AtlasRegion::Index(atlas_index(other)?),
+            };
+            Ok(Some(StyleboxSource::Atlas(path.clone(), region)))
+        }
+        Some(StylePropertyToken::Function(func)) if func.name == "texture-atlas" => {
+            let [StylePropertyToken::String(name), index] = &func.args[..] else {
+                return Err(ElementsError::InvalidPropertyValue(format!(
+                    "Expected texture-atlas($string, $num), got `{}`",
+                    tokens.to_string()
+                )));
+            };
+            Ok(Some(StyleboxSource::TextureAtlas(
+                name.clone(),
+                atlas_index(index)?,
+            )))
+        }
+        _ => Ok(tokens.option_string()?.map(StyleboxSource::Image)),
+    }
+}
+
+fn atlas_index(token: &StylePropertyToken) -> Result<usize, ElementsError> {
+    match token {
+        StylePropertyToken::Number(index)
+            if index.to_float() >= 0. && index.to_float().fract() == 0. =>
+        {
+            Ok(index.to_float() as usize)
+        }
+        other => Err(ElementsError::InvalidPropertyValue(format!(
+            "Expected atlas region index, got `{}`",
+            other.to_string()
+        ))),
+    }
+}
+
+/// <!-- @property-type=none|$string|atlas($string, $string|$num)|texture-atlas($string, $num) -->
+/// The source of the stylebox image: `none`, the path to the image, the
+/// region of the atlas referenced by the name or by the index, or the region
+/// of the bevy `TextureAtlas` registered under the name:
+/// ```css
+/// stylebox-source: "panel-blue.png"
+/// stylebox-source: atlas("ui.atlas", "panel_blue")
+/// stylebox-source: atlas("ui.atlas", 3)
+/// stylebox-source: texture-atlas("ui-icons", 3)
+/// ```
+pub struct StyleboxSourceParser;
+impl PropertyParser<Option<StyleboxSource>> for StyleboxSourceParser {
+    fn parse(value: &StyleProperty) -> Result<Option<StyleboxSource>, ElementsError> {
+        stylebox_source(value)
+    }
+}
+
 style_property! {
-    #[doc = " The `stylebox-source` property specifies the path to the image to be used"]
-    #[doc = " as a stylebox. The property accepts `String` values."]
+    #[doc = " The `stylebox-source` property specifies the image to be used as a stylebox."]
+    #[doc = " The property accepts the path to the image or the region of the atlas"]
+    #[doc = " loaded from the `.atlas` file (`atlas(\"ui.atlas\", \"panel_blue\")`)."]
+    #[doc = " The atlas region is resolved automatically, so the atlas can be repacked"]
+    #[doc = " without touching the styles. `stylebox-region` is relative to the atlas region."]
+    #[doc = " The bevy `TextureAtlas` is referenced by the name it is added to the assets with"]
+    #[doc = " (`texture_atlases.set_untracked(\"ui-icons\", atlas)`): `texture-atlas(\"ui-icons\", 3)`."]
+    #[doc = " <!-- @property-type=none|$string|atlas($string, $string|$num)|texture-atlas($string, $num) -->"]
     #[doc = " <!-- @property-category=Stylebox -->"]
     StyleboxSourceProperty("stylebox-source") {
         Default = "none";
-        Item = Option<String>;
+        Item = Option<StyleboxSource>;
         Components = Option<&'static mut Stylebox>;
         Filters = With<Node>;
-        Parser = parse::OptionalStringParser;
+        Parser = StyleboxSourceParser;
         Apply = |value, stylebox, assets, commands, entity| {
-            if value.is_none() || value.as_ref().unwrap().is_empty() {
+            let source = match value {
+                Some(StyleboxSource::Image(path)) if path.is_empty() => None,
+                source => source.as_ref(),
+            };
+            let Some(source) = source else {
                 if stylebox.is_some() {
                     commands.entity(entity)
                         .remove::<Stylebox>()
@@ -80,17 +173,30 @@ style_property! {
                         .remove::<StyleboxSlices>();
                 }
                 return;
-            }
-            let value = value.as_ref().unwrap();
-            let image = assets.load(value);
+            };
+            let (texture, atlas) = match source {
+                StyleboxSource::Image(path) => (assets.load(path), None),
+                StyleboxSource::Atlas(path, region) => (
+                    Handle::<Image>::default(),
+                    Some(StyleboxAtlas::Sprite(assets.load(path), region.clone())),
+                ),
+                StyleboxSource::TextureAtlas(name, index) => (
+                    Handle::<Image>::default(),
+                    Some(StyleboxAtlas::TextureAtlas(assets.get_handle(name.as_str()), *index)),
+                ),
+            };
             if let Some(mut stylebox) = stylebox {
-                if stylebox.texture != image {
-                    stylebox.texture = image;
+                if stylebox.texture != texture {
+                    stylebox.texture = texture;
+                }
+                if stylebox.atlas != atlas {
+                    stylebox.atlas = atlas;
                 }
             } else {
                 commands.add(Element::invalidate_entity(entity));
                 commands.entity(entity).insert(Stylebox {
-                    texture: image,
+                    texture,
+                    atlas,
                     ..default()
                 });
             }
@@ -181,3 +287,134 @@ style_property! {
         };
     }
 }
+
+/// <!-- @property-type=$repeat-mode -->
+/// One or two repeat modes of the stylebox: `stretch`, `repeat`, `round` or `space`.
+/// The single value is applied to both axes, two values specify the horizontal and
+/// the vertical modes.
+pub struct StyleboxRepeatParser;
+impl PropertyParser<StyleboxRepeat> for StyleboxRepeatParser {
+    fn parse(value: &StyleProperty) -> Result<StyleboxRepeat, ElementsError> {
+        let mode = |token: &StylePropertyToken| match token {
+            StylePropertyToken::Identifier(ident) => match ident.as_str() {
+                "stretch" => Ok(RepeatMode::Stretch),
+                "repeat" => Ok(RepeatMode::Repeat),
+                "round" => Ok(RepeatMode::Round),
+                "space" => Ok(RepeatMode::Space),
+                _ => Err(ElementsError::InvalidPropertyValue(format!(
+                    "Expected stretch|repeat|round|space, got `{ident}`"
+                ))),
+            },
+            _ => Err(ElementsError::InvalidPropertyValue(format!(
+                "Expected $repeat-mode, got `{}`",
+                value.to_string()
+            ))),
+        };
+        match value.len() {
+            1 => Ok(StyleboxRepeat::all(mode(&value[0])?)),
+            2 => Ok(StyleboxRepeat::new(mode(&value[0])?, mode(&value[1])?)),
+            _ => Err(ElementsError::InvalidPropertyValue(format!(
+                "Expected one or two $repeat-mode values, got `{}`",
+                value.to_string()
+            ))),
+        }
+    }
+}
+
+style_property! {
+    #[doc = " The `stylebox-repeat` property specifies how the edges and the middle of"]
+    #[doc = " the stylebox fill the space between the corners, like the css"]
+    #[doc = " `border-image-repeat` does:"]
+    #[doc = " - `stretch`: the slices are stretched to fill the space"]
+    #[doc = " - `repeat`: the slices are tiled, the tiles are centered and clipped at the ends"]
+    #[doc = " - `round`: the slices are tiled and rescaled to fit the whole number of tiles"]
+    #[doc = " - `space`: the slices are tiled, the space left is distributed around the tiles"]
+    #[doc = " "]
+    #[doc = " The first value is applied horizontally (top & bottom edges), the second one"]
+    #[doc = " is applied vertically (left & right edges). The middle follows both of them:"]
+    #[doc = " ```css"]
+    #[doc = "   stylebox-repeat: round"]
+    #[doc = "   stylebox-repeat: repeat stretch"]
+    #[doc = " ```"]
+    #[doc = " <!-- @property-type=$repeat-mode -->"]
+    #[doc = " <!-- @property-category=Stylebox -->"]
+    StyleboxRepeatProperty("stylebox-repeat") {
+        Default = "stretch";
+        Item = StyleboxRepeat;
+        Components = &'static mut Stylebox;
+        Filters = With<Node>;
+        Parser = StyleboxRepeatParser;
+        Apply = |value, stylebox, _assets, _commands, _entity| {
+            if stylebox.repeat != *value {
+                stylebox.repeat = *value
+            }
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn parse_stylebox_source() {
+        let p = StyleProperty::from_str("none").unwrap();
+        assert_eq!(StyleboxSourceParser::parse(&p).unwrap(), None);
+
+        let p = StyleProperty::from_str("\"panel.png\"").unwrap();
+        let source = StyleboxSourceParser::parse(&p).unwrap();
+        assert_eq!(source, Some(StyleboxSource::Image("panel.png".into())));
+
+        let p = StyleProperty::from_str("atlas(\"ui.atlas\", \"panel_blue\")").unwrap();
+        let source = StyleboxSourceParser::parse(&p).unwrap();
+        let region = AtlasRegion::Name("panel_blue".into());
+        assert_eq!(
+            source,
+            Some(StyleboxSource::Atlas("ui.atlas".into(), region))
+        );
+
+        let p = StyleProperty::from_str("atlas(\"ui.atlas\", 3)").unwrap();
+        let source = StyleboxSourceParser::parse(&p).unwrap();
+        let region = AtlasRegion::Index(3);
+        assert_eq!(
+            source,
+            Some(StyleboxSource::Atlas("ui.atlas".into(), region))
+        );
+
+        let p = StyleProperty::from_str("texture-atlas(\"ui-icons\", 2)").unwrap();
+        let source = StyleboxSourceParser::parse(&p).unwrap();
+        assert_eq!(
+            source,
+            Some(StyleboxSource::TextureAtlas("ui-icons".into(), 2))
+        );
+
+        for invalid in [
+            "atlas(\"ui.atlas\")",
+            "atlas(\"ui.atlas\", 1.5)",
+            "atlas(ui, 1)",
+            "texture-atlas(\"ui-icons\", \"panel\")",
+            "texture-atlas(\"ui-icons\")",
+        ] {
+            let p = StyleProperty::from_str(invalid).unwrap();
+            assert!(StyleboxSourceParser::parse(&p).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn parse_stylebox_repeat() {
+        let p = StyleProperty::from_str("round").unwrap();
+        let repeat = StyleboxRepeatParser::parse(&p).unwrap();
+        assert_eq!(repeat, StyleboxRepeat::all(RepeatMode::Round));
+
+        let p = StyleProperty::from_str("repeat space").unwrap();
+        let repeat = StyleboxRepeatParser::parse(&p).unwrap();
+        assert_eq!(
+            repeat,
+            StyleboxRepeat::new(RepeatMode::Repeat, RepeatMode::Space)
+        );
+
+        let p = StyleProperty::from_str("tile").unwrap();
+        assert!(StyleboxRepeatParser::parse(&p).is_err());
+    }
+}
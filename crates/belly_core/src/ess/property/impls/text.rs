@@ -1,5 +1,8 @@
 use super::parse;
 use crate::ess::defaults::Defaults;
+use crate::ess::property::text::{
+    LineBox, LineHeight, TextTransform, TransformedText, VerticalAlign, WhiteSpace,
+};
 use crate::ess::PropertyParser;
 use crate::ess::StyleProperty;
 use crate::ess::StylePropertyToken;
@@ -56,6 +59,7 @@ style_property! {
         Components = &'static mut Text;
         Filters = With<Node>;
         AffectsVirtual = true;
+        Inherited = true;
         Parser = FontParser;
         Apply = |value, text, assets, commands, entity| {
             if let FontPath::Custom(path) = value {
@@ -96,6 +100,7 @@ style_property! {
         Components = &'static mut Text;
         Filters = With<Node>;
         AffectsVirtual = true;
+        Inherited = true;
         Parser = parse::ColorParser;
         Apply = |value, text, _assets, _commands, _entity| {
             // TODO: mark it deprecated
@@ -111,11 +116,12 @@ style_property! {
     #[doc = " TODO: write FontSizeProperty description"]
     #[doc = " <!-- @property-category=Text -->"]
     FontSizeProperty("font-size") {
-        Default = "24";
+        Default = "22";
         Item = f32;
         Components = &'static mut Text;
         Filters = With<Node>;
         AffectsVirtual = true;
+        Inherited = true;
         Parser = parse::NumParser;
         Apply = |value, text, _assets, _commands, _entity| {
             text
@@ -125,83 +131,208 @@ style_property! {
         };
     }
 }
-//     /// Applies the `vertical-align` property on [`TextAlignment::vertical`](`TextAlignment`) property of matched [`Text`] components.
-//     #[derive(Default)]
-//     pub(crate) struct VerticalAlignProperty;
-
-//     impl Property for VerticalAlignProperty {
-//         // Using Option since Cache must impl Default, which VerticalAlign doesn't
-//         type Item = Option<VerticalAlign>;
-//         type Components = &'static mut Text;
-//         type Filters = With<Node>;
-
-//         fn name() -> Tag {
-//             tag!("vertical-align")
-//         }
-
-//         fn parse<'a>(values: &StyleProperty) -> Result<Self::Item, ElementsError> {
-//             if let Ok(ident) = values.identifier() {
-//                 match ident {
-//                     "top" => return Ok(Some(VerticalAlign::Top)),
-//                     "center" => return Ok(Some(VerticalAlign::Center)),
-//                     "bottom" => return Ok(Some(VerticalAlign::Bottom)),
-//                     _ => (),
-//                 }
-//             }
-//             Err(ElementsError::InvalidPropertyValue(
-//                 Self::name().to_string(),
-//             ))
-//         }
-
-//         fn apply<'w>(
-//             cache: &Self::Item,
-//             mut components: QueryItem<Self::Components>,
-//             _asset_server: &AssetServer,
-//             _commands: &mut Commands,
-//             _entity: Entity,
-//         ) {
-//             components.alignment.vertical = cache.expect("Should always have a inner value");
-//         }
-//     }
+style_property! {
+    #[doc = " Specify the horizontal alignment of the text lines:"]
+    #[doc = " ```css"]
+    #[doc = " text-align: center;"]
+    #[doc = " ```"]
+    #[doc = " <!-- @property-type=$ident -->"]
+    #[doc = " - `left`: lines are aligned to the left edge of the text"]
+    #[doc = " - `center`: lines are centered"]
+    #[doc = " - `right`: lines are aligned to the right edge of the text"]
+    #[doc = " <!-- @property-category=Text -->"]
+    TextAlignProperty("text-align") {
+        Default = "left";
+        Item = TextAlignment;
+        Components = &'static mut Text;
+        Filters = With<Node>;
+        AffectsVirtual = true;
+        Inherited = true;
+        Parser = parse::IdentifierParser<TextAlignment>;
+        Apply = |value, text, _assets, _commands, _entity| {
+            if &text.alignment != value {
+                text.alignment = *value;
+            }
+        };
+    }
+}
 
-//     /// Applies the `text-align` property on [`TextAlignment::horizontal`](`TextAlignment`) property of matched [`Text`] components.
-//     #[derive(Default)]
-//     pub(crate) struct HorizontalAlignProperty;
+style_property! {
+    #[doc = " Specify the vertical position of the text lines inside the text node:"]
+    #[doc = " ```css"]
+    #[doc = " vertical-align: middle;"]
+    #[doc = " ```"]
+    #[doc = " <!-- @property-type=$ident -->"]
+    #[doc = " - `top`: the lines start at the top of the node"]
+    #[doc = " - `middle`: the lines are centered inside the node"]
+    #[doc = " - `bottom`: the lines end at the bottom of the node"]
+    #[doc = " The alignment is visible only when the text node is taller than its lines,"]
+    #[doc = " e.g. stretched by the parent or with `min-height` defined."]
+    #[doc = " <!-- @property-category=Text -->"]
+    VerticalAlignProperty("vertical-align") {
+        Default = "top";
+        Item = VerticalAlign;
+        Components = Option<&'static mut VerticalAlign>;
+        Filters = (With<Node>, With<Text>);
+        AffectsVirtual = true;
+        Parser = parse::IdentifierParser<VerticalAlign>;
+        Apply = |value, align, _assets, commands, entity| {
+            match (value, align) {
+                (VerticalAlign::Top, None) => { }
+                (value, Some(mut align)) => {
+                    if &*align != value {
+                        *align = *value;
+                    }
+                }
+                (value, None) => {
+                    commands.entity(entity).insert(*value);
+                }
+            }
+        };
+    }
+}
 
-//     impl Property for HorizontalAlignProperty {
-//         // Using Option since Cache must impl Default, which HorizontalAlign doesn't
-//         type Item = Option<HorizontalAlign>;
-//         type Components = &'static mut Text;
-//         type Filters = With<Node>;
+style_property! {
+    #[doc = " Specify how the text lines are wrapped:"]
+    #[doc = " ```css"]
+    #[doc = " white-space: nowrap;"]
+    #[doc = " ```"]
+    #[doc = " <!-- @property-type=$ident -->"]
+    #[doc = " - `normal` or `wrap`: lines are broken at the word boundaries to fit the node"]
+    #[doc = " - `nowrap`: lines are broken at the explicit line breaks only"]
+    #[doc = " <!-- @property-category=Text -->"]
+    WhiteSpaceProperty("white-space") {
+        Default = "normal";
+        Item = WhiteSpace;
+        Components = &'static mut Text;
+        Filters = With<Node>;
+        AffectsVirtual = true;
+        Inherited = true;
+        Parser = parse::IdentifierParser<WhiteSpace>;
+        Apply = |value, text, _assets, _commands, _entity| {
+            let behavior = (*value).into();
+            if text.linebreak_behavior != behavior {
+                text.linebreak_behavior = behavior;
+            }
+        };
+    }
+}
 
-//         fn name() -> Tag {
-//             tag!("text-align")
-//         }
+style_property! {
+    #[doc = " Specify the case of the text content:"]
+    #[doc = " ```css"]
+    #[doc = " text-transform: uppercase;"]
+    #[doc = " ```"]
+    #[doc = " <!-- @property-type=$ident -->"]
+    #[doc = " - `none`: the text is displayed as is"]
+    #[doc = " - `uppercase`: all the characters are converted to uppercase"]
+    #[doc = " - `lowercase`: all the characters are converted to lowercase"]
+    #[doc = " - `capitalize`: the first character of every word is converted to uppercase"]
+    #[doc = " The original content is kept, so the content updated by bindings"]
+    #[doc = " is transformed as well."]
+    #[doc = " <!-- @property-category=Text -->"]
+    TextTransformProperty("text-transform") {
+        Default = "none";
+        Item = TextTransform;
+        Components = Option<&'static mut TransformedText>;
+        Filters = (With<Node>, With<Text>);
+        AffectsVirtual = true;
+        Inherited = true;
+        Parser = parse::IdentifierParser<TextTransform>;
+        Apply = |value, transformed, _assets, commands, entity| {
+            match (value, transformed) {
+                (TextTransform::None, None) => { }
+                (transform, Some(mut component)) => {
+                    if &component.transform() != transform {
+                        component.set_transform(*transform);
+                    }
+                }
+                (transform, None) => {
+                    commands.entity(entity).insert(TransformedText::new(*transform));
+                }
+            }
+        };
+    }
+}
 
-//         fn parse<'a>(values: &StyleProperty) -> Result<Self::Item, ElementsError> {
-//             if let Ok(ident) = values.identifier() {
-//                 match ident {
-//                     "left" => return Ok(Some(HorizontalAlign::Left)),
-//                     "center" => return Ok(Some(HorizontalAlign::Center)),
-//                     "right" => return Ok(Some(HorizontalAlign::Right)),
-//                     _ => (),
-//                 }
-//             }
-//             Err(ElementsError::InvalidPropertyValue(
-//                 Self::name().to_string(),
-//             ))
-//         }
+/// normal|$number|$length|$percent
+pub struct LineHeightParser;
+impl PropertyParser<Option<LineHeight>> for LineHeightParser {
+    fn parse(prop: &StyleProperty) -> Result<Option<LineHeight>, ElementsError> {
+        let Some(token) = prop.first() else {
+            return Err(ElementsError::InvalidPropertyValue(format!(
+                "Expected normal|$number|$length|$percent, got nothing"
+            )));
+        };
+        match token {
+            StylePropertyToken::Identifier(ident) if ident == "normal" => Ok(None),
+            StylePropertyToken::Number(num) if num.to_float() >= 0. => {
+                Ok(Some(LineHeight::Factor(num.to_float())))
+            }
+            StylePropertyToken::Percentage(num) if num.to_float() >= 0. => {
+                Ok(Some(LineHeight::Factor(num.to_float() / 100.)))
+            }
+            StylePropertyToken::Dimension(num, unit) if unit == "px" && num.to_float() >= 0. => {
+                Ok(Some(LineHeight::Px(num.to_float())))
+            }
+            token => Err(ElementsError::InvalidPropertyValue(format!(
+                "Expected normal|$number|$length|$percent, got `{}`",
+                token.to_string()
+            ))),
+        }
+    }
+}
 
-//         fn apply<'w>(
-//             cache: &Self::Item,
-//             mut components: QueryItem<Self::Components>,
-//             _asset_server: &AssetServer,
-//             _commands: &mut Commands,
-//             _entity: Entity,
-//         ) {
-//             components.alignment.horizontal = cache.expect("Should always have a inner value");
-//         }
-//     }
+style_property! {
+    #[doc = " Specify the height of the text lines:"]
+    #[doc = " ```css"]
+    #[doc = " line-height: 1.5;"]
+    #[doc = " line-height: 30px;"]
+    #[doc = " ```"]
+    #[doc = " - `normal`: the line height is defined by the font"]
+    #[doc = " - `$number` or `$percent`: the multiplier of the `font-size`"]
+    #[doc = " - `$length`: the line height in pixels"]
+    #[doc = " The text node reserves the space for its lines with `min-height`,"]
+    #[doc = " the declared `min-height` is kept if it is larger and restored"]
+    #[doc = " when the line height is reset to `normal`."]
+    #[doc = " <!-- @property-category=Text -->"]
+    LineHeightProperty("line-height") {
+        Default = "normal";
+        Item = Option<LineHeight>;
+        Components = (
+            Option<&'static mut LineHeight>,
+            Option<&'static LineBox>,
+            &'static mut Text,
+            &'static mut Style,
+        );
+        Filters = With<Node>;
+        AffectsVirtual = true;
+        Inherited = true;
+        Parser = LineHeightParser;
+        Apply = |value, components, _assets, commands, entity| {
+            let (line_height, line_box, mut text, mut style) = components;
+            match (value, line_height) {
+                (Some(value), Some(mut component)) => {
+                    if &*component != value {
+                        *component = *value;
+                    }
+                }
+                (Some(value), None) => { commands.entity(entity).insert(*value); }
+                (None, Some(_)) => {
+                    commands.entity(entity).remove::<(LineHeight, LineBox)>();
+                    if let Some(line_box) = line_box {
+                        let min_height = line_box.restore(style.min_height);
+                        if style.min_height != min_height {
+                            style.min_height = min_height;
+                        }
+                    }
+                    text.set_changed();
+                }
+                (None, None) => { }
+            }
+        };
+    }
+}
 
 //     /// Apply a custom `text-content` which updates [`TextSection::value`](`TextSection`) of all sections on matched [`Text`] components
 //     #[derive(Default)]
@@ -0,0 +1,294 @@
+use crate::compound_style_property;
+use crate::element::Element;
+use crate::eml::Variant;
+use crate::ess::property::transition::{self, EasingFunction, Transitions};
+use crate::ess::{PropertyParser, PropertyValue, StyleProperty, StylePropertyToken};
+use crate::style_property;
+use crate::ElementsError;
+use bevy::ecs::system::Command;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use tagstr::*;
+
+pub(super) fn split_list(value: &StyleProperty) -> impl Iterator<Item = &[StylePropertyToken]> {
+    value.split(|t| matches!(t, StylePropertyToken::Comma))
+}
+
+fn is_time(token: &StylePropertyToken) -> bool {
+    transition::time(std::slice::from_ref(token)).is_ok()
+}
+
+/// Updates the component of the element, inserting it if missed.
+pub(super) fn update_or_insert<C: Component + Default>(
+    entity: Entity,
+    update: impl FnOnce(&mut C) + Send + 'static,
+) -> impl Command {
+    move |world: &mut World| {
+        let Some(mut entity) = world.get_entity_mut(entity) else {
+            return;
+        };
+        if let Some(mut component) = entity.get_mut::<C>() {
+            update(&mut component);
+        } else {
+            let mut component = C::default();
+            update(&mut component);
+            entity.insert(component);
+            // the properties should be applied once again to
+            // know the values to transition from
+            if let Some(mut element) = entity.get_mut::<Element>() {
+                element.invalidate();
+            }
+        }
+    }
+}
+
+/// <!-- @property-type=none|all|$ident, ... -->
+pub struct TransitionPropertyParser;
+impl PropertyParser<Vec<Tag>> for TransitionPropertyParser {
+    fn parse(value: &StyleProperty) -> Result<Vec<Tag>, ElementsError> {
+        split_list(value)
+            .map(|item| match item {
+                [StylePropertyToken::Identifier(ident)] => Ok(Tag::new(ident.clone())),
+                item => Err(ElementsError::InvalidPropertyValue(format!(
+                    "Expected property name, got `{}`",
+                    item.iter()
+                        .map(|t| t.to_string())
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                ))),
+            })
+            .collect()
+    }
+}
+
+/// <!-- @property-type=$time, ... -->
+/// Time in seconds (`0.5s`) or milliseconds (`200ms`)
+pub struct TimeListParser;
+impl PropertyParser<Vec<f32>> for TimeListParser {
+    fn parse(value: &StyleProperty) -> Result<Vec<f32>, ElementsError> {
+        split_list(value).map(transition::time).collect()
+    }
+}
+
+/// <!-- @property-type=$easing, ... -->
+/// Easing function: `linear`, `ease`, `ease-in`, `ease-out`, `ease-in-out`,
+/// `step-start`, `step-end`, `steps(4, jump-start)` or `cubic-bezier(0.1, 0.7, 1.0, 0.1)`
+pub struct EasingListParser;
+impl PropertyParser<Vec<EasingFunction>> for EasingListParser {
+    fn parse(value: &StyleProperty) -> Result<Vec<EasingFunction>, ElementsError> {
+        split_list(value).map(EasingFunction::try_from).collect()
+    }
+}
+
+compound_style_property! {
+    #[doc = " Makes the changes of the property values smooth. The `transition`"]
+    #[doc = " property is shorthand property for:"]
+    #[doc = " - `transition-property` specifies which properties should be transitioned"]
+    #[doc = " - `transition-duration` specifies how long the transition takes"]
+    #[doc = " - `transition-timing-function` specifies the easing of the transition"]
+    #[doc = " - `transition-delay` specifies when the transition starts"]
+    #[doc = " "]
+    #[doc = " The format of property is comma separated list of:"]
+    #[doc = " ```css"]
+    #[doc = " property duration timing-function delay"]
+    #[doc = " ```"]
+    #[doc = " Every part is optional, the first time value is the duration,"]
+    #[doc = " the second one is the delay."]
+    #[doc = " "]
+    #[doc = " Only the properties with interpolable values are transitioned:"]
+    #[doc = " colors, sizes, rects and numbers. `Val` sizes are transitioned only"]
+    #[doc = " between the values of the same units (`px` to `px`, `%` to `%`)."]
+    #[doc = " "]
+    #[doc = " Example:"]
+    #[doc = " ```css"]
+    #[doc = "   transition: background-color 200ms ease-out"]
+    #[doc = "   transition: width 0.5s, color 1s linear 0.2s"]
+    #[doc = " ```"]
+    #[doc = " <!-- @property-type=[property] [duration] [timing-function] [delay], ... -->"]
+    #[doc = " <!-- @property-category=Transitions -->"]
+    TransitionProperty("transition", value) {
+        Longhands = [
+            "transition-property",
+            "transition-duration",
+            "transition-timing-function",
+            "transition-delay",
+        ];
+        let props = match value {
+            Variant::String(unparsed) => StyleProperty::try_from(unparsed)?,
+            Variant::Style(prop) => prop,
+            v => return Self::error(format!("Don't know how to extract transition from {v:?}"))
+        };
+        let mut properties = vec![];
+        let mut durations = vec![];
+        let mut easings = vec![];
+        let mut delays = vec![];
+        for item in split_list(&props) {
+            let mut property = None;
+            let mut duration = None;
+            let mut easing = None;
+            let mut delay = None;
+            for token in item.iter() {
+                let token_slice = std::slice::from_ref(token);
+                if is_time(token) && duration.is_none() {
+                    duration = Some(transition::time(token_slice)?);
+                } else if is_time(token) && delay.is_none() {
+                    delay = Some(transition::time(token_slice)?);
+                } else if let (None, Ok(ease)) = (easing, EasingFunction::try_from(token_slice)) {
+                    easing = Some(ease);
+                } else if let (None, StylePropertyToken::Identifier(ident)) = (property, token) {
+                    property = Some(Tag::new(ident.clone()));
+                } else {
+                    return Self::error(format!(
+                        "Unexpected `{}` in transition",
+                        token.to_string()
+                    ));
+                }
+            }
+            properties.push(property.unwrap_or(tag!("all")));
+            durations.push(duration.unwrap_or(0.0));
+            easings.push(easing.unwrap_or_default());
+            delays.push(delay.unwrap_or(0.0));
+        }
+        let mut result = HashMap::default();
+        result.insert(tag!("transition-property"), PropertyValue::new(properties));
+        result.insert(tag!("transition-duration"), PropertyValue::new(durations));
+        result.insert(tag!("transition-timing-function"), PropertyValue::new(easings));
+        result.insert(tag!("transition-delay"), PropertyValue::new(delays));
+        Ok(result)
+    }
+}
+
+style_property! {
+    #[doc = " The `transition-property` property specifies the names of the properties"]
+    #[doc = " which changes should be transitioned. `all` matches every property,"]
+    #[doc = " `none` disables the transitions."]
+    #[doc = " <!-- @property-category=Transitions -->"]
+    TransitionPropertyProperty("transition-property") {
+        Default = "all";
+        Item = Vec<Tag>;
+        Components = ();
+        Filters = With<Node>;
+        Parser = TransitionPropertyParser;
+        Apply = |value, _components, _assets, commands, entity| {
+            let value = value.clone();
+            commands.add(update_or_insert(entity, move |t: &mut Transitions| t.properties = value));
+        };
+    }
+}
+
+style_property! {
+    #[doc = " The `transition-duration` property specifies how long the transitions"]
+    #[doc = " of the properties listed by `transition-property` take."]
+    #[doc = " <!-- @property-category=Transitions -->"]
+    TransitionDurationProperty("transition-duration") {
+        Default = "0s";
+        Item = Vec<f32>;
+        Components = ();
+        Filters = With<Node>;
+        Parser = TimeListParser;
+        Apply = |value, _components, _assets, commands, entity| {
+            let value = value.clone();
+            commands.add(update_or_insert(entity, move |t: &mut Transitions| t.durations = value));
+        };
+    }
+}
+
+style_property! {
+    #[doc = " The `transition-timing-function` property specifies how the intermediate"]
+    #[doc = " values of the transitions are calculated."]
+    #[doc = " <!-- @property-category=Transitions -->"]
+    TransitionTimingFunctionProperty("transition-timing-function") {
+        Default = "ease";
+        Item = Vec<EasingFunction>;
+        Components = ();
+        Filters = With<Node>;
+        Parser = EasingListParser;
+        Apply = |value, _components, _assets, commands, entity| {
+            let value = value.clone();
+            commands.add(update_or_insert(entity, move |t: &mut Transitions| t.easings = value));
+        };
+    }
+}
+
+style_property! {
+    #[doc = " The `transition-delay` property specifies how long to wait before"]
+    #[doc = " the transitions start."]
+    #[doc = " <!-- @property-category=Transitions -->"]
+    TransitionDelayProperty("transition-delay") {
+        Default = "0s";
+        Item = Vec<f32>;
+        Components = ();
+        Filters = With<Node>;
+        Parser = TimeListParser;
+        Apply = |value, _components, _assets, commands, entity| {
+            let value = value.clone();
+            commands.add(update_or_insert(entity, move |t: &mut Transitions| t.delays = value));
+        };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ess::CompoundProperty;
+
+    fn extract(value: &str) -> HashMap<Tag, PropertyValue> {
+        TransitionProperty::extract(Variant::String(value.to_string())).unwrap()
+    }
+
+    fn get<T: Clone + 'static>(props: &HashMap<Tag, PropertyValue>, name: Tag) -> T {
+        props
+            .get(&name)
+            .unwrap()
+            .downcast_ref::<T>()
+            .unwrap()
+            .clone()
+    }
+
+    #[test]
+    fn extract_transition() {
+        let props = extract("background-color 200ms ease-out, width 1s linear 0.5s, 2s");
+        assert_eq!(
+            get::<Vec<Tag>>(&props, tag!("transition-property")),
+            vec![tag!("background-color"), tag!("width"), tag!("all")]
+        );
+        assert_eq!(
+            get::<Vec<f32>>(&props, tag!("transition-duration")),
+            vec![0.2, 1.0, 2.0]
+        );
+        assert_eq!(
+            get::<Vec<EasingFunction>>(&props, tag!("transition-timing-function")),
+            vec![
+                EasingFunction::EaseOut,
+                EasingFunction::Linear,
+                EasingFunction::Ease
+            ]
+        );
+        assert_eq!(
+            get::<Vec<f32>>(&props, tag!("transition-delay")),
+            vec![0.0, 0.5, 0.0]
+        );
+        assert!(TransitionProperty::extract(Variant::String("color red".into())).is_err());
+    }
+
+    #[test]
+    fn parse_transition_lists() {
+        let prop = |v: &str| StyleProperty::try_from(v).unwrap();
+        assert_eq!(
+            TransitionPropertyParser::parse(&prop("color, width")),
+            Ok(vec![tag!("color"), tag!("width")])
+        );
+        assert_eq!(
+            TimeListParser::parse(&prop("100ms, 2s")),
+            Ok(vec![0.1, 2.0])
+        );
+        assert_eq!(
+            EasingListParser::parse(&prop("ease-in, steps(3)")),
+            Ok(vec![
+                EasingFunction::EaseIn,
+                EasingFunction::Steps(3, false)
+            ])
+        );
+        assert!(TimeListParser::parse(&prop("100px")).is_err());
+    }
+}
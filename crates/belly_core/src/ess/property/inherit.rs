@@ -0,0 +1,78 @@
+use crate::{
+    eml::Variant,
+    ess::{StyleProperty, StylePropertyToken},
+};
+
+/// The keywords accepted by every registered property:
+/// - `inherit` takes the computed value of the parent element
+/// - `initial` takes the initial (default) value of the property
+/// - `unset` works as `inherit` for inherited properties
+///   and as `initial` for the rest
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CssWideKeyword {
+    Inherit,
+    Initial,
+    Unset,
+}
+
+impl CssWideKeyword {
+    pub fn from_ident(ident: &str) -> Option<CssWideKeyword> {
+        match ident {
+            "inherit" => Some(CssWideKeyword::Inherit),
+            "initial" => Some(CssWideKeyword::Initial),
+            "unset" => Some(CssWideKeyword::Unset),
+            _ => None,
+        }
+    }
+
+    pub fn from_property(property: &StyleProperty) -> Option<CssWideKeyword> {
+        match property.as_slice() {
+            [StylePropertyToken::Identifier(ident)] => CssWideKeyword::from_ident(ident),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn from_variant(variant: &Variant) -> Option<CssWideKeyword> {
+        match variant {
+            Variant::Style(property) => CssWideKeyword::from_property(property),
+            Variant::String(value) => CssWideKeyword::from_ident(value.trim()),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the value should be taken from the parent element
+    pub fn inherits(&self, inherited_property: bool) -> bool {
+        match self {
+            CssWideKeyword::Inherit => true,
+            CssWideKeyword::Initial => false,
+            CssWideKeyword::Unset => inherited_property,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_css_wide_keywords() {
+        let style = |value: &str| Variant::Style(StyleProperty::try_from(value).unwrap());
+        assert_eq!(
+            CssWideKeyword::from_variant(&style("inherit")),
+            Some(CssWideKeyword::Inherit)
+        );
+        assert_eq!(
+            CssWideKeyword::from_variant(&Variant::String(" initial ".to_string())),
+            Some(CssWideKeyword::Initial)
+        );
+        assert_eq!(
+            CssWideKeyword::from_variant(&style("unset")),
+            Some(CssWideKeyword::Unset)
+        );
+        assert_eq!(CssWideKeyword::from_variant(&style("inherit red")), None);
+        assert_eq!(CssWideKeyword::from_variant(&style("\"inherit\"")), None);
+        assert!(CssWideKeyword::Unset.inherits(true));
+        assert!(!CssWideKeyword::Unset.inherits(false));
+        assert!(!CssWideKeyword::Initial.inherits(true));
+    }
+}
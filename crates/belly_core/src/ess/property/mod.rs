@@ -1,29 +1,71 @@
+pub mod animation;
+pub mod border;
+pub mod calc;
 pub mod colors;
+mod custom;
 pub mod enums;
+pub mod gradient;
 pub mod impls;
+mod inherit;
+pub mod opacity;
 pub mod parse;
+pub mod shadow;
 mod style;
+pub mod text;
+pub mod transform;
+pub mod transition;
 use std::any::{type_name, Any};
+use std::cell::OnceCell;
+use std::rc::Rc;
 use std::sync::{Arc, RwLock};
 
+pub use self::animation::AnimationEvent;
+pub use self::animation::AnimationEventKind;
+pub use self::animation::Animations;
+pub use self::animation::Keyframe;
+pub use self::animation::Keyframes;
+pub use self::border::BorderColors;
+pub use self::calc::CalcExpr;
+pub use self::calc::CalcLengths;
+pub use self::calc::Length;
+pub use self::calc::LengthRect;
 pub use self::colors::*;
+pub use self::custom::*;
+pub use self::gradient::BackgroundGradient;
+pub use self::gradient::Gradient;
+pub use self::inherit::CssWideKeyword;
+pub use self::opacity::Opacity;
+pub use self::shadow::BoxShadow;
+pub use self::shadow::BoxShadows;
 pub use self::style::StyleProperty;
 pub use self::style::StylePropertyMethods;
 pub use self::style::StylePropertyToken;
 pub use self::style::StylePropertyFunction;
 pub use self::style::ToRectMap;
+pub use self::text::LineHeight;
+pub use self::text::TextTransform;
+pub use self::text::VerticalAlign;
+pub use self::text::WhiteSpace;
+pub use self::transform::ElementTransform;
+pub use self::transform::TransformOrigin;
+pub use self::transform::UiTransform;
+pub use self::transition::EasingFunction;
+pub use self::transition::Interpolate;
+pub use self::transition::PropertyTransitions;
+pub use self::transition::Transitions;
 use crate::tags::*;
 use crate::{
     element::*,
     eml::Variant,
-    ess::{ElementsBranch, StyleSheet, Styles},
+    ess::{ElementsBranch, Media, RuleIndex, StyleSheet, Styles},
     ElementsError,
 };
-use bevy::ui::UiSystem;
+use bevy::render::{Render, RenderApp, RenderSet};
+use bevy::ui::{RenderUiSystem, UiSystem};
 use bevy::{
     ecs::query::{QueryItem, ReadOnlyWorldQuery, WorldQuery},
     prelude::*,
-    utils::HashMap,
+    utils::{HashMap, HashSet},
 };
 use itertools::Itertools;
 
@@ -32,7 +74,75 @@ impl Plugin for PropertyPlugin {
     fn build(&self, app: &mut App) {
         // general
         app.register_property::<impls::BackgroundColorProperty>();
+        app.register_property::<impls::BackgroundImageProperty>();
         app.register_property::<impls::ZIndexProperty>();
+        app.register_property::<impls::OpacityProperty>();
+        app.register_property::<impls::VisibilityProperty>();
+        app.register_property::<impls::BoxShadowProperty>();
+        app.register_property::<impls::CursorProperty>();
+
+        // gradients
+        app.init_resource::<gradient::GradientImages>();
+        app.add_systems(
+            PostUpdate,
+            gradient::update_gradient_images.after(UiSystem::Layout),
+        );
+
+        // transforms
+        app.register_property::<impls::TransformProperty>();
+        app.register_property::<impls::TransformOriginProperty>();
+        app.add_systems(
+            PostUpdate,
+            transform::apply_element_transforms
+                .after(UiSystem::Layout)
+                .before(bevy::transform::TransformSystem::TransformPropagate),
+        );
+
+        // border colors
+        app.register_compound_property::<impls::BorderColorProperty>();
+        app.register_property::<impls::BorderLeftColorProperty>();
+        app.register_property::<impls::BorderRightColorProperty>();
+        app.register_property::<impls::BorderTopColorProperty>();
+        app.register_property::<impls::BorderBottomColorProperty>();
+        app.add_systems(
+            PostUpdate,
+            border::update_border_colors
+                .after(ApplyStyleProperties)
+                .before(UiSystem::Layout),
+        );
+
+        // rendering: gradients, borders, shadows and opacity
+        if let Ok(render_app) = app.get_sub_app_mut(RenderApp) {
+            // gradients are drawn right after the background colors,
+            // the borders are drawn over the gradients and the styleboxes
+            render_app.add_systems(
+                ExtractSchedule,
+                (
+                    gradient::extract_gradients
+                        .after(RenderUiSystem::ExtractNode)
+                        .before(RenderUiSystem::ExtractAtlasNode)
+                        .before(bevy_stylebox::extract_stylebox),
+                    border::extract_border_colors
+                        .after(RenderUiSystem::ExtractNode)
+                        .after(gradient::extract_gradients)
+                        .after(bevy_stylebox::extract_stylebox),
+                ),
+            );
+            // shadows and opacity are applied when all the ui nodes are extracted
+            render_app
+                .init_resource::<shadow::ExtractedBoxShadows>()
+                .init_resource::<opacity::ExtractedOpacity>()
+                .add_systems(
+                    ExtractSchedule,
+                    (shadow::extract_box_shadows, opacity::extract_opacity),
+                )
+                .add_systems(
+                    Render,
+                    (shadow::apply_box_shadows, opacity::apply_opacity)
+                        .chain()
+                        .in_set(RenderSet::ExtractCommands),
+                );
+        }
 
         // layout control
         app.register_compound_property::<impls::layout_control::PositionProperty>();
@@ -89,6 +199,21 @@ impl Plugin for PropertyPlugin {
         app.register_property::<impls::text::ColorProperty>();
         app.register_property::<impls::text::FontProperty>();
         app.register_property::<impls::text::FontSizeProperty>();
+        app.register_property::<impls::text::TextAlignProperty>();
+        app.register_property::<impls::text::WhiteSpaceProperty>();
+        app.register_property::<impls::text::TextTransformProperty>();
+        app.register_property::<impls::text::LineHeightProperty>();
+        app.register_property::<impls::text::VerticalAlignProperty>();
+        app.add_systems(
+            PostUpdate,
+            (
+                text::transform_text
+                    .after(ApplyStyleProperties)
+                    .before(bevy::ui::widget::measure_text_system),
+                text::apply_line_height.after(bevy::ui::widget::text_system),
+                text::apply_vertical_align.after(text::apply_line_height),
+            ),
+        );
 
         // stylebox
         app.register_compound_property::<impls::stylebox::StyleboxProperty>();
@@ -97,6 +222,7 @@ impl Plugin for PropertyPlugin {
         app.register_property::<impls::stylebox::StyleboxRegionProperty>();
         app.register_property::<impls::stylebox::StyleboxSliceProperty>();
         app.register_property::<impls::stylebox::StyleboxWidthProperty>();
+        app.register_property::<impls::stylebox::StyleboxRepeatProperty>();
 
         // grid
         app.register_property::<impls::grid::GridAutoColumnsProperty>();
@@ -108,7 +234,46 @@ impl Plugin for PropertyPlugin {
         app.register_property::<impls::grid::GridAutoFlowProperty>();
         app.register_property::<impls::grid::JustifyItemsProperty>();
         app.register_property::<impls::grid::JustifySelfProperty>();
-        
+
+        // transitions
+        app.register_compound_property::<impls::transition::TransitionProperty>();
+        app.register_property::<impls::transition::TransitionPropertyProperty>();
+        app.register_property::<impls::transition::TransitionDurationProperty>();
+        app.register_property::<impls::transition::TransitionTimingFunctionProperty>();
+        app.register_property::<impls::transition::TransitionDelayProperty>();
+
+        // animations
+        app.add_event::<AnimationEvent>();
+        app.register_compound_property::<impls::animation::AnimationProperty>();
+        app.register_property::<impls::animation::AnimationNameProperty>();
+        app.register_property::<impls::animation::AnimationDurationProperty>();
+        app.register_property::<impls::animation::AnimationTimingFunctionProperty>();
+        app.register_property::<impls::animation::AnimationDelayProperty>();
+        app.register_property::<impls::animation::AnimationIterationCountProperty>();
+        app.register_property::<impls::animation::AnimationDirectionProperty>();
+        app.register_property::<impls::animation::AnimationFillModeProperty>();
+        app.add_systems(
+            PostUpdate,
+            animation::advance_animations
+                .in_set(InvalidateElements)
+                .before(UiSystem::Layout),
+        );
+
+        // math functions
+        app.add_systems(
+            PostUpdate,
+            calc::evaluate_lengths
+                .after(ApplyStyleProperties)
+                .before(UiSystem::Layout),
+        );
+
+        // custom properties
+        app.add_systems(
+            PostUpdate,
+            custom::invalidate_custom_properties
+                .in_set(InvalidateElements)
+                .before(UiSystem::Layout),
+        );
     }
 }
 
@@ -215,6 +380,18 @@ pub trait Property: Default + Sized + Send + Sync + 'static {
         false
     }
 
+    /// Inherited properties take the computed value of the parent element
+    /// when the element doesn't define the property by itself.
+    fn inherited() -> bool {
+        false
+    }
+
+    /// The value applied by the `initial` keyword (and by `unset` for
+    /// the properties which are not inherited).
+    fn initial() -> Self::Item {
+        Self::Item::default()
+    }
+
     fn docstring() -> &'static str {
         ""
     }
@@ -226,6 +403,9 @@ pub trait Property: Default + Sized + Send + Sync + 'static {
     // fn parse(values: &StyleProperty) -> Result<Self::Item, ElementsError>;
 
     fn transform(variant: Variant) -> Result<PropertyValue, ElementsError> {
+        if let Some(keyword) = CssWideKeyword::from_variant(&variant) {
+            return Ok(PropertyValue::new(keyword));
+        }
         match variant {
             Variant::Style(p) => Self::Parser::parse(&p).map(|p| PropertyValue::new(p)),
             Variant::String(s) => StyleProperty::try_from(s)
@@ -249,6 +429,14 @@ pub trait Property: Default + Sized + Send + Sync + 'static {
         }
     }
 
+    /// Returns the value between `from` (`t == 0.0`) and `to` (`t == 1.0`) used by `transition`.
+    /// Properties with values which can't be interpolated are applied immediately.
+    ///
+    /// The default implementation handles `f32`, `Color`, `Val`, `Length` and `UiRect` items.
+    fn interpolate(from: &Self::Item, to: &Self::Item, t: f32) -> Option<Self::Item> {
+        transition::interpolate(from, to, t)
+    }
+
     /// Applies on the given [`Components`](Property::Components) the [`Cache`](Property::Cache) value.
     /// Additionally, an [`AssetServer`] and [`Commands`] parameters are provided for more complex use cases.
     ///
@@ -267,83 +455,81 @@ pub trait Property: Default + Sized + Send + Sync + 'static {
     /// The default implementation will cover most use cases, by just implementing [`apply`](Property::apply)
     fn apply_defaults(
         // mut cached_properties: Local<CachedProperties<Self>>,
-        mut components: Query<(Entity, Self::Components), (Changed<Element>, Self::Filters)>,
+        mut components: Query<Self::Components, Self::Filters>,
+        changed: Query<Entity, Changed<Element>>,
         mut commands: Commands,
         asset_server: Res<AssetServer>,
         styles: Res<Styles>,
         stylesheets: Res<Assets<StyleSheet>>,
+        media: Res<Media>,
+        index: Res<RuleIndex>,
+        extractor: Res<PropertyExtractor>,
+        mut transitions: ResMut<PropertyTransitions<Self>>,
+        transition_settings: Query<&Transitions>,
         parents: Query<&Parent>,
+        children: Query<&Children>,
         elements: Query<&Element>,
     ) {
-        if components.is_empty() {
+        if changed.is_empty() {
             return;
         }
-        // info!("[prop] changed {}", components.iter().count());
-        // TODO: this should be cached
-        let mut rules: Vec<_> = styles
-            .iter()
-            .filter_map(|h| stylesheets.get(h))
-            .flat_map(|s| s.iter())
-            .filter(|r| r.properties.contains_key(&Self::name()))
-            .collect();
-        rules.sort_by_key(|r| -r.selector.weight);
-
-        for (entity, components) in components.iter_mut() {
-            let Ok(element) = elements.get(entity) else { continue };
-            if element.is_virtual() && !Self::affects_virtual_elements() {
-                continue;
-            }
+        // info!("[prop] changed {}", changed.iter().count());
+        let variable_rules = OnceCell::new();
 
-            // extract default value
-            let mut element_with_default = element;
-            let mut entity_with_default = entity;
-            let mut default = None;
+        // the styles defined by the element itself, virtual elements
+        // take the styles of the closest non-virtual ancestor
+        let own_styles = |entity: Entity| {
+            let mut entity = entity;
             loop {
-                if !element_with_default.is_virtual() {
-                    default = element_with_default.styles.get(&Self::name());
-                    break;
-                }
-                if let Ok(parent) = parents.get(entity_with_default) {
-                    entity_with_default = parent.get();
-                    if let Ok(element) = elements.get(entity_with_default) {
-                        element_with_default = element;
-                    } else {
-                        break;
-                    }
-                } else {
-                    break;
-                }
-            }
-            if default.is_some() && default.unwrap().is_managed() {
-                continue;
-            }
-
-            // compute branch
-            let mut branch = ElementsBranch::new();
-            let mut tail = entity;
-            while let Ok(element) = elements.get(tail) {
+                let element = elements.get(entity).ok()?;
                 if !element.is_virtual() {
-                    branch.insert(element);
-                }
-                if let Ok(parent) = parents.get(tail) {
-                    tail = parent.get();
-                } else {
-                    break;
+                    return Some(&element.styles);
                 }
+                entity = parents.get(entity).ok()?.get();
             }
-            let property = default.or_else(|| {
-                rules
-                    .iter()
-                    .filter_map(|r| {
-                        if let Some(depth) = r.selector.match_depth(&branch) {
-                            Some((
-                                r.properties.get(&Self::name()).unwrap(),
-                                depth,
-                                r.selector.weight,
-                            ))
-                        } else {
-                            None
-                        }
+        };
+
+        // the value defined by the element styles or by the matched rules
+        let declared = |entity: Entity| {
+            // the rules are matched by RuleIndex, the branch is required
+            // only to collect custom properties if some value refers them
+            let branch = OnceCell::new();
+            let variables = OnceCell::new();
+            let resolve = |unresolved: &UnresolvedProperty| {
+                let variables = variables.get_or_init(|| {
+                    let branch = branch.get_or_init(|| {
+                        ElementsBranch::build(
+                            entity,
+                            true,
+                            |e| parents.get(e).ok().map(|p| p.get()),
+                            |e| children.get(e).ok(),
+                            |e| elements.get(e).ok(),
+                        )
+                    });
+                    let rules = variable_rules
+                        .get_or_init(|| index.variable_rules(&styles, &stylesheets, &media));
+                    collect_variables(rules, branch)
+                });
+                match unresolved.resolve(Self::name(), variables, Self::transform, &extractor) {
+                    Ok(value) => value,
+                    Err(e) if unresolved.compound => {
+                        debug!("Unable to resolve {} property: {}", unresolved.name, e);
+                        None
+                    }
+                    Err(e) => {
+                        warn!("Unable to resolve {} property: {}", Self::name(), e);
+                        None
+                    }
+                }
+            };
+
+            let default = own_styles(entity).and_then(|s| find_property(Self::name(), s, &resolve));
+            default.or_else(|| {
+                index
+                    .matched_rules(entity, &stylesheets)
+                    .filter_map(|(r, depth)| {
+                        let value = find_property(Self::name(), &r.properties, &resolve)?;
+                        Some((value, depth, r.selector.weight))
                     })
                     .group_by(|(_prop, _depth, weight)| *weight)
                     .into_iter()
@@ -355,21 +541,193 @@ pub trait Property: Default + Sized + Send + Sync + 'static {
                         let (value, _depth, _weight) = variants.pop().unwrap();
                         value
                     })
-            });
+            })
+        };
+
+        let initial = || Rc::new(ResolvedProperty::Owned(PropertyValue::new(Self::initial())));
+        // the initial value taken when no ancestor declares the property,
+        // it is compared by pointer to tell it from the declared values
+        let undeclared = initial();
+        let is_managed = |entity: Entity| {
+            own_styles(entity)
+                .and_then(|s| s.get(&Self::name()))
+                .map(|v| v.is_managed())
+                .unwrap_or(false)
+        };
+
+        // the value computed for the element: the declared value with
+        // `inherit`, `initial` & `unset` keywords resolved, or the value
+        // inherited from the parent if nothing is declared
+        let compute = |entity: Entity, inherited: &dyn Fn() -> Option<Rc<_>>| {
+            let value = declared(entity);
+            let keyword = value
+                .as_ref()
+                .and_then(|v| v.downcast_ref::<CssWideKeyword>())
+                .copied();
+            match keyword {
+                Some(keyword) if keyword.inherits(Self::inherited()) => inherited(),
+                Some(_) => Some(initial()),
+                None if value.is_some() => value.map(Rc::new),
+                None if Self::inherited() => inherited(),
+                None => None,
+            }
+        };
 
-            if let Some(property) = property {
+        // the value inherited from the parent when it isn't passed down from
+        // the ancestor being updated. The entities without `Element` are skipped,
+        // the root elements inherit the initial value.
+        let inherited = |entity: Entity| {
+            let mut entity = entity;
+            loop {
+                let Ok(parent) = parents.get(entity).map(|p| p.get()) else {
+                    return Some(undeclared.clone());
+                };
+                if !elements.contains(parent) {
+                    entity = parent;
+                    continue;
+                }
+                if is_managed(parent) {
+                    // the value of the managed property is unknown
+                    return None;
+                }
+                let value = declared(parent);
+                let keyword = value
+                    .as_ref()
+                    .and_then(|v| v.downcast_ref::<CssWideKeyword>())
+                    .copied();
+                match keyword {
+                    Some(keyword) if keyword.inherits(Self::inherited()) => {}
+                    Some(_) => return Some(initial()),
+                    None if value.is_some() => return value.map(Rc::new),
+                    None if Self::inherited() => {}
+                    None => return None,
+                }
+                entity = parent;
+            }
+        };
+
+        // the values are computed top-down starting from the changed elements
+        // without changed ancestors, so the inherited value flows down to the
+        // descendants in a single pass
+        let changed: HashSet<Entity> = changed.iter().collect();
+        let mut stack: Vec<(Entity, OnceCell<Option<Rc<ResolvedProperty>>>)> = changed
+            .iter()
+            .copied()
+            .filter(|entity| {
+                if !Self::inherited() {
+                    return true;
+                }
+                let mut entity = *entity;
+                while let Ok(parent) = parents.get(entity) {
+                    entity = parent.get();
+                    if changed.contains(&entity) {
+                        return false;
+                    }
+                }
+                true
+            })
+            .map(|entity| (entity, OnceCell::new()))
+            .collect();
+
+        while let Some((entity, from_parent)) = stack.pop() {
+            let Ok(element) = elements.get(entity) else {
+                // the entities without `Element` pass the inherited value through
+                if let Ok(chs) = children.get(entity) {
+                    let value = from_parent.get_or_init(|| inherited(entity)).clone();
+                    stack.extend(chs.iter().map(|c| (*c, OnceCell::from(value.clone()))));
+                }
+                continue;
+            };
+            let managed = is_managed(entity);
+            let value = if managed {
+                None
+            } else {
+                compute(entity, &|| {
+                    from_parent.get_or_init(|| inherited(entity)).clone()
+                })
+            };
+            if Self::inherited() {
+                if let Ok(chs) = children.get(entity) {
+                    stack.extend(chs.iter().map(|c| (*c, OnceCell::from(value.clone()))));
+                }
+            }
+            if managed || element.is_virtual() && !Self::affects_virtual_elements() {
+                continue;
+            }
+            let Ok(components) = components.get_mut(entity) else {
+                continue;
+            };
+            if let Some(property) = value {
+                // nothing declared the property: only the elements take the initial
+                // value, the virtual ones (like text) keep the value set from code
+                if element.is_virtual() && Rc::ptr_eq(&property, &undeclared) {
+                    continue;
+                }
                 if let Some(property) = property.downcast_ref::<Self::Item>() {
+                    if let Ok(settings) = transition_settings.get(entity) {
+                        let spec = settings.get(Self::name());
+                        if transitions.start(entity, property, spec) {
+                            continue;
+                        }
+                    } else {
+                        transitions.remove(entity);
+                    }
                     Self::apply(property, components, &asset_server, &mut commands, entity);
                 } else {
                     error!(
                         "Unable to apply {} property: inconsistent Variant {:?}",
                         Self::name(),
-                        property
+                        **property
                     );
                 }
             }
         }
     }
+
+    /// The [`system`](https://docs.rs/bevy_ecs/0.8.1/bevy_ecs/system/index.html) which advances
+    /// the running transitions of the property and applies the intermediate values.
+    fn apply_transitions(
+        mut transitions: ResMut<PropertyTransitions<Self>>,
+        mut components: Query<Self::Components, Self::Filters>,
+        mut removed: RemovedComponents<Element>,
+        mut commands: Commands,
+        asset_server: Res<AssetServer>,
+        time: Res<Time>,
+    ) {
+        for entity in removed.iter() {
+            transitions.remove(entity);
+        }
+        if !transitions.is_running() {
+            return;
+        }
+        for (entity, value) in transitions.advance(time.delta_seconds()) {
+            if let Ok(components) = components.get_mut(entity) {
+                Self::apply(&value, components, &asset_server, &mut commands, entity);
+            }
+        }
+    }
+
+    /// The [`system`](https://docs.rs/bevy_ecs/0.8.1/bevy_ecs/system/index.html) which applies
+    /// the values of the property from `@keyframes` played by the element [`Animations`].
+    fn apply_animations(
+        mut components: Query<(Entity, &Animations, Self::Components), Self::Filters>,
+        mut commands: Commands,
+        asset_server: Res<AssetServer>,
+    ) {
+        for (entity, animations, components) in components.iter_mut() {
+            // the later animation wins if multiple animations affect the property
+            let value = animations
+                .playing()
+                .filter_map(|(spec, elapsed, keyframes)| {
+                    let progress = spec.fill_progress(elapsed)?;
+                    keyframes.value::<Self>(progress, spec.easing)
+                })
+                .last();
+            if let Some(value) = value {
+                Self::apply(&value, components, &asset_server, &mut commands, entity);
+            }
+        }
+    }
 }
 
 pub trait CompoundProperty: Default + Sized + Send + Sync + 'static {
@@ -377,6 +735,9 @@ pub trait CompoundProperty: Default + Sized + Send + Sync + 'static {
     fn docstring() -> &'static str {
         ""
     }
+    /// The longhand properties the compound property is extracted into,
+    /// the css-wide keywords are applied to each of them.
+    fn longhands() -> &'static [&'static str];
     fn extract(value: Variant) -> Result<HashMap<Tag, PropertyValue>, ElementsError>;
     fn error(message: String) -> Result<HashMap<Tag, PropertyValue>, ElementsError> {
         Err(ElementsError::InvalidPropertyValue(message))
@@ -398,23 +759,32 @@ impl PropertyTransformer {
         name: Tag,
         value: Variant,
     ) -> Result<PropertyValue, ElementsError> {
-        self.0
+        if is_custom_property(name) {
+            return CustomProperty::transform(value);
+        }
+        let transform = self
+            .0
             .read()
             .unwrap()
             .get(&name)
-            .ok_or(ElementsError::UnsupportedProperty(name.to_string()))
-            .and_then(|transform| transform(value))
+            .copied()
+            .ok_or(ElementsError::UnsupportedProperty(name.to_string()))?;
+        if let Some(unresolved) = UnresolvedProperty::defer(name, false, &value) {
+            return Ok(PropertyValue::new(unresolved));
+        }
+        transform(value)
     }
 }
 
 pub(crate) type ExtractProperty = fn(Variant) -> Result<HashMap<Tag, PropertyValue>, ElementsError>;
+pub(crate) type Longhands = &'static [&'static str];
 #[derive(Default, Clone, Resource)]
-pub struct PropertyExtractor(Arc<RwLock<HashMap<Tag, ExtractProperty>>>);
+pub struct PropertyExtractor(Arc<RwLock<HashMap<Tag, (ExtractProperty, Longhands)>>>);
 unsafe impl Send for PropertyExtractor {}
 unsafe impl Sync for PropertyExtractor {}
 impl PropertyExtractor {
     #[cfg(test)]
-    pub(crate) fn new(rules: HashMap<Tag, ExtractProperty>) -> PropertyExtractor {
+    pub(crate) fn new(rules: HashMap<Tag, (ExtractProperty, Longhands)>) -> PropertyExtractor {
         PropertyExtractor(Arc::new(RwLock::new(rules)))
     }
     pub(crate) fn is_compound_property(&self, name: Tag) -> bool {
@@ -426,12 +796,25 @@ impl PropertyExtractor {
         name: Tag,
         value: Variant,
     ) -> Result<HashMap<Tag, PropertyValue>, ElementsError> {
-        self.0
+        let (extract, longhands) = self
+            .0
             .read()
             .unwrap()
             .get(&name)
-            .ok_or(ElementsError::UnsupportedProperty(name.to_string()))
-            .and_then(|extractor| extractor(value))
+            .copied()
+            .ok_or(ElementsError::UnsupportedProperty(name.to_string()))?;
+        if let Some(keyword) = CssWideKeyword::from_variant(&value) {
+            return Ok(longhands
+                .iter()
+                .map(|longhand| (Tag::new(longhand), PropertyValue::new(keyword)))
+                .collect());
+        }
+        if let Some(unresolved) = UnresolvedProperty::defer(name, true, &value) {
+            let mut properties = HashMap::default();
+            properties.insert(name, PropertyValue::new(unresolved));
+            return Ok(properties);
+        }
+        extract(value)
     }
 }
 
@@ -450,12 +833,23 @@ impl RegisterProperty for bevy::prelude::App {
             .entry(T::name())
             .and_modify(|_| panic!("Property `{}` already registered.", T::name()))
             .or_insert(T::transform);
+        self.init_resource::<PropertyTransitions<T>>();
         self.add_systems(
             PostUpdate,
-            T::apply_defaults
-                .in_set(ApplyStyleProperties)
-                .after(InvalidateElements)
-                .before(UiSystem::Layout),
+            (
+                T::apply_defaults
+                    .in_set(ApplyStyleProperties)
+                    .after(InvalidateElements)
+                    .before(UiSystem::Layout),
+                T::apply_transitions
+                    .in_set(ApplyStyleProperties)
+                    .after(T::apply_defaults)
+                    .before(UiSystem::Layout),
+                T::apply_animations
+                    .in_set(ApplyStyleProperties)
+                    .after(T::apply_transitions)
+                    .before(UiSystem::Layout),
+            ),
         );
         self
     }
@@ -468,7 +862,7 @@ impl RegisterProperty for bevy::prelude::App {
             .unwrap()
             .entry(T::name())
             .and_modify(|_| panic!("CompoundProperty `{}` already registered", T::name()))
-            .insert(T::extract);
+            .insert((T::extract, T::longhands()));
         self
     }
 }
@@ -482,6 +876,7 @@ macro_rules! style_property {
         Components = $components:ty;
         Filters = $filters:ty;
         AffectsVirtual = $affects_virtual:literal;
+        $(Inherited = $inherited:literal;)?
         Parser = $parser:ty;
         Apply = | $value:ident, $component:ident, $assets:ident, $commands:ident, $entity:ident |
             $body:expr;
@@ -505,6 +900,18 @@ macro_rules! style_property {
                 $affects_virtual
             }
 
+            $(
+            fn inherited() -> bool {
+                $inherited
+            }
+            )?
+
+            fn initial() -> Self::Item {
+                $crate::ess::StyleProperty::try_from($default)
+                    .and_then(|p| <$parser as $crate::ess::PropertyParser<$item>>::parse(&p))
+                    .unwrap_or_default()
+            }
+
             fn apply(
                 $value: &Self::Item,
                 #[allow(unused_mut)]
@@ -523,6 +930,7 @@ macro_rules! style_property {
         Item = $item:ty;
         Components = $components:ty;
         Filters = $filters:ty;
+        $(Inherited = $inherited:literal;)?
         Parser = $parser:ty;
         Apply = | $value:ident, $component:ident, $assets:ident, $commands:ident, $entity:ident |
             $body:expr;
@@ -534,6 +942,7 @@ macro_rules! style_property {
             Components = $components;
             Filters = $filters;
             AffectsVirtual = false;
+            $(Inherited = $inherited;)?
             Parser = $parser;
             Apply = | $value, $component, $assets, $commands, $entity |
                 $body;
@@ -544,8 +953,10 @@ macro_rules! style_property {
 #[macro_export]
 macro_rules! compound_style_property {
     (   $(#[doc = $s:literal])*
-        $typename:ident($prop_name:literal, $value:ident)
-            $body:expr
+        $typename:ident($prop_name:literal, $value:ident) {
+            Longhands = [$($longhand:literal),* $(,)?];
+            $($body:tt)*
+        }
     ) => {
         #[derive(Default)]
         $(#[doc = $s])*
@@ -555,8 +966,11 @@ macro_rules! compound_style_property {
             fn name() -> $crate::Tag {
                 $crate::tag!($prop_name)
             }
+            fn longhands() -> &'static [&'static str] {
+                &[$($longhand),*]
+            }
             fn extract($value: $crate::eml::Variant) -> Result<::bevy::utils::HashMap<$crate::Tag, $crate::ess::PropertyValue>, $crate::ElementsError> {
-                $body
+                $($body)*
             }
         }
     }
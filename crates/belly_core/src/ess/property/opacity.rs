@@ -0,0 +1,101 @@
+use bevy::{
+    prelude::*,
+    render::Extract,
+    ui::{ExtractedUiNodes, UiStack},
+    utils::HashMap,
+};
+
+/// The opacity of the element and all of its descendants. The opacities
+/// of the nested elements are multiplied.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct Opacity(pub f32);
+
+impl Default for Opacity {
+    fn default() -> Self {
+        Opacity(1.)
+    }
+}
+
+/// The resulting opacity of every node of the [`UiStack`]
+/// in the render world, indexed by the stack index
+#[derive(Resource, Default)]
+pub(crate) struct ExtractedOpacity(Vec<f32>);
+
+fn resolve_opacity(
+    entity: Entity,
+    opacity_of: &impl Fn(Entity) -> Option<f32>,
+    parent_of: &impl Fn(Entity) -> Option<Entity>,
+    cache: &mut HashMap<Entity, f32>,
+) -> f32 {
+    if let Some(opacity) = cache.get(&entity) {
+        return *opacity;
+    }
+    let inherited = parent_of(entity)
+        .map(|parent| resolve_opacity(parent, opacity_of, parent_of, cache))
+        .unwrap_or(1.);
+    let opacity = opacity_of(entity).unwrap_or(1.) * inherited;
+    cache.insert(entity, opacity);
+    opacity
+}
+
+pub(crate) fn extract_opacity(
+    mut extracted: ResMut<ExtractedOpacity>,
+    ui_stack: Extract<Res<UiStack>>,
+    opacities: Extract<Query<&Opacity>>,
+    parents: Extract<Query<&Parent>>,
+) {
+    extracted.0.clear();
+    if opacities.is_empty() {
+        return;
+    }
+    let opacity_of = |entity| opacities.get(entity).ok().map(|o| o.0);
+    let parent_of = |entity| parents.get(entity).ok().map(|p| p.get());
+    let mut cache = HashMap::default();
+    for entity in ui_stack.uinodes.iter() {
+        let opacity = resolve_opacity(*entity, &opacity_of, &parent_of, &mut cache);
+        extracted.0.push(opacity);
+    }
+}
+
+/// Multiplies the alpha of the extracted backgrounds, images, text glyphs,
+/// borders and styleboxes by the opacity of the node they belong to.
+pub(crate) fn apply_opacity(
+    opacity: Res<ExtractedOpacity>,
+    mut extracted_uinodes: ResMut<ExtractedUiNodes>,
+) {
+    if opacity.0.is_empty() {
+        return;
+    }
+    for node in extracted_uinodes.uinodes.iter_mut() {
+        let Some(opacity) = opacity.0.get(node.stack_index).copied() else {
+            continue;
+        };
+        if opacity < 1. {
+            let alpha = node.color.a() * opacity;
+            node.color.set_a(alpha);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn compose_nested_opacity() {
+        let mut world = World::new();
+        let root = world.spawn(Opacity(0.5)).id();
+        let panel = world.spawn_empty().set_parent(root).id();
+        let label = world.spawn(Opacity(0.5)).set_parent(panel).id();
+        let other = world.spawn(Opacity(0.8)).id();
+
+        let opacity_of = |entity| world.get::<Opacity>(entity).map(|o| o.0);
+        let parent_of = |entity| world.get::<Parent>(entity).map(|p| p.get());
+        let mut cache = HashMap::default();
+        let mut resolve = |entity| resolve_opacity(entity, &opacity_of, &parent_of, &mut cache);
+        assert_eq!(resolve(label), 0.25);
+        assert_eq!(resolve(panel), 0.5);
+        assert_eq!(resolve(root), 0.5);
+        assert_eq!(resolve(other), 0.8);
+    }
+}
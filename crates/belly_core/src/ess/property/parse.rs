@@ -3,7 +3,9 @@ use std::{any::Any, marker::PhantomData};
 use crate::ElementsError;
 use bevy::prelude::*;
 
-use super::{colors, PropertyParser, StyleProperty, StylePropertyMethods, StylePropertyToken};
+use super::{
+    calc::Length, colors, PropertyParser, StyleProperty, StylePropertyMethods, StylePropertyToken,
+};
 
 pub fn identifier<S>(prop: &StyleProperty) -> Result<S, ElementsError>
 where
@@ -55,6 +57,31 @@ impl PropertyParser<Val> for ValParser {
     }
 }
 
+pub fn length(prop: &StyleProperty) -> Result<Length, ElementsError> {
+    let Some(token) = prop.first() else {
+        return Err(ElementsError::InvalidPropertyValue(format!("Expected $length, found nothing")))
+    };
+    Length::from_token(token)
+}
+
+/// <!-- @property-type=$length -->
+/// Size type accepting every [`$val`](#$val) value and the math functions,
+/// evaluated against the size of the parent element after the layout:
+/// - `calc()` with `+`, `-`, `*` and `/` operators: `calc(100% - 240px)`
+/// - `min()` and `max()` taking the smallest or the largest of the values:
+///   `max(200px, 30%)`
+/// - `clamp(min, value, max)` limiting the value by the bounds:
+///   `clamp(100px, 50%, 400px)`
+///
+/// Percentages inside the functions are resolved like the percentages of the
+/// property itself, the functions without percentages are evaluated at once.
+pub struct LengthParser;
+impl PropertyParser<Length> for LengthParser {
+    fn parse(value: &StyleProperty) -> Result<Length, ElementsError> {
+        length(value)
+    }
+}
+
 pub fn overflow(prop: &StyleProperty) -> Result<Overflow, ElementsError> {
     let Some(prop) = prop.first() else {
         return Err(ElementsError::InvalidPropertyValue(format!("Expected $val, found nothing")))
@@ -123,6 +150,8 @@ pub fn rect(prop: &StyleProperty) -> Result<UiRect, ElementsError> {
 /// - 4 values specifies all edges in top, right, bottom, left order (clock-wise):
 ///   `margin: 5px 4px 3% auto`
 ///
+/// The shorthand properties (`margin`, `padding`, `border-width` and `position`)
+/// accept [`$length`](#$length) items as well: `margin: 0 calc(50% - 200px)`
 pub struct RectParser;
 impl PropertyParser<UiRect> for RectParser {
     fn parse(value: &StyleProperty) -> Result<UiRect, ElementsError> {
@@ -136,25 +165,21 @@ pub fn color(prop: &StyleProperty) -> Result<Color, ElementsError> {
             "Expected $color, got nothing"
         )));
     }
-    match &prop[0] {
-        StylePropertyToken::Identifier(name) => colors::parse_named_color(name.as_str())
-            .ok_or_else(|| {
-                ElementsError::InvalidPropertyValue(format!("Unknown color name `{name}`"))
-            }),
-        StylePropertyToken::Hash(hash) => colors::parse_hex_color(hash.as_str()),
-        prop => {
-            return Err(ElementsError::InvalidPropertyValue(format!(
-                "Expected $color, got `{}`",
-                prop.to_string()
-            )))
-        }
-    }
+    colors::parse_color(&prop[0])
 }
 
 /// <!-- @property-type=$color -->
-/// Describes the `Color` value. Accepts color names (`white`, `red`)
-/// or hex codes (`#3fde1a`). List of predefined colors can be found
-/// here (coming soon).
+/// Describes the `Color` value. Accepts color names (`white`, `red`),
+/// hex codes (`#3fde1a`) or color functions:
+/// - `rgb(255, 127, 0)`, `rgba(255, 127, 0, 0.5)` or `rgb(100% 50% 0 / 50%)`
+/// - `hsl(210, 50%, 40%)`, `hsla(210deg, 50%, 40%, 0.5)` or `hsl(0.5turn 50% 40% / 50%)`
+/// - `mix(color, color, weight)` mixes two colors, `weight` is the proportion
+///   of the first color (`50%` by default): `mix(#3fde1a, white, 80%)`
+/// - `lighten(color, amount)` and `darken(color, amount)` change the HSL
+///   lightness of the color: `lighten(#3fde1a, 10%)`
+/// - `alpha(color, alpha)` replaces the alpha of the color: `alpha(red, 0.5)`
+///
+/// List of predefined colors can be found here (coming soon).
 /// <!-- TODO: add link to color list -->
 pub struct ColorParser;
 impl PropertyParser<Color> for ColorParser {
@@ -262,4 +287,89 @@ impl PropertyParser<Option<f32>> for OptionalNumParser {
     fn parse(value: &StyleProperty) -> Result<Option<f32>, ElementsError> {
         optional_num(value)
     }
-}
\ No newline at end of file
+}
+/// `px`, `%` or `0`
+pub fn length_val(token: &StylePropertyToken) -> Result<Val, ElementsError> {
+    match token {
+        StylePropertyToken::Dimension(value, unit) if unit == "px" => Ok(Val::Px(value.to_float())),
+        StylePropertyToken::Percentage(value) => Ok(Val::Percent(value.to_float())),
+        StylePropertyToken::Number(value) if value.to_float() == 0. => Ok(Val::Px(0.)),
+        token => Err(ElementsError::InvalidPropertyValue(format!(
+            "Expected $length, got `{}`",
+            token.to_string()
+        ))),
+    }
+}
+
+/// `deg`, `rad`, `turn` or `0` into radians
+pub fn angle(token: &StylePropertyToken) -> Result<f32, ElementsError> {
+    match token {
+        StylePropertyToken::Dimension(value, unit) if unit == "deg" => {
+            Ok(value.to_float().to_radians())
+        }
+        StylePropertyToken::Dimension(value, unit) if unit == "rad" => Ok(value.to_float()),
+        StylePropertyToken::Dimension(value, unit) if unit == "turn" => {
+            Ok(value.to_float() * std::f32::consts::TAU)
+        }
+        StylePropertyToken::Number(value) if value.to_float() == 0. => Ok(0.),
+        token => Err(ElementsError::InvalidPropertyValue(format!(
+            "Expected $angle, got `{}`",
+            token.to_string()
+        ))),
+    }
+}
+
+/// One or two of `left`, `center`, `right`, `top`, `bottom` keywords or
+/// lengths into the horizontal and vertical positions. The single length
+/// sets the horizontal position, `property` is used in the error messages.
+pub fn position(
+    tokens: &[StylePropertyToken],
+    property: &str,
+) -> Result<(Val, Val), ElementsError> {
+    let center = Val::Percent(50.);
+    let vertical = |t: &StylePropertyToken| t.is_ident("top") || t.is_ident("bottom");
+    let horizontal = |t: &StylePropertyToken| t.is_ident("left") || t.is_ident("right");
+    let x = |t: &StylePropertyToken| position_component(t, false, property);
+    let y = |t: &StylePropertyToken| position_component(t, true, property);
+    match tokens {
+        [v] if vertical(v) => Ok((center, y(v)?)),
+        [h] => Ok((x(h)?, center)),
+        [v, h] if vertical(v) || horizontal(h) => Ok((x(h)?, y(v)?)),
+        [h, v] => Ok((x(h)?, y(v)?)),
+        tokens => Err(ElementsError::InvalidPropertyValue(format!(
+            "Expected one or two positions, got `{}`",
+            tokens
+                .iter()
+                .map(|t| t.to_string())
+                .collect::<Vec<_>>()
+                .join(" ")
+        ))),
+    }
+}
+
+fn position_component(
+    token: &StylePropertyToken,
+    vertical: bool,
+    property: &str,
+) -> Result<Val, ElementsError> {
+    match token {
+        StylePropertyToken::Identifier(ident) => match (ident.as_str(), vertical) {
+            ("center", _) => Ok(Val::Percent(50.)),
+            ("left", false) | ("top", true) => Ok(Val::Percent(0.)),
+            ("right", false) | ("bottom", true) => Ok(Val::Percent(100.)),
+            (ident, _) => Err(ElementsError::InvalidPropertyValue(format!(
+                "Unexpected `{ident}` in {property}"
+            ))),
+        },
+        token => length_val(token),
+    }
+}
+
+/// Resolves the `px` or `%` of the `size` into physical pixels
+pub fn resolve_val(value: Val, size: f32, scale: f32) -> f32 {
+    match value {
+        Val::Px(px) => px * scale,
+        Val::Percent(percent) => size * percent / 100.,
+        _ => 0.,
+    }
+}
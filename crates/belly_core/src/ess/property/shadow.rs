@@ -0,0 +1,342 @@
+use super::{colors, StyleProperty, StylePropertyToken};
+use crate::{ess::defaults::Defaults, ElementsError};
+use bevy::{
+    prelude::*,
+    render::{
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+        Extract,
+    },
+    ui::{ExtractedUiNode, ExtractedUiNodes, UiStack},
+};
+use smallvec::SmallVec;
+
+/// The half of the size of the shadow texture: the length of the blur ramp in texels
+const RAMP: usize = 32;
+const EPSILON: f32 = 0.0001;
+
+/// The single shadow of the `box-shadow` property
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoxShadow {
+    pub offset: Vec2,
+    pub blur: f32,
+    pub spread: f32,
+    pub color: Color,
+}
+
+impl BoxShadow {
+    fn parse(tokens: &[StylePropertyToken]) -> Result<BoxShadow, ElementsError> {
+        let mut lengths = SmallVec::<[f32; 4]>::new();
+        let mut color = None;
+        for token in tokens {
+            match token {
+                StylePropertyToken::Dimension(value, unit) if unit == "px" => {
+                    lengths.push(value.to_float())
+                }
+                StylePropertyToken::Number(value) if value.to_float() == 0. => lengths.push(0.),
+                token if color.is_none() => color = Some(colors::parse_color(token)?),
+                token => {
+                    return Err(ElementsError::InvalidPropertyValue(format!(
+                        "Unexpected `{}` in box-shadow",
+                        token.to_string()
+                    )))
+                }
+            }
+        }
+        let (offset, blur, spread) = match lengths.as_slice() {
+            [x, y] => (Vec2::new(*x, *y), 0., 0.),
+            [x, y, blur] => (Vec2::new(*x, *y), *blur, 0.),
+            [x, y, blur, spread] => (Vec2::new(*x, *y), *blur, *spread),
+            _ => {
+                return Err(ElementsError::InvalidPropertyValue(format!(
+                    "Expected 2 to 4 lengths in box-shadow, got {}",
+                    lengths.len()
+                )))
+            }
+        };
+        if blur < 0. {
+            return Err(ElementsError::InvalidPropertyValue(format!(
+                "Negative box-shadow blur radius: {blur}px"
+            )));
+        }
+        Ok(BoxShadow {
+            offset,
+            blur,
+            spread,
+            color: color.unwrap_or(Color::rgba(0., 0., 0., 0.5)),
+        })
+    }
+}
+
+/// The shadows of the element, the first one is drawn on top
+#[derive(Component, Debug, Clone, PartialEq, Default)]
+pub struct BoxShadows(pub SmallVec<[BoxShadow; 1]>);
+
+impl BoxShadows {
+    /// Parses `none` or the comma separated list of
+    /// `offset-x offset-y [blur [spread]] [color]` shadows
+    pub fn parse(prop: &StyleProperty) -> Result<BoxShadows, ElementsError> {
+        match prop.as_slice() {
+            [] => Err(ElementsError::InvalidPropertyValue(format!(
+                "Expected none|$shadow, got nothing"
+            ))),
+            [StylePropertyToken::Identifier(ident)] if ident == "none" => Ok(BoxShadows::default()),
+            tokens => tokens
+                .split(|t| t == &StylePropertyToken::Comma)
+                .map(BoxShadow::parse)
+                .collect::<Result<_, _>>()
+                .map(BoxShadows),
+        }
+    }
+}
+
+/// Approximation of the error function (Abramowitz & Stegun 7.1.26)
+fn erf(x: f32) -> f32 {
+    let t = 1. / (1. + 0.3275911 * x.abs());
+    let poly =
+        t * (0.2548296 + t * (-0.28449672 + t * (1.4214138 + t * (-1.4531521 + t * 1.0614054))));
+    let value = 1. - poly * (-x * x).exp();
+    value.copysign(x)
+}
+
+/// Builds the white texture with the alpha of the blurred rectangle:
+/// the blur ramp goes from the edges to the center of the texture.
+pub(crate) fn shadow_image() -> Image {
+    let size = RAMP * 2;
+    // the ramp covers 4 standard deviations of gaussian blur
+    let cdf = |t: f32| 0.5 * (1. + erf((t - 0.5) * 2. * std::f32::consts::SQRT_2));
+    let ramp = |idx: usize| {
+        let idx = if idx < RAMP { idx } else { size - 1 - idx };
+        let t = idx as f32 / (RAMP - 1) as f32;
+        ((cdf(t) - cdf(0.)) / (cdf(1.) - cdf(0.))).clamp(0., 1.)
+    };
+    let mut data = Vec::with_capacity(size * size * 4);
+    for y in 0..size {
+        for x in 0..size {
+            let alpha = ramp(x) * ramp(y);
+            data.extend([255, 255, 255, (alpha * 255.).round() as u8]);
+        }
+    }
+    Image::new(
+        Extent3d {
+            width: size as u32,
+            height: size as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+    )
+}
+
+/// The shadow quads extracted from the main world. They are merged into
+/// [`ExtractedUiNodes`] right before the nodes they belong to.
+#[derive(Resource, Default)]
+pub(crate) struct ExtractedBoxShadows(Vec<ExtractedUiNode>);
+
+/// Returns the parts of the `rect` outside of the `hole`: the full-width
+/// stripes above and below the hole and the stripes at its sides.
+fn subtract(rect: Rect, hole: Rect) -> SmallVec<[Rect; 4]> {
+    let hole = hole.intersect(rect);
+    if hole.is_empty() {
+        return SmallVec::from_elem(rect, 1);
+    }
+    [
+        Rect::new(rect.min.x, rect.min.y, rect.max.x, hole.min.y),
+        Rect::new(rect.min.x, hole.max.y, rect.max.x, rect.max.y),
+        Rect::new(rect.min.x, hole.min.y, hole.min.x, hole.max.y),
+        Rect::new(hole.max.x, hole.min.y, rect.max.x, hole.max.y),
+    ]
+    .into_iter()
+    .filter(|part| part.width() > EPSILON && part.height() > EPSILON)
+    .collect()
+}
+
+/// Splits the blurred shadow into 9 slices of the shadow texture, the same
+/// way `bevy_stylebox` does. The part of the shadow under the element border
+/// box is skipped, so the shadow doesn't show through a translucent background.
+/// The quads are never scaled: the texture region is stretched by the atlas
+/// size instead, so the clipped quads keep the right texture coordinates.
+pub(crate) fn extract_box_shadows(
+    mut extracted: ResMut<ExtractedBoxShadows>,
+    ui_stack: Extract<Res<UiStack>>,
+    defaults: Extract<Res<Defaults>>,
+    nodes: Extract<
+        Query<(
+            &Node,
+            &GlobalTransform,
+            &BoxShadows,
+            &ComputedVisibility,
+            Option<&CalculatedClip>,
+        )>,
+    >,
+) {
+    extracted.0.clear();
+    let ramp = RAMP as f32;
+    let texture_size = Vec2::splat(ramp * 2.);
+    // the ramp, the solid middle line and the reversed ramp of the texture
+    let texture = [(0., ramp), (ramp - 1., ramp + 1.), (ramp, ramp * 2.)];
+    for (stack_index, entity) in ui_stack.uinodes.iter().enumerate() {
+        let Ok((node, transform, shadows, visibility, clip)) = nodes.get(*entity) else {
+            continue;
+        };
+        if !visibility.is_visible() || node.size() == Vec2::ZERO {
+            continue;
+        }
+        let transform = transform.compute_matrix();
+        let border_box = Rect::from_center_size(Vec2::ZERO, node.size());
+        for shadow in shadows.0.iter().rev() {
+            if shadow.color.a() <= 0. {
+                continue;
+            }
+            let half = (node.size() * 0.5 + shadow.spread).max(Vec2::ZERO);
+            if half.cmple(Vec2::ZERO).any() {
+                continue;
+            }
+            let quad = |part: Rect, rect: Rect, image: Handle<Image>, atlas_size: Option<Vec2>| {
+                ExtractedUiNode {
+                    transform: transform * Mat4::from_translation(part.center().extend(0.)),
+                    color: shadow.color,
+                    rect,
+                    image,
+                    atlas_size,
+                    clip: clip.map(|clip| clip.clip),
+                    stack_index,
+                    flip_x: false,
+                    flip_y: false,
+                }
+            };
+            if shadow.blur < EPSILON {
+                let solid = Rect::from_center_size(shadow.offset, half * 2.);
+                for part in subtract(solid, border_box) {
+                    let rect = Rect {
+                        max: part.size(),
+                        ..default()
+                    };
+                    let image = bevy::render::texture::DEFAULT_IMAGE_HANDLE.typed();
+                    extracted.0.push(quad(part, rect, image, None));
+                }
+                continue;
+            }
+            let outer = half + shadow.blur;
+            let inner = (half - shadow.blur).max(Vec2::ZERO);
+            // pairs of the slice edges with the texture ranges
+            let slices = |edges: [f32; 4]| {
+                edges
+                    .windows(2)
+                    .map(|edge| (edge[0], edge[1]))
+                    .zip(texture)
+                    .collect::<SmallVec<[_; 3]>>()
+            };
+            let rows = slices([-outer.y, -inner.y, inner.y, outer.y]);
+            let cols = slices([-outer.x, -inner.x, inner.x, outer.x]);
+            for ((y0, y1), (ty0, ty1)) in rows.iter().copied() {
+                for ((x0, x1), (tx0, tx1)) in cols.iter().copied() {
+                    let slice = Rect::new(x0, y0, x1, y1);
+                    if slice.width() < EPSILON || slice.height() < EPSILON {
+                        continue;
+                    }
+                    let slice = Rect {
+                        min: slice.min + shadow.offset,
+                        max: slice.max + shadow.offset,
+                    };
+                    let region = Rect::new(tx0, ty0, tx1, ty1);
+                    for part in subtract(slice, border_box) {
+                        // the texture region of the part and its stretch
+                        let texel =
+                            |p: Vec2| region.min + (p - slice.min) / slice.size() * region.size();
+                        let part_region = Rect::from_corners(texel(part.min), texel(part.max));
+                        let scale = part.size() / part_region.size();
+                        let rect = Rect {
+                            min: part_region.min * scale,
+                            max: part_region.max * scale,
+                        };
+                        let image = defaults.box_shadow_image.clone_weak();
+                        extracted
+                            .0
+                            .push(quad(part, rect, image, Some(texture_size * scale)));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Places the extracted shadows right before the first node with the same
+/// stack index, so the shadows are drawn behind the element background.
+pub(crate) fn apply_box_shadows(
+    mut shadows: ResMut<ExtractedBoxShadows>,
+    mut extracted_uinodes: ResMut<ExtractedUiNodes>,
+) {
+    if shadows.0.is_empty() {
+        return;
+    }
+    let mut shadows = std::mem::take(&mut shadows.0).into_iter().peekable();
+    let mut nodes = std::mem::take(&mut extracted_uinodes.uinodes);
+    nodes.sort_by_key(|node| node.stack_index);
+    let mut merged = Vec::with_capacity(nodes.len() + shadows.len());
+    for node in nodes {
+        while let Some(shadow) = shadows.next_if(|s| s.stack_index <= node.stack_index) {
+            merged.push(shadow);
+        }
+        merged.push(node);
+    }
+    merged.extend(shadows);
+    extracted_uinodes.uinodes = merged;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn shadows(value: &str) -> Result<BoxShadows, ElementsError> {
+        BoxShadows::parse(&StyleProperty::try_from(value).unwrap())
+    }
+
+    #[test]
+    fn parse_box_shadows() {
+        assert_eq!(shadows("none"), Ok(BoxShadows::default()));
+        let parsed = shadows("2px 4px red, 0 0 10px 2px #00000080").unwrap();
+        assert_eq!(parsed.0.len(), 2);
+        assert_eq!(
+            parsed.0[0],
+            BoxShadow {
+                offset: Vec2::new(2., 4.),
+                blur: 0.,
+                spread: 0.,
+                color: Color::RED
+            }
+        );
+        assert_eq!(parsed.0[1].offset, Vec2::ZERO);
+        assert_eq!(parsed.0[1].blur, 10.);
+        assert_eq!(parsed.0[1].spread, 2.);
+        assert_eq!(shadows("white 1px 1px").unwrap().0[0].color, Color::WHITE);
+        assert!(shadows("1px").is_err());
+        assert!(shadows("1px 1px -2px").is_err());
+        assert!(shadows("1px 1px red blue").is_err());
+    }
+
+    #[test]
+    fn build_shadow_texture() {
+        let image = shadow_image();
+        let size = RAMP * 2;
+        let alpha = |x: usize, y: usize| image.data[(y * size + x) * 4 + 3];
+        assert_eq!(alpha(0, 0), 0);
+        assert_eq!(alpha(RAMP, RAMP), 255);
+        assert_eq!(alpha(RAMP - 1, RAMP), alpha(RAMP, RAMP - 1));
+        assert!(alpha(RAMP / 2, RAMP) > 100 && alpha(RAMP / 2, RAMP) < 155);
+    }
+
+    #[test]
+    fn subtract_border_box() {
+        let hole = Rect::new(-10., -10., 10., 10.);
+        let parts = subtract(Rect::new(-15., -12., 15., 12.), hole);
+        assert_eq!(parts.len(), 4);
+        let area: f32 = parts.iter().map(|p| p.width() * p.height()).sum();
+        assert_eq!(area, 30. * 24. - 20. * 20.);
+        let shifted = subtract(Rect::new(-10., -5., 10., 15.), hole);
+        assert_eq!(shifted.as_slice(), &[Rect::new(-10., 10., 10., 15.)]);
+        let outside = Rect::new(20., 20., 30., 30.);
+        assert_eq!(subtract(outside, hole).as_slice(), &[outside]);
+        assert!(subtract(hole, hole).is_empty());
+    }
+}
@@ -83,6 +83,10 @@ pub enum StylePropertyToken {
     /// Property delimiter (comma or slash)
     Slash,
     Comma,
+    /// Math operators used by `calc()`
+    Plus,
+    Minus,
+    Asterisk,
 }
 
 impl StylePropertyToken {
@@ -107,6 +111,9 @@ impl StylePropertyToken {
             StylePropertyToken::Tokens(t) => t.iter().map(|t| t.to_string()).join(" "),
             StylePropertyToken::Slash => format!("/"),
             StylePropertyToken::Comma => format!(","),
+            StylePropertyToken::Plus => format!("+"),
+            StylePropertyToken::Minus => format!("-"),
+            StylePropertyToken::Asterisk => format!("*"),
         }
     }
 
@@ -277,8 +284,9 @@ pub trait StylePropertyMethods {
 
     /// Tries to parses the current values as a single [`Color`].
     ///
-    /// Currently only [named colors](https://developer.mozilla.org/en-US/docs/Web/CSS/named-color)
-    /// and [hex-colors](https://developer.mozilla.org/en-US/docs/Web/CSS/hex-color) are supported.
+    /// [Named colors](https://developer.mozilla.org/en-US/docs/Web/CSS/named-color),
+    /// [hex-colors](https://developer.mozilla.org/en-US/docs/Web/CSS/hex-color) and
+    /// color functions (`rgb()`, `hsl()`, `mix()`, `lighten()`, etc.) are supported.
     fn color(&self) -> Result<Color, ElementsError> {
         let props = self.tokens();
         if props.len() == 0 {
@@ -286,19 +294,7 @@ pub trait StylePropertyMethods {
                 "Expected color, got nothing"
             )));
         }
-        match &props[0] {
-            StylePropertyToken::Identifier(name) => colors::parse_named_color(name.as_str())
-                .ok_or_else(|| {
-                    ElementsError::InvalidPropertyValue(format!("Unknown color name '{name}'"))
-                }),
-            StylePropertyToken::Hash(hash) => colors::parse_hex_color(hash.as_str()),
-            prop => {
-                return Err(ElementsError::InvalidPropertyValue(format!(
-                    "Can't parse color from {}",
-                    prop.to_string()
-                )))
-            }
-        }
+        colors::parse_color(&props[0])
     }
 
     /// Tries to parses the current values as a single identifier.
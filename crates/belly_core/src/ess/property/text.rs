@@ -0,0 +1,440 @@
+use bevy::{
+    prelude::*,
+    text::{BreakLineOn, TextLayoutInfo},
+    utils::HashMap,
+    window::PrimaryWindow,
+};
+
+/// The wrapping of the text lines
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WhiteSpace {
+    #[default]
+    Normal,
+    NoWrap,
+}
+
+impl From<WhiteSpace> for BreakLineOn {
+    fn from(value: WhiteSpace) -> Self {
+        match value {
+            WhiteSpace::Normal => BreakLineOn::WordBoundary,
+            WhiteSpace::NoWrap => BreakLineOn::NoWrap,
+        }
+    }
+}
+
+/// The case transformation of the text sections
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextTransform {
+    #[default]
+    None,
+    Uppercase,
+    Lowercase,
+    Capitalize,
+}
+
+impl TextTransform {
+    pub fn apply(&self, value: &str) -> String {
+        match self {
+            TextTransform::None => value.to_string(),
+            TextTransform::Uppercase => value.to_uppercase(),
+            TextTransform::Lowercase => value.to_lowercase(),
+            TextTransform::Capitalize => {
+                let mut word_start = true;
+                let mut result = String::with_capacity(value.len());
+                for ch in value.chars() {
+                    if word_start {
+                        result.extend(ch.to_uppercase());
+                    } else {
+                        result.push(ch);
+                    }
+                    word_start = ch.is_whitespace();
+                }
+                result
+            }
+        }
+    }
+}
+
+/// The vertical position of the text lines inside the text node
+/// taller than the lines
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VerticalAlign {
+    #[default]
+    Top,
+    Middle,
+    Bottom,
+}
+
+impl VerticalAlign {
+    /// The offset of the lines for the `free` space left in the node
+    pub fn offset(&self, free: f32) -> f32 {
+        match self {
+            VerticalAlign::Top => 0.,
+            VerticalAlign::Middle => free * 0.5,
+            VerticalAlign::Bottom => free,
+        }
+    }
+}
+
+/// Keeps the original values of the transformed [`Text`] sections, so
+/// the content can be restored or transformed again when the
+/// `text-transform` property or the content changes.
+#[derive(Component, Debug, Default)]
+pub struct TransformedText {
+    transform: TextTransform,
+    originals: Vec<String>,
+    transformed: Vec<String>,
+}
+
+impl TransformedText {
+    pub fn new(transform: TextTransform) -> TransformedText {
+        TransformedText {
+            transform,
+            ..default()
+        }
+    }
+
+    pub fn transform(&self) -> TextTransform {
+        self.transform
+    }
+
+    pub fn set_transform(&mut self, transform: TextTransform) {
+        self.transform = transform;
+    }
+
+    /// Transforms the sections of the text. Sections changed since the
+    /// last transformation (by bindings, for example) are treated as
+    /// the new originals.
+    fn apply(&mut self, text: &mut Mut<Text>) {
+        let sections = text.sections.len();
+        self.originals.resize(sections, String::new());
+        self.transformed.resize(sections, String::new());
+        let values = self.originals.iter_mut().zip(self.transformed.iter_mut());
+        for (idx, (original, transformed)) in values.enumerate() {
+            if &text.sections[idx].value != transformed {
+                *original = text.sections[idx].value.clone();
+            }
+            *transformed = self.transform.apply(original);
+            if &text.sections[idx].value != transformed {
+                text.sections[idx].value = transformed.clone();
+            }
+        }
+    }
+}
+
+pub(crate) fn transform_text(
+    mut texts: Query<
+        (&mut Text, &mut TransformedText),
+        Or<(Changed<Text>, Changed<TransformedText>)>,
+    >,
+) {
+    for (mut text, mut transformed) in texts.iter_mut() {
+        transformed.apply(&mut text);
+    }
+}
+
+/// The height of the text line: the multiplier of the font size or
+/// the absolute value in pixels
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub enum LineHeight {
+    Factor(f32),
+    Px(f32),
+}
+
+impl LineHeight {
+    pub fn resolve(&self, font_size: f32) -> f32 {
+        match self {
+            LineHeight::Factor(factor) => factor * font_size,
+            LineHeight::Px(px) => *px,
+        }
+    }
+}
+
+/// The height reserved for the text lines along with the `min-height`
+/// declared for the text node, so the declared value isn't lost when
+/// the line box is applied.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Default)]
+pub(crate) struct LineBox {
+    height: f32,
+    declared: Val,
+    applied: Val,
+}
+
+impl LineBox {
+    /// The `min-height` to restore when the line height is removed: the
+    /// declared value, or the current one if it was changed since the
+    /// line box was applied.
+    pub(crate) fn restore(&self, current: Val) -> Val {
+        if current == self.applied {
+            self.declared
+        } else {
+            current
+        }
+    }
+
+    /// The `min-height` of the text node: the larger one of the declared
+    /// value and the line box. Only pixels are comparable, other declared
+    /// values are kept as is.
+    fn min_height(&self) -> Val {
+        match self.declared {
+            _ if self.height <= 0. => self.declared,
+            Val::Auto => Val::Px(self.height),
+            Val::Px(px) => Val::Px(px.max(self.height)),
+            declared => declared,
+        }
+    }
+}
+
+/// The same scale factor bevy_text lays out the text with
+fn text_scale(
+    windows: &Query<&Window, With<PrimaryWindow>>,
+    ui_scale: Option<Res<UiScale>>,
+) -> f32 {
+    let window_scale = windows.get_single().map(|w| w.scale_factor()).unwrap_or(1.);
+    (window_scale * ui_scale.map(|s| s.scale).unwrap_or(1.)) as f32
+}
+
+/// Bevy lays out text lines using the font metrics only. This system
+/// spreads the freshly computed lines to the requested height and
+/// reserves the space for them with the `min_height` of the text node
+/// (unless the declared `min-height` is larger). When only the line height
+/// changes, the text is marked as changed to get the lines computed again.
+pub(crate) fn apply_line_height(
+    mut texts: Query<
+        (
+            Entity,
+            &mut Text,
+            Ref<LineHeight>,
+            &mut TextLayoutInfo,
+            &mut Style,
+            Option<&mut LineBox>,
+        ),
+        Or<(Changed<TextLayoutInfo>, Changed<LineHeight>, Changed<Style>)>,
+    >,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    ui_scale: Option<Res<UiScale>>,
+    mut commands: Commands,
+) {
+    let scale = text_scale(&windows, ui_scale);
+    for (entity, mut text, line_height, mut layout, mut style, line_box) in texts.iter_mut() {
+        let mut actual = line_box.as_deref().copied().unwrap_or(LineBox {
+            height: 0.,
+            declared: style.min_height,
+            applied: style.min_height,
+        });
+        if style.min_height != actual.applied {
+            // min-height is declared by the style
+            actual.declared = style.min_height;
+        }
+        if layout.is_changed() {
+            actual.height = spread_lines(&text, &line_height, layout.as_mut(), scale) / scale;
+        } else if line_height.is_changed() {
+            text.set_changed();
+            continue;
+        }
+        let min_height = actual.min_height();
+        if style.min_height != min_height {
+            style.min_height = min_height;
+        }
+        actual.applied = min_height;
+        match line_box {
+            Some(mut line_box) if *line_box != actual => *line_box = actual,
+            Some(_) => {}
+            None => {
+                commands.entity(entity).insert(actual);
+            }
+        }
+    }
+}
+
+/// Moves the text lines down according to [`VerticalAlign`] when the node
+/// is taller than the lines. The glyphs are moved bypassing the change
+/// detection, so the offset applied to the layout is kept to move the
+/// lines again when only the alignment or the size of the node changes.
+pub(crate) fn apply_vertical_align(
+    mut texts: Query<
+        (Entity, &Node, &VerticalAlign, &mut TextLayoutInfo),
+        Or<(
+            Changed<TextLayoutInfo>,
+            Changed<Node>,
+            Changed<VerticalAlign>,
+        )>,
+    >,
+    mut removed: RemovedComponents<VerticalAlign>,
+    mut offsets: Local<HashMap<Entity, f32>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    ui_scale: Option<Res<UiScale>>,
+) {
+    for entity in removed.iter() {
+        offsets.remove(&entity);
+    }
+    let scale = text_scale(&windows, ui_scale);
+    for (entity, node, align, mut layout) in texts.iter_mut() {
+        // the freshly computed layout isn't moved yet
+        let applied = if layout.is_changed() {
+            0.
+        } else {
+            offsets.get(&entity).copied().unwrap_or(0.)
+        };
+        let offset = align.offset((node.size().y * scale - layout.size.y).max(0.));
+        if offset != applied {
+            let layout = layout.bypass_change_detection();
+            for glyph in layout.glyphs.iter_mut() {
+                glyph.position.y += offset - applied;
+            }
+        }
+        offsets.insert(entity, offset);
+    }
+}
+
+/// Moves the glyphs of every line to the requested line height and
+/// returns the height of the lines in physical pixels.
+fn spread_lines(
+    text: &Text,
+    line_height: &LineHeight,
+    layout: &mut TextLayoutInfo,
+    scale: f32,
+) -> f32 {
+    let mut lines = 0;
+    let mut last_x = f32::INFINITY;
+    let mut line_indices = Vec::with_capacity(layout.glyphs.len());
+    for glyph in layout.glyphs.iter() {
+        if glyph.position.x < last_x {
+            lines += 1;
+        }
+        last_x = glyph.position.x;
+        line_indices.push(lines - 1);
+    }
+    if lines == 0 {
+        return 0.;
+    }
+    let font_size = text
+        .sections
+        .iter()
+        .map(|s| s.style.font_size)
+        .fold(0., f32::max);
+    let natural = layout.size.y / lines as f32;
+    let target = line_height.resolve(font_size) * scale;
+    let delta = target - natural;
+    for (glyph, line) in layout.glyphs.iter_mut().zip(line_indices) {
+        glyph.position.y += delta * line as f32 + delta * 0.5;
+    }
+    layout.size.y = target * lines as f32;
+    layout.size.y
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn transform_text_case() {
+        let value = "hello, big  world";
+        assert_eq!(TextTransform::None.apply(value), "hello, big  world");
+        assert_eq!(TextTransform::Uppercase.apply(value), "HELLO, BIG  WORLD");
+        assert_eq!(TextTransform::Lowercase.apply("Hello WORLD"), "hello world");
+        assert_eq!(TextTransform::Capitalize.apply(value), "Hello, Big  World");
+        assert_eq!(
+            TextTransform::Capitalize.apply("über\nalles"),
+            "Über\nAlles"
+        );
+    }
+
+    #[test]
+    fn keep_original_text() {
+        let mut world = World::new();
+        let entity = world
+            .spawn((
+                Text::from_section("some label", default()),
+                TransformedText::new(TextTransform::Uppercase),
+            ))
+            .id();
+        let mut system = IntoSystem::into_system(transform_text);
+        system.initialize(&mut world);
+        let text = |world: &World| world.get::<Text>(entity).unwrap().sections[0].value.clone();
+
+        system.run((), &mut world);
+        assert_eq!(text(&world), "SOME LABEL");
+
+        world.get_mut::<Text>(entity).unwrap().sections[0].value = "other label".into();
+        system.run((), &mut world);
+        assert_eq!(text(&world), "OTHER LABEL");
+
+        world
+            .get_mut::<TransformedText>(entity)
+            .unwrap()
+            .set_transform(TextTransform::None);
+        system.run((), &mut world);
+        assert_eq!(text(&world), "other label");
+    }
+
+    #[test]
+    fn vertical_align_offset() {
+        assert_eq!(VerticalAlign::Top.offset(40.), 0.);
+        assert_eq!(VerticalAlign::Middle.offset(40.), 20.);
+        assert_eq!(VerticalAlign::Bottom.offset(40.), 40.);
+    }
+
+    #[test]
+    fn keep_declared_min_height() {
+        let line_box = |height, declared| LineBox {
+            height,
+            declared,
+            applied: Val::Auto,
+        };
+        assert_eq!(line_box(40., Val::Auto).min_height(), Val::Px(40.));
+        assert_eq!(line_box(40., Val::Px(60.)).min_height(), Val::Px(60.));
+        assert_eq!(line_box(40., Val::Px(20.)).min_height(), Val::Px(40.));
+        assert_eq!(
+            line_box(40., Val::Percent(50.)).min_height(),
+            Val::Percent(50.)
+        );
+        assert_eq!(line_box(0., Val::Auto).min_height(), Val::Auto);
+    }
+
+    #[test]
+    fn restore_min_height_without_line_height() {
+        use crate::ess::{property::impls::text::LineHeightProperty, Property};
+
+        let mut app = App::new();
+        app.add_plugins((TaskPoolPlugin::default(), AssetPlugin::default()));
+        // min-height: 40px extended to two lines of 30px
+        let entity = app
+            .world
+            .spawn((
+                Node::default(),
+                Text::default(),
+                Style {
+                    min_height: Val::Px(60.),
+                    ..default()
+                },
+                LineHeight::Px(30.),
+                LineBox {
+                    height: 60.,
+                    declared: Val::Px(40.),
+                    applied: Val::Px(60.),
+                },
+            ))
+            .id();
+        let mut system = IntoSystem::into_system(
+            |mut query: Query<
+                (Entity, <LineHeightProperty as Property>::Components),
+                <LineHeightProperty as Property>::Filters,
+            >,
+             assets: Res<AssetServer>,
+             mut commands: Commands| {
+                for (entity, components) in query.iter_mut() {
+                    LineHeightProperty::apply(&None, components, &assets, &mut commands, entity);
+                }
+            },
+        );
+        system.initialize(&mut app.world);
+        system.run((), &mut app.world);
+        system.apply_deferred(&mut app.world);
+
+        let style = app.world.get::<Style>(entity).unwrap();
+        assert_eq!(style.min_height, Val::Px(40.));
+        assert!(app.world.get::<LineHeight>(entity).is_none());
+        assert!(app.world.get::<LineBox>(entity).is_none());
+    }
+}
@@ -0,0 +1,374 @@
+use super::{
+    parse::{self, length_val, resolve_val},
+    Interpolate, StyleProperty, StylePropertyFunction, StylePropertyToken,
+};
+use crate::ElementsError;
+use bevy::prelude::*;
+
+/// The transformation of the element applied on top of the position
+/// computed by the layout. The functions of the `transform` property
+/// are combined into the single translation, rotation and scale, which
+/// are applied in that order.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UiTransform {
+    pub translate_x: Val,
+    pub translate_y: Val,
+    /// Clockwise rotation in radians
+    pub rotation: f32,
+    pub scale: Vec2,
+}
+
+impl Default for UiTransform {
+    fn default() -> Self {
+        UiTransform {
+            translate_x: Val::Px(0.),
+            translate_y: Val::Px(0.),
+            rotation: 0.,
+            scale: Vec2::ONE,
+        }
+    }
+}
+
+impl UiTransform {
+    /// Parses `none` or the space separated list of `translate()`,
+    /// `translateX()`, `translateY()`, `scale()`, `scaleX()`, `scaleY()`
+    /// and `rotate()` functions.
+    pub fn parse(prop: &StyleProperty) -> Result<UiTransform, ElementsError> {
+        let mut transform = UiTransform::default();
+        match prop.as_slice() {
+            [] => Err(ElementsError::InvalidPropertyValue(format!(
+                "Expected none|$transform-function, got nothing"
+            ))),
+            [StylePropertyToken::Identifier(ident)] if ident == "none" => Ok(transform),
+            tokens => {
+                for token in tokens {
+                    let StylePropertyToken::Function(func) = token else {
+                        return Err(ElementsError::InvalidPropertyValue(format!(
+                            "Expected $transform-function, got `{}`",
+                            token.to_string()
+                        )));
+                    };
+                    transform.apply_function(func)?;
+                }
+                Ok(transform)
+            }
+        }
+    }
+
+    fn apply_function(&mut self, func: &StylePropertyFunction) -> Result<(), ElementsError> {
+        let zero = Val::Px(0.);
+        match (func.name.as_str(), func.args.as_slice()) {
+            ("translate", [x]) => self.translate(length_val(x)?, zero)?,
+            ("translate", [x, y]) => self.translate(length_val(x)?, length_val(y)?)?,
+            ("translateX", [x]) => self.translate(length_val(x)?, zero)?,
+            ("translateY", [y]) => self.translate(zero, length_val(y)?)?,
+            ("scale", [s]) => self.scale *= number(s)?,
+            ("scale", [x, y]) => self.scale *= Vec2::new(number(x)?, number(y)?),
+            ("scaleX", [x]) => self.scale.x *= number(x)?,
+            ("scaleY", [y]) => self.scale.y *= number(y)?,
+            ("rotate", [angle]) => self.rotation += parse::angle(angle)?,
+            (
+                "translate" | "translateX" | "translateY" | "scale" | "scaleX" | "scaleY"
+                | "rotate",
+                _,
+            ) => {
+                return Err(ElementsError::InvalidPropertyValue(format!(
+                    "Invalid arguments of `{}`",
+                    StylePropertyToken::Function(func.clone()).to_string()
+                )))
+            }
+            (name, _) => {
+                return Err(ElementsError::InvalidPropertyValue(format!(
+                    "Unknown transform function `{name}()`"
+                )))
+            }
+        }
+        Ok(())
+    }
+
+    fn translate(&mut self, x: Val, y: Val) -> Result<(), ElementsError> {
+        self.translate_x = add(self.translate_x, x)?;
+        self.translate_y = add(self.translate_y, y)?;
+        Ok(())
+    }
+
+    /// Returns the translation of the element in pixels. Percentages are
+    /// relative to the size of the element itself.
+    pub fn offset(&self, size: Vec2, scale: f32) -> Vec2 {
+        Vec2::new(
+            resolve_val(self.translate_x, size.x, scale),
+            resolve_val(self.translate_y, size.y, scale),
+        )
+    }
+}
+
+impl Interpolate for UiTransform {
+    fn interpolate(&self, to: &Self, t: f32) -> Option<Self> {
+        Some(UiTransform {
+            translate_x: interpolate_translation(self.translate_x, to.translate_x, t)?,
+            translate_y: interpolate_translation(self.translate_y, to.translate_y, t)?,
+            rotation: self.rotation.interpolate(&to.rotation, t)?,
+            scale: Vec2::new(
+                self.scale.x.interpolate(&to.scale.x, t)?,
+                self.scale.y.interpolate(&to.scale.y, t)?,
+            ),
+        })
+    }
+}
+
+/// The zero translation is interpolated into any units,
+/// so `none` is smoothly transitioned into `translate(50%)`.
+fn interpolate_translation(from: Val, to: Val, t: f32) -> Option<Val> {
+    match (from, to) {
+        (Val::Px(zero), Val::Percent(_)) if zero == 0. => Val::Percent(0.).interpolate(&to, t),
+        (Val::Percent(_), Val::Px(zero)) if zero == 0. => from.interpolate(&Val::Percent(0.), t),
+        (from, to) => from.interpolate(&to, t),
+    }
+}
+
+/// The point of the element the rotation and the scale are applied around
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TransformOrigin {
+    pub x: Val,
+    pub y: Val,
+}
+
+impl Default for TransformOrigin {
+    fn default() -> Self {
+        TransformOrigin {
+            x: Val::Percent(50.),
+            y: Val::Percent(50.),
+        }
+    }
+}
+
+impl TransformOrigin {
+    /// Parses one or two of `left`, `center`, `right`, `top`, `bottom`
+    /// keywords or lengths. The single length sets the horizontal position.
+    pub fn parse(prop: &StyleProperty) -> Result<TransformOrigin, ElementsError> {
+        let (x, y) = parse::position(prop.as_slice(), "transform-origin")?;
+        Ok(TransformOrigin { x, y })
+    }
+
+    /// Returns the origin in pixels relative to the center of the element
+    pub fn pivot(&self, size: Vec2, scale: f32) -> Vec2 {
+        let origin = Vec2::new(
+            resolve_val(self.x, size.x, scale),
+            resolve_val(self.y, size.y, scale),
+        );
+        origin - size * 0.5
+    }
+}
+
+fn number(token: &StylePropertyToken) -> Result<f32, ElementsError> {
+    match token {
+        StylePropertyToken::Number(value) => Ok(value.to_float()),
+        StylePropertyToken::Percentage(value) => Ok(value.to_float() / 100.),
+        token => Err(ElementsError::InvalidPropertyValue(format!(
+            "Expected $num, got `{}`",
+            token.to_string()
+        ))),
+    }
+}
+
+fn add(a: Val, b: Val) -> Result<Val, ElementsError> {
+    match (a, b) {
+        (a, Val::Px(b)) if b == 0. => Ok(a),
+        (Val::Px(a), b) if a == 0. => Ok(b),
+        (Val::Px(a), Val::Px(b)) => Ok(Val::Px(a + b)),
+        (Val::Percent(a), Val::Percent(b)) => Ok(Val::Percent(a + b)),
+        (a, b) => Err(ElementsError::InvalidPropertyValue(format!(
+            "Can't combine {a:?} and {b:?} translations"
+        ))),
+    }
+}
+
+/// The `transform` and `transform-origin` of the element. The
+/// translation applied by the last frame is kept to restore the
+/// position computed by the layout when the layout doesn't update it.
+#[derive(Component, Debug, Default)]
+pub struct ElementTransform {
+    pub transform: UiTransform,
+    pub origin: TransformOrigin,
+    applied: Vec2,
+}
+
+impl ElementTransform {
+    pub(crate) fn modify(
+        element: Option<Mut<ElementTransform>>,
+        commands: &mut Commands,
+        entity: Entity,
+        modify: impl FnOnce(&mut ElementTransform) + Send + 'static,
+    ) {
+        match element {
+            Some(mut element) => modify(&mut element),
+            None => commands.add(move |world: &mut World| {
+                let Some(mut entity) = world.get_entity_mut(entity) else {
+                    return;
+                };
+                if let Some(mut element) = entity.get_mut::<ElementTransform>() {
+                    modify(&mut element);
+                } else {
+                    let mut element = ElementTransform::default();
+                    modify(&mut element);
+                    entity.insert(element);
+                }
+            }),
+        }
+    }
+
+    /// Returns the translation, rotation and scale to apply
+    /// to the element of the `size` positioned by the layout
+    fn compose(&self, size: Vec2, scale: f32) -> (Vec2, Quat, Vec2) {
+        let rotation = Quat::from_rotation_z(self.transform.rotation);
+        let pivot = self.origin.pivot(size, scale);
+        let scaled = (self.transform.scale * pivot).extend(0.);
+        let translation =
+            self.transform.offset(size, scale) + pivot - (rotation * scaled).truncate();
+        (translation, rotation, self.transform.scale)
+    }
+}
+
+/// Applies [`ElementTransform`] on top of the [`Transform`] computed by
+/// the layout. The layout only writes the translation of the nodes, so
+/// the rotation and the scale are kept between the frames, and the
+/// translation is restored when the layout hasn't touched it.
+pub(crate) fn apply_element_transforms(
+    mut elements: Query<(&Node, &mut Transform, &mut ElementTransform)>,
+    ui_scale: Res<UiScale>,
+) {
+    let scale = ui_scale.scale as f32;
+    for (node, mut transform, mut element) in elements.iter_mut() {
+        let (translation, rotation, node_scale) = element.compose(node.size(), scale);
+        let layout = if transform.is_changed() {
+            transform.translation.truncate()
+        } else {
+            transform.translation.truncate() - element.applied
+        };
+        let target = Transform {
+            translation: (layout + translation).extend(transform.translation.z),
+            rotation,
+            scale: node_scale.extend(1.),
+        };
+        if *transform != target {
+            *transform = target;
+        }
+        element.bypass_change_detection().applied = translation;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn transform(value: &str) -> Result<UiTransform, ElementsError> {
+        UiTransform::parse(&StyleProperty::try_from(value).unwrap())
+    }
+
+    fn origin(value: &str) -> Result<TransformOrigin, ElementsError> {
+        TransformOrigin::parse(&StyleProperty::try_from(value).unwrap())
+    }
+
+    #[test]
+    fn parse_transform() {
+        assert_eq!(transform("none"), Ok(UiTransform::default()));
+        let parsed = transform("translate(10px, 50%) scale(2) rotate(90deg)").unwrap();
+        assert_eq!(parsed.translate_x, Val::Px(10.));
+        assert_eq!(parsed.translate_y, Val::Percent(50.));
+        assert_eq!(parsed.scale, Vec2::splat(2.));
+        assert!((parsed.rotation - std::f32::consts::FRAC_PI_2).abs() < 1e-6);
+        let parsed = transform("translateX(5px) translateX(5px) scaleY(0.5) rotate(0.5turn)");
+        let parsed = parsed.unwrap();
+        assert_eq!(parsed.translate_x, Val::Px(10.));
+        assert_eq!(parsed.scale, Vec2::new(1., 0.5));
+        assert_eq!(parsed.rotation, std::f32::consts::PI);
+        assert!(transform("translate(10px) translate(10%)").is_err());
+        assert!(transform("skew(10deg)").is_err());
+        assert!(transform("rotate(10px)").is_err());
+        assert!(transform("10px").is_err());
+    }
+
+    #[test]
+    fn parse_transform_origin() {
+        let center = Val::Percent(50.);
+        assert_eq!(origin("center"), Ok(TransformOrigin::default()));
+        assert_eq!(
+            origin("left"),
+            Ok(TransformOrigin {
+                x: Val::Percent(0.),
+                y: center
+            })
+        );
+        assert_eq!(
+            origin("bottom right"),
+            Ok(TransformOrigin {
+                x: Val::Percent(100.),
+                y: Val::Percent(100.)
+            })
+        );
+        assert_eq!(
+            origin("10px 20%"),
+            Ok(TransformOrigin {
+                x: Val::Px(10.),
+                y: Val::Percent(20.)
+            })
+        );
+        assert!(origin("top top").is_err());
+        assert!(origin("left right").is_err());
+    }
+
+    #[test]
+    fn interpolate_transform() {
+        let from = transform("none").unwrap();
+        let to = transform("translateY(-50%) scale(2) rotate(1rad)").unwrap();
+        let half = from.interpolate(&to, 0.5).unwrap();
+        assert_eq!(half.translate_x, Val::Px(0.));
+        assert_eq!(half.translate_y, Val::Percent(-25.));
+        assert_eq!(half.scale, Vec2::splat(1.5));
+        assert_eq!(half.rotation, 0.5);
+        let px = transform("translateY(10px)").unwrap();
+        assert_eq!(px.interpolate(&to, 0.5), None);
+    }
+
+    #[test]
+    fn transform_around_origin() {
+        let element = ElementTransform {
+            transform: transform("scale(2)").unwrap(),
+            origin: origin("left top").unwrap(),
+            ..default()
+        };
+        // the top left corner stays at the same place
+        let (translation, _, scale) = element.compose(Vec2::new(100., 50.), 1.);
+        assert_eq!(translation, Vec2::new(50., 25.));
+        assert_eq!(scale, Vec2::splat(2.));
+    }
+
+    #[test]
+    fn keep_layout_translation() {
+        let mut world = World::new();
+        world.init_resource::<UiScale>();
+        let entity = world
+            .spawn((
+                Node::default(),
+                Transform::from_xyz(100., 100., 0.),
+                ElementTransform {
+                    transform: transform("translate(10px, 5px)").unwrap(),
+                    ..default()
+                },
+            ))
+            .id();
+        let mut system = IntoSystem::into_system(apply_element_transforms);
+        system.initialize(&mut world);
+        let translation = |world: &World| world.get::<Transform>(entity).unwrap().translation;
+
+        system.run((), &mut world);
+        assert_eq!(translation(&world), Vec3::new(110., 105., 0.));
+        system.run((), &mut world);
+        assert_eq!(translation(&world), Vec3::new(110., 105., 0.));
+
+        // the layout moves the node
+        world.get_mut::<Transform>(entity).unwrap().translation = Vec3::new(50., 50., 0.);
+        system.run((), &mut world);
+        assert_eq!(translation(&world), Vec3::new(60., 55., 0.));
+    }
+}
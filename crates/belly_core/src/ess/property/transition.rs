@@ -0,0 +1,560 @@
+use std::any::Any;
+
+use super::{Length, Property, StylePropertyMethods, StylePropertyToken, UiTransform};
+use crate::ElementsError;
+use bevy::{prelude::*, utils::HashMap};
+use tagstr::*;
+
+/// Describes how the intermediate values of the transition are
+/// calculated: [easing function](https://developer.mozilla.org/en-US/docs/Web/CSS/easing-function)
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum EasingFunction {
+    /// `linear`
+    Linear,
+    /// `ease`, same as `cubic-bezier(0.25, 0.1, 0.25, 1.0)`
+    #[default]
+    Ease,
+    /// `ease-in`, same as `cubic-bezier(0.42, 0.0, 1.0, 1.0)`
+    EaseIn,
+    /// `ease-out`, same as `cubic-bezier(0.0, 0.0, 0.58, 1.0)`
+    EaseOut,
+    /// `ease-in-out`, same as `cubic-bezier(0.42, 0.0, 0.58, 1.0)`
+    EaseInOut,
+    /// `cubic-bezier(x1, y1, x2, y2)`
+    CubicBezier(f32, f32, f32, f32),
+    /// `steps(count, jump-start|jump-end)`, `step-start` & `step-end`.
+    /// The second field is `true` when the jump happens at the start
+    /// of each step.
+    Steps(u32, bool),
+}
+
+impl EasingFunction {
+    /// Maps the progress of the transition `t` (`0.0..=1.0`) into the
+    /// progress of the value.
+    pub fn ease(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match *self {
+            EasingFunction::Linear => t,
+            EasingFunction::Ease => cubic_bezier(0.25, 0.1, 0.25, 1.0, t),
+            EasingFunction::EaseIn => cubic_bezier(0.42, 0.0, 1.0, 1.0, t),
+            EasingFunction::EaseOut => cubic_bezier(0.0, 0.0, 0.58, 1.0, t),
+            EasingFunction::EaseInOut => cubic_bezier(0.42, 0.0, 0.58, 1.0, t),
+            EasingFunction::CubicBezier(x1, y1, x2, y2) => cubic_bezier(x1, y1, x2, y2, t),
+            EasingFunction::Steps(steps, start) => {
+                let steps = steps.max(1) as f32;
+                let step = if start {
+                    (t * steps).ceil()
+                } else {
+                    (t * steps).floor()
+                };
+                (step / steps).min(1.0)
+            }
+        }
+    }
+}
+
+fn cubic_bezier(x1: f32, y1: f32, x2: f32, y2: f32, x: f32) -> f32 {
+    if x <= 0.0 || x >= 1.0 {
+        return x;
+    }
+    let bezier = |a: f32, b: f32, s: f32| {
+        3.0 * a * s * (1.0 - s).powi(2) + 3.0 * b * s * s * (1.0 - s) + s.powi(3)
+    };
+    let derivative = |a: f32, b: f32, s: f32| {
+        3.0 * a * (1.0 - s).powi(2) + 6.0 * (b - a) * s * (1.0 - s) + 3.0 * (1.0 - b) * s * s
+    };
+    // newton's method converges fast for the most of the curves
+    let mut s = x;
+    for _ in 0..8 {
+        let dx = bezier(x1, x2, s) - x;
+        if dx.abs() < 1e-5 {
+            return bezier(y1, y2, s);
+        }
+        let d = derivative(x1, x2, s);
+        if d.abs() < 1e-6 {
+            break;
+        }
+        s = (s - dx / d).clamp(0.0, 1.0);
+    }
+    // and bisection handles the rest
+    let (mut lo, mut hi) = (0.0, 1.0);
+    s = x;
+    for _ in 0..32 {
+        let value = bezier(x1, x2, s);
+        if (value - x).abs() < 1e-5 {
+            break;
+        }
+        if value < x {
+            lo = s;
+        } else {
+            hi = s;
+        }
+        s = (lo + hi) * 0.5;
+    }
+    bezier(y1, y2, s)
+}
+
+impl TryFrom<&[StylePropertyToken]> for EasingFunction {
+    type Error = ElementsError;
+    fn try_from(value: &[StylePropertyToken]) -> Result<Self, Self::Error> {
+        let Some(token) = value.first() else {
+            return Err(ElementsError::InvalidPropertyValue(format!(
+                "Expected easing function, got nothing"
+            )));
+        };
+        match token {
+            StylePropertyToken::Identifier(ident) => match ident.as_str() {
+                "linear" => Ok(EasingFunction::Linear),
+                "ease" => Ok(EasingFunction::Ease),
+                "ease-in" => Ok(EasingFunction::EaseIn),
+                "ease-out" => Ok(EasingFunction::EaseOut),
+                "ease-in-out" => Ok(EasingFunction::EaseInOut),
+                "step-start" => Ok(EasingFunction::Steps(1, true)),
+                "step-end" => Ok(EasingFunction::Steps(1, false)),
+                ident => Err(ElementsError::InvalidPropertyValue(format!(
+                    "Unknown easing function `{ident}`"
+                ))),
+            },
+            StylePropertyToken::Function(func) if func.name == "cubic-bezier" => {
+                let args = func
+                    .args
+                    .iter()
+                    .map(|a| std::slice::from_ref(a).f32())
+                    .collect::<Result<Vec<_>, _>>()?;
+                if args.len() != 4 {
+                    return Err(ElementsError::InvalidPropertyValue(format!(
+                        "cubic-bezier() expects 4 arguments, got {}",
+                        args.len()
+                    )));
+                }
+                if !(0.0..=1.0).contains(&args[0]) || !(0.0..=1.0).contains(&args[2]) {
+                    return Err(ElementsError::InvalidPropertyValue(format!(
+                        "cubic-bezier() x values should be in [0, 1] range"
+                    )));
+                }
+                Ok(EasingFunction::CubicBezier(
+                    args[0], args[1], args[2], args[3],
+                ))
+            }
+            StylePropertyToken::Function(func) if func.name == "steps" => {
+                let Some(StylePropertyToken::Number(steps)) = func.args.first() else {
+                    return Err(ElementsError::InvalidPropertyValue(format!(
+                        "steps() expects number of steps as first argument"
+                    )));
+                };
+                let start = match func.args.get(1) {
+                    None => false,
+                    Some(t) if t.is_ident("jump-end") || t.is_ident("end") => false,
+                    Some(t) if t.is_ident("jump-start") || t.is_ident("start") => true,
+                    Some(t) => {
+                        return Err(ElementsError::InvalidPropertyValue(format!(
+                            "Unsupported step position `{}`",
+                            t.to_string()
+                        )))
+                    }
+                };
+                Ok(EasingFunction::Steps(steps.to_int().max(1) as u32, start))
+            }
+            token => Err(ElementsError::InvalidPropertyValue(format!(
+                "Expected easing function, got `{}`",
+                token.to_string()
+            ))),
+        }
+    }
+}
+
+/// Parses the time value (`200ms`, `0.5s`, `0`) into seconds
+pub fn time(value: &[StylePropertyToken]) -> Result<f32, ElementsError> {
+    match value.first() {
+        Some(StylePropertyToken::Dimension(val, unit)) if unit == "s" => Ok(val.into()),
+        Some(StylePropertyToken::Dimension(val, unit)) if unit == "ms" => {
+            Ok(val.to_float() / 1000.0)
+        }
+        Some(StylePropertyToken::Number(val)) if val.to_float() == 0.0 => Ok(0.0),
+        Some(token) => Err(ElementsError::InvalidPropertyValue(format!(
+            "Expected time, got `{}`",
+            token.to_string()
+        ))),
+        None => Err(ElementsError::InvalidPropertyValue(format!(
+            "Expected time, got nothing"
+        ))),
+    }
+}
+
+/// Values which could be smoothly changed by transitions.
+pub trait Interpolate: Clone + PartialEq + Any {
+    /// Returns the value between `self` (`t == 0.0`) and `to` (`t == 1.0`)
+    /// or `None` if the values can't be interpolated.
+    fn interpolate(&self, to: &Self, t: f32) -> Option<Self>;
+}
+
+impl Interpolate for f32 {
+    fn interpolate(&self, to: &Self, t: f32) -> Option<Self> {
+        Some(self + (to - self) * t)
+    }
+}
+
+impl Interpolate for Color {
+    fn interpolate(&self, to: &Self, t: f32) -> Option<Self> {
+        let [fr, fg, fb, fa] = self.as_rgba_f32();
+        let [tr, tg, tb, ta] = to.as_rgba_f32();
+        Some(Color::rgba(
+            fr.interpolate(&tr, t)?,
+            fg.interpolate(&tg, t)?,
+            fb.interpolate(&tb, t)?,
+            fa.interpolate(&ta, t)?,
+        ))
+    }
+}
+
+impl Interpolate for Val {
+    fn interpolate(&self, to: &Self, t: f32) -> Option<Self> {
+        match (*self, *to) {
+            (Val::Auto, Val::Auto) => Some(Val::Auto),
+            (Val::Px(f), Val::Px(to)) => Some(Val::Px(f.interpolate(&to, t)?)),
+            (Val::Percent(f), Val::Percent(to)) => Some(Val::Percent(f.interpolate(&to, t)?)),
+            (Val::Vw(f), Val::Vw(to)) => Some(Val::Vw(f.interpolate(&to, t)?)),
+            (Val::Vh(f), Val::Vh(to)) => Some(Val::Vh(f.interpolate(&to, t)?)),
+            (Val::VMin(f), Val::VMin(to)) => Some(Val::VMin(f.interpolate(&to, t)?)),
+            (Val::VMax(f), Val::VMax(to)) => Some(Val::VMax(f.interpolate(&to, t)?)),
+            _ => None,
+        }
+    }
+}
+
+impl Interpolate for UiRect {
+    fn interpolate(&self, to: &Self, t: f32) -> Option<Self> {
+        Some(UiRect {
+            left: self.left.interpolate(&to.left, t)?,
+            right: self.right.interpolate(&to.right, t)?,
+            top: self.top.interpolate(&to.top, t)?,
+            bottom: self.bottom.interpolate(&to.bottom, t)?,
+        })
+    }
+}
+
+fn downcast<T: Any, U: Any>(value: &T) -> Option<&U> {
+    (value as &dyn Any).downcast_ref::<U>()
+}
+
+fn interpolate_as<T: Any, U: Interpolate>(from: &T, to: &T, t: f32) -> Option<Option<T>> {
+    let from = downcast::<T, U>(from)?;
+    let to = downcast::<T, U>(to)?;
+    let value: Box<dyn Any> = Box::new(from.interpolate(to, t)?);
+    Some(value.downcast::<T>().ok().map(|v| *v))
+}
+
+fn equals_as<T: Any, U: Interpolate>(a: &T, b: &T) -> Option<bool> {
+    Some(downcast::<T, U>(a)? == downcast::<T, U>(b)?)
+}
+
+/// Interpolates the values of any [`Interpolate`] type known by belly:
+/// `f32`, `Color`, `Val`, `Length` and `UiRect`. Returns `None` for the other types
+/// or if the values can't be interpolated (`Val::Px` into `Val::Percent`).
+pub fn interpolate<T: Any>(from: &T, to: &T, t: f32) -> Option<T> {
+    interpolate_as::<T, Color>(from, to, t)
+        .or_else(|| interpolate_as::<T, Val>(from, to, t))
+        .or_else(|| interpolate_as::<T, Length>(from, to, t))
+        .or_else(|| interpolate_as::<T, UiRect>(from, to, t))
+        .or_else(|| interpolate_as::<T, f32>(from, to, t))
+        .or_else(|| interpolate_as::<T, UiTransform>(from, to, t))
+        .flatten()
+}
+
+fn equals<T: Any>(a: &T, b: &T) -> bool {
+    equals_as::<T, Color>(a, b)
+        .or_else(|| equals_as::<T, Val>(a, b))
+        .or_else(|| equals_as::<T, Length>(a, b))
+        .or_else(|| equals_as::<T, UiRect>(a, b))
+        .or_else(|| equals_as::<T, f32>(a, b))
+        .or_else(|| equals_as::<T, UiTransform>(a, b))
+        .unwrap_or(false)
+}
+
+/// Timing of the single property transition
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TransitionSpec {
+    /// Duration in seconds
+    pub duration: f32,
+    /// Delay in seconds
+    pub delay: f32,
+    pub easing: EasingFunction,
+}
+
+/// Transition settings of the element, defined by the `transition-*`
+/// properties. Lists are matched the css way: the `properties` list
+/// defines the number of transitions, the other lists are repeated
+/// when they are shorter.
+#[derive(Component, Debug, Clone, Default)]
+pub struct Transitions {
+    pub properties: Vec<Tag>,
+    pub durations: Vec<f32>,
+    pub easings: Vec<EasingFunction>,
+    pub delays: Vec<f32>,
+}
+
+impl Transitions {
+    /// Returns the transition timing for the `property` if it should
+    /// be transitioned.
+    pub fn get(&self, property: Tag) -> Option<TransitionSpec> {
+        let idx = self
+            .properties
+            .iter()
+            .rposition(|p| *p == property || *p == tag!("all"))?;
+        let cycle = |len: usize| idx % len.max(1);
+        let duration = self.durations.get(cycle(self.durations.len())).copied();
+        let delay = self.delays.get(cycle(self.delays.len())).copied();
+        let easing = self.easings.get(cycle(self.easings.len())).copied();
+        let spec = TransitionSpec {
+            duration: duration.unwrap_or(0.0).max(0.0),
+            delay: delay.unwrap_or(0.0),
+            easing: easing.unwrap_or_default(),
+        };
+        if spec.duration == 0.0 && spec.delay <= 0.0 {
+            None
+        } else {
+            Some(spec)
+        }
+    }
+}
+
+struct RunningTransition<V> {
+    from: V,
+    to: V,
+    elapsed: f32,
+    spec: TransitionSpec,
+}
+
+impl<V> RunningTransition<V> {
+    fn progress(&self) -> f32 {
+        if self.elapsed < 0.0 {
+            0.0
+        } else if self.spec.duration <= 0.0 {
+            1.0
+        } else {
+            (self.elapsed / self.spec.duration).min(1.0)
+        }
+    }
+}
+
+/// The state of the transitions of the property `T`: the last values
+/// applied to the elements with [`Transitions`] and the transitions
+/// in progress.
+#[derive(Resource)]
+pub struct PropertyTransitions<T: Property> {
+    values: HashMap<Entity, T::Item>,
+    running: HashMap<Entity, RunningTransition<T::Item>>,
+}
+
+impl<T: Property> Default for PropertyTransitions<T> {
+    fn default() -> Self {
+        PropertyTransitions {
+            values: Default::default(),
+            running: Default::default(),
+        }
+    }
+}
+
+impl<T: Property> PropertyTransitions<T> {
+    /// Takes a copy of the interpolable value, `None` means the value
+    /// can't be transitioned.
+    fn snapshot(value: &T::Item) -> Option<T::Item> {
+        T::interpolate(value, value, 1.0)
+    }
+
+    fn current(&self, entity: Entity) -> Option<T::Item> {
+        if let Some(transition) = self.running.get(&entity) {
+            let t = transition.spec.easing.ease(transition.progress());
+            T::interpolate(&transition.from, &transition.to, t)
+        } else {
+            self.values.get(&entity).and_then(Self::snapshot)
+        }
+    }
+
+    /// Registers the new `value` of the property applied to the `entity`.
+    /// Returns `true` if the transition to this value is started (or
+    /// is already running) and the value shouldn't be applied immediately.
+    pub(crate) fn start(
+        &mut self,
+        entity: Entity,
+        value: &T::Item,
+        spec: Option<TransitionSpec>,
+    ) -> bool {
+        let Some(to) = Self::snapshot(value) else {
+            return false;
+        };
+        if let Some(running) = self.running.get(&entity) {
+            if equals(&running.to, value) {
+                return true;
+            }
+        }
+        let from = self.current(entity);
+        self.running.remove(&entity);
+        self.values.remove(&entity);
+        let Some(spec) = spec else {
+            self.values.insert(entity, to);
+            return false;
+        };
+        let Some(from) = from else {
+            self.values.insert(entity, to);
+            return false;
+        };
+        if equals(&from, value) || T::interpolate(&from, &to, 0.0).is_none() {
+            self.values.insert(entity, to);
+            return false;
+        }
+        self.running.insert(
+            entity,
+            RunningTransition {
+                from,
+                to,
+                elapsed: -spec.delay,
+                spec,
+            },
+        );
+        true
+    }
+
+    /// Advances the running transitions by `delta` seconds and returns
+    /// the values to apply.
+    pub(crate) fn advance(&mut self, delta: f32) -> Vec<(Entity, T::Item)> {
+        let mut values = vec![];
+        let mut finished = vec![];
+        for (entity, transition) in self.running.iter_mut() {
+            transition.elapsed += delta;
+            if transition.elapsed < 0.0 {
+                continue;
+            }
+            let progress = transition.progress();
+            let t = transition.spec.easing.ease(progress);
+            if let Some(value) = T::interpolate(&transition.from, &transition.to, t) {
+                values.push((*entity, value));
+            }
+            if progress >= 1.0 {
+                finished.push(*entity);
+            }
+        }
+        for entity in finished {
+            if let Some(transition) = self.running.remove(&entity) {
+                self.values.insert(entity, transition.to);
+            }
+        }
+        values
+    }
+
+    pub(crate) fn is_running(&self) -> bool {
+        !self.running.is_empty()
+    }
+
+    pub(crate) fn remove(&mut self, entity: Entity) {
+        self.values.remove(&entity);
+        self.running.remove(&entity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ess::StyleProperty;
+
+    fn easing(value: &str) -> Result<EasingFunction, ElementsError> {
+        let prop = StyleProperty::try_from(value).unwrap();
+        EasingFunction::try_from(&prop[..])
+    }
+
+    #[test]
+    fn parse_easing_functions() {
+        assert_eq!(easing("linear"), Ok(EasingFunction::Linear));
+        assert_eq!(easing("ease-out"), Ok(EasingFunction::EaseOut));
+        assert_eq!(easing("step-start"), Ok(EasingFunction::Steps(1, true)));
+        assert_eq!(easing("steps(4)"), Ok(EasingFunction::Steps(4, false)));
+        assert_eq!(
+            easing("steps(2, jump-start)"),
+            Ok(EasingFunction::Steps(2, true))
+        );
+        assert_eq!(
+            easing("cubic-bezier(0.1, 0.7, 1.0, 0.1)"),
+            Ok(EasingFunction::CubicBezier(0.1, 0.7, 1.0, 0.1))
+        );
+        assert!(easing("cubic-bezier(2, 0, 0, 1)").is_err());
+        assert!(easing("bounce").is_err());
+    }
+
+    #[test]
+    fn easing_curves() {
+        for easing in [
+            EasingFunction::Linear,
+            EasingFunction::Ease,
+            EasingFunction::EaseIn,
+            EasingFunction::EaseOut,
+            EasingFunction::EaseInOut,
+        ] {
+            assert_eq!(easing.ease(0.0), 0.0);
+            assert_eq!(easing.ease(1.0), 1.0);
+        }
+        assert_eq!(EasingFunction::Linear.ease(0.3), 0.3);
+        assert!(EasingFunction::EaseIn.ease(0.5) < 0.5);
+        assert!(EasingFunction::EaseOut.ease(0.5) > 0.5);
+        assert!((EasingFunction::EaseInOut.ease(0.5) - 0.5).abs() < 1e-3);
+        assert_eq!(EasingFunction::Steps(4, false).ease(0.3), 0.25);
+        assert_eq!(EasingFunction::Steps(4, true).ease(0.3), 0.5);
+    }
+
+    #[test]
+    fn parse_time() {
+        let parse = |v: &str| time(&StyleProperty::try_from(v).unwrap()[..]);
+        assert_eq!(parse("200ms"), Ok(0.2));
+        assert_eq!(parse("1.5s"), Ok(1.5));
+        assert_eq!(parse("0"), Ok(0.0));
+        assert!(parse("10px").is_err());
+    }
+
+    #[test]
+    fn interpolate_values() {
+        assert_eq!(interpolate(&0.0f32, &10.0, 0.25), Some(2.5));
+        assert_eq!(
+            interpolate(&Val::Px(10.), &Val::Px(20.), 0.5),
+            Some(Val::Px(15.))
+        );
+        assert_eq!(interpolate(&Val::Px(10.), &Val::Percent(20.), 0.5), None);
+        assert_eq!(
+            interpolate(
+                &Color::rgba(0., 0., 0., 0.),
+                &Color::rgba(1., 1., 1., 1.),
+                0.5
+            ),
+            Some(Color::rgba(0.5, 0.5, 0.5, 0.5))
+        );
+        assert_eq!(interpolate(&Some(1.0f32), &Some(2.0), 0.5), None);
+        assert_eq!(interpolate(&"a".to_string(), &"b".to_string(), 0.5), None);
+    }
+
+    #[test]
+    fn transitions_lists() {
+        let transitions = Transitions {
+            properties: vec![tag!("color"), tag!("width"), tag!("height")],
+            durations: vec![1.0, 0.5],
+            easings: vec![EasingFunction::Linear],
+            delays: vec![],
+        };
+        let height = transitions.get(tag!("height")).unwrap();
+        assert_eq!(height.duration, 1.0);
+        assert_eq!(height.easing, EasingFunction::Linear);
+        assert_eq!(height.delay, 0.0);
+        assert_eq!(transitions.get(tag!("width")).unwrap().duration, 0.5);
+        assert_eq!(transitions.get(tag!("margin-left")), None);
+
+        let transitions = Transitions {
+            properties: vec![tag!("all")],
+            durations: vec![0.2],
+            ..default()
+        };
+        assert_eq!(transitions.get(tag!("margin-left")).unwrap().duration, 0.2);
+        let transitions = Transitions {
+            properties: vec![tag!("none")],
+            durations: vec![0.2],
+            ..default()
+        };
+        assert_eq!(transitions.get(tag!("color")), None);
+    }
+}
@@ -1,5 +1,6 @@
-use crate::element::Element;
-use bevy::prelude::{default, Changed, Entity, Parent, Query};
+use super::parser::parse_selector;
+use crate::element::{Element, ElementPosition};
+use bevy::prelude::{default, Changed, Children, Entity, Parent, Query};
 use smallvec::{smallvec, SmallVec};
 use std::ops::Neg;
 use tagstr::Tag;
@@ -34,7 +35,7 @@ impl Neg for SelectorWeight {
     }
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone)]
 pub struct SelectorIndex(usize);
 
 impl SelectorIndex {
@@ -43,15 +44,150 @@ impl SelectorIndex {
     }
 }
 
-#[derive(Debug)]
+/// Describes how the value of `[name<op>value]` selector
+/// is compared with the element attribute.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttributeMatcher {
+    /// `[name]`: attribute is defined
+    Exists,
+    /// `[name=value]`: attribute is exactly `value`
+    Equals(String),
+    /// `[name~=value]`: attribute is a whitespace-separated list containing `value`
+    Includes(String),
+    /// `[name|=value]`: attribute is `value` or starts with `value-`
+    DashMatch(String),
+    /// `[name^=value]`: attribute starts with `value`
+    Prefix(String),
+    /// `[name$=value]`: attribute ends with `value`
+    Suffix(String),
+    /// `[name*=value]`: attribute contains `value`
+    Substring(String),
+}
+
+impl AttributeMatcher {
+    pub fn matches(&self, attribute: &str) -> bool {
+        match self {
+            AttributeMatcher::Exists => true,
+            AttributeMatcher::Equals(value) => attribute == value,
+            AttributeMatcher::Includes(value) => {
+                attribute.split_whitespace().any(|part| part == value)
+            }
+            AttributeMatcher::DashMatch(value) => {
+                attribute == value
+                    || attribute
+                        .strip_prefix(value.as_str())
+                        .map(|rest| rest.starts_with('-'))
+                        .unwrap_or(false)
+            }
+            AttributeMatcher::Prefix(value) => attribute.starts_with(value.as_str()),
+            AttributeMatcher::Suffix(value) => attribute.ends_with(value.as_str()),
+            AttributeMatcher::Substring(value) => attribute.contains(value.as_str()),
+        }
+    }
+
+    pub fn to_string(&self) -> String {
+        match self {
+            AttributeMatcher::Exists => "".to_string(),
+            AttributeMatcher::Equals(v) => format!("=\"{}\"", v),
+            AttributeMatcher::Includes(v) => format!("~=\"{}\"", v),
+            AttributeMatcher::DashMatch(v) => format!("|=\"{}\"", v),
+            AttributeMatcher::Prefix(v) => format!("^=\"{}\"", v),
+            AttributeMatcher::Suffix(v) => format!("$=\"{}\"", v),
+            AttributeMatcher::Substring(v) => format!("*=\"{}\"", v),
+        }
+    }
+}
+
+/// Pseudo-classes matching elements by their position in the tree
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StructuralPseudoClass {
+    /// `:first-child`
+    FirstChild,
+    /// `:last-child`
+    LastChild,
+    /// `:only-child`
+    OnlyChild,
+    /// `:empty`: element without any children
+    Empty,
+    /// `:nth-child(An+B)`
+    NthChild(i32, i32),
+    /// `:nth-last-child(An+B)`
+    NthLastChild(i32, i32),
+}
+
+impl StructuralPseudoClass {
+    pub fn from_ident(ident: &str) -> Option<StructuralPseudoClass> {
+        match ident {
+            "first-child" => Some(StructuralPseudoClass::FirstChild),
+            "last-child" => Some(StructuralPseudoClass::LastChild),
+            "only-child" => Some(StructuralPseudoClass::OnlyChild),
+            "empty" => Some(StructuralPseudoClass::Empty),
+            _ => None,
+        }
+    }
+
+    pub fn from_function(name: &str, a: i32, b: i32) -> Option<StructuralPseudoClass> {
+        match name {
+            "nth-child" => Some(StructuralPseudoClass::NthChild(a, b)),
+            "nth-last-child" => Some(StructuralPseudoClass::NthLastChild(a, b)),
+            _ => None,
+        }
+    }
+
+    pub fn matches(&self, node: &impl EmlNode) -> bool {
+        let index = node.sibling_index() as i32;
+        let count = node.sibling_count() as i32;
+        match *self {
+            StructuralPseudoClass::FirstChild => index == 0,
+            StructuralPseudoClass::LastChild => index == count - 1,
+            StructuralPseudoClass::OnlyChild => count == 1,
+            StructuralPseudoClass::Empty => node.children_count() == 0,
+            StructuralPseudoClass::NthChild(a, b) => nth(a, b, index + 1),
+            StructuralPseudoClass::NthLastChild(a, b) => nth(a, b, count - index),
+        }
+    }
+
+    pub fn to_string(&self) -> String {
+        match self {
+            StructuralPseudoClass::FirstChild => ":first-child".to_string(),
+            StructuralPseudoClass::LastChild => ":last-child".to_string(),
+            StructuralPseudoClass::OnlyChild => ":only-child".to_string(),
+            StructuralPseudoClass::Empty => ":empty".to_string(),
+            StructuralPseudoClass::NthChild(a, b) => format!(":nth-child({}n{:+})", a, b),
+            StructuralPseudoClass::NthLastChild(a, b) => format!(":nth-last-child({}n{:+})", a, b),
+        }
+    }
+}
+
+/// Checks if there is non-negative integer `n` such as `a*n + b == position`
+fn nth(a: i32, b: i32, position: i32) -> bool {
+    if a == 0 {
+        position == b
+    } else {
+        let offset = position - b;
+        offset % a == 0 && offset / a >= 0
+    }
+}
+
+#[derive(Debug, Clone)]
 pub enum SelectorElement {
     AnyChild,
     DirectChild,
+    /// `a + b`: node immediately follows the sibling
+    AdjacentSibling,
+    /// `a ~ b`: node follows the sibling (not necessary immediately)
+    GeneralSibling,
     Any,
     Id(Tag),
     Class(Tag),
     Tag(Tag),
     State(Tag),
+    Attribute(Tag, AttributeMatcher),
+    Structural(StructuralPseudoClass),
+    /// `:not(a, b)`: node matches none of the selectors
+    Not(Vec<Selector>),
+    /// `:is(a, b)`: node matches any of the selectors
+    Is(Vec<Selector>),
 }
 
 impl SelectorElement {
@@ -69,8 +205,26 @@ impl SelectorElement {
         }
     }
 
+    pub fn is_adjacent_sibling(&self) -> bool {
+        match self {
+            SelectorElement::AdjacentSibling => true,
+            _ => false,
+        }
+    }
+
+    pub fn is_general_sibling(&self) -> bool {
+        match self {
+            SelectorElement::GeneralSibling => true,
+            _ => false,
+        }
+    }
+
+    pub fn is_sibling(&self) -> bool {
+        self.is_adjacent_sibling() || self.is_general_sibling()
+    }
+
     pub fn is_separator(&self) -> bool {
-        self.is_any_child() || self.is_direct_child()
+        self.is_any_child() || self.is_direct_child() || self.is_sibling()
     }
 
     pub fn is_value(&self) -> bool {
@@ -84,6 +238,13 @@ impl SelectorElement {
             SelectorElement::State(attr) => node.has_state(attr),
             SelectorElement::Tag(tag) => node.has_tag(tag),
             SelectorElement::Class(class) => node.has_class(class),
+            SelectorElement::Attribute(name, matcher) => node
+                .attribute(name)
+                .map(|value| matcher.matches(value))
+                .unwrap_or(false),
+            SelectorElement::Structural(pseudo) => pseudo.matches(node),
+            SelectorElement::Not(selectors) => !selectors.iter().any(|s| s.describes(node)),
+            SelectorElement::Is(selectors) => selectors.iter().any(|s| s.describes(node)),
             _ => false,
         }
     }
@@ -92,11 +253,17 @@ impl SelectorElement {
         match self {
             SelectorElement::AnyChild => " ".to_string(),
             SelectorElement::DirectChild => " > ".to_string(),
+            SelectorElement::AdjacentSibling => " + ".to_string(),
+            SelectorElement::GeneralSibling => " ~ ".to_string(),
             SelectorElement::Any => "*".to_string(),
             SelectorElement::State(s) => format!(":{}", s),
             SelectorElement::Tag(t) => format!("{}", t),
             SelectorElement::Class(c) => format!(".{}", c),
             SelectorElement::Id(i) => format!("#{}", i),
+            SelectorElement::Attribute(n, m) => format!("[{}{}]", n, m.to_string()),
+            SelectorElement::Structural(s) => s.to_string(),
+            SelectorElement::Not(s) => format!(":not({})", Selector::list_to_string(s)),
+            SelectorElement::Is(s) => format!(":is({})", Selector::list_to_string(s)),
         }
     }
 
@@ -104,11 +271,19 @@ impl SelectorElement {
         match self {
             SelectorElement::AnyChild => 0,
             SelectorElement::DirectChild => 1,
+            SelectorElement::AdjacentSibling => 1,
+            SelectorElement::GeneralSibling => 0,
             SelectorElement::Any => 0,
             SelectorElement::Tag(_) => 1,
             SelectorElement::State(_) => 10,
             SelectorElement::Class(_) => 10,
+            SelectorElement::Attribute(_, _) => 10,
+            SelectorElement::Structural(_) => 10,
             SelectorElement::Id(_) => 100,
+            // the most specific selector from the list
+            SelectorElement::Not(s) | SelectorElement::Is(s) => {
+                s.iter().map(|s| s.weight.0 as u32).max().unwrap_or(0)
+            }
         }
     }
 }
@@ -131,7 +306,7 @@ impl<'a> SelectorEntry<'a> {
     fn next(&self) -> Option<SelectorEntry<'a>> {
         let mut offset = self.offset;
         let elements = self.elements;
-        if elements[offset].is_any_child() || elements[offset].is_direct_child() {
+        if elements[offset].is_separator() {
             offset += 1;
             if offset >= elements.len() {
                 return None;
@@ -154,7 +329,7 @@ impl<'a> SelectorEntry<'a> {
     pub fn len(&self) -> u8 {
         let mut len = 0;
         for element in self.elements.iter().skip(self.offset) {
-            if element.is_any_child() || element.is_direct_child() {
+            if element.is_separator() {
                 return len;
             } else {
                 len += 1;
@@ -171,6 +346,18 @@ impl<'a> SelectorEntry<'a> {
         self.elements[self.offset].is_direct_child()
     }
 
+    pub fn is_adjacent_sibling(&self) -> bool {
+        self.elements[self.offset].is_adjacent_sibling()
+    }
+
+    pub fn is_general_sibling(&self) -> bool {
+        self.elements[self.offset].is_general_sibling()
+    }
+
+    pub fn is_sibling(&self) -> bool {
+        self.elements[self.offset].is_sibling()
+    }
+
     pub fn is_separator(&self) -> bool {
         self.elements[self.offset].is_separator()
     }
@@ -182,8 +369,7 @@ impl<'a> SelectorEntry<'a> {
     pub fn has_id(&self, id: Tag) -> bool {
         for element in self.elements.iter().skip(self.offset) {
             match element {
-                SelectorElement::DirectChild => return false,
-                SelectorElement::AnyChild => return false,
+                e if e.is_separator() => return false,
                 SelectorElement::Id(element_id) if id == *element_id => return true,
                 _ => continue,
             }
@@ -194,8 +380,7 @@ impl<'a> SelectorEntry<'a> {
     pub fn get_id(&self) -> Option<Tag> {
         for element in self.elements.iter().skip(self.offset) {
             match element {
-                SelectorElement::DirectChild => return None,
-                SelectorElement::AnyChild => return None,
+                e if e.is_separator() => return None,
                 SelectorElement::Id(id) => return Some(*id),
                 _ => continue,
             }
@@ -206,7 +391,7 @@ impl<'a> SelectorEntry<'a> {
     pub fn has_class(&self, class: Tag) -> bool {
         for element in self.elements.iter().skip(self.offset) {
             match element {
-                SelectorElement::AnyChild => return false,
+                e if e.is_separator() => return false,
                 SelectorElement::Class(element_class) if class == *element_class => return true,
                 _ => continue,
             }
@@ -217,7 +402,7 @@ impl<'a> SelectorEntry<'a> {
     pub fn has_tag(&self, tag: Tag) -> bool {
         for element in self.elements.iter().skip(self.offset) {
             match element {
-                SelectorElement::AnyChild => return false,
+                e if e.is_separator() => return false,
                 SelectorElement::Tag(element_tag) if tag == *element_tag => return true,
                 _ => continue,
             }
@@ -228,7 +413,7 @@ impl<'a> SelectorEntry<'a> {
     pub fn describes_node(&self, node: &impl EmlNode) -> bool {
         let mut offset = self.offset;
         let elements = self.elements;
-        if elements[offset].is_any_child() || elements[offset].is_direct_child() {
+        if elements[offset].is_separator() {
             return false;
         }
         while offset < elements.len() && elements[offset].is_value() {
@@ -242,7 +427,7 @@ impl<'a> SelectorEntry<'a> {
     }
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone)]
 pub struct Selector {
     pub index: SelectorIndex,
     pub weight: SelectorWeight,
@@ -292,6 +477,23 @@ impl Selector {
         branch.tail().fits(&slice).is_some()
     }
 
+    /// Checks if selector matches the `node` (and the node's ancestors)
+    pub fn describes(&self, node: &impl EmlNode) -> bool {
+        if self.elements.is_empty() {
+            return false;
+        }
+        let slice = SelectorEntry::new(&self.elements);
+        node.fits(&slice).is_some()
+    }
+
+    pub fn list_to_string(selectors: &[Selector]) -> String {
+        selectors
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
     pub fn to_string(&self) -> String {
         let mut result = "".to_string();
         for token in self.elements.iter().rev() {
@@ -300,12 +502,22 @@ impl Selector {
         result
     }
 
+    /// Returns the id of the top-most selector entry if the rest of the
+    /// selector describes its descendants (`#root > a + b`, but not `#root + a`)
     pub fn get_root_id(&self) -> Option<Tag> {
         let mut entry = self.tail();
+        let mut sibling = false;
         while let Some(next) = entry.next() {
+            if next.is_separator() {
+                sibling = next.is_sibling();
+            }
             entry = next;
         }
-        entry.get_id()
+        if sibling {
+            None
+        } else {
+            entry.get_id()
+        }
     }
 
     pub fn overridable_by_props(&self) -> bool {
@@ -323,8 +535,17 @@ pub trait EmlNode: Sized {
     fn has_tag(&self, tag: &Tag) -> bool;
     fn has_state(&self, tag: &Tag) -> bool;
     fn has_class(&self, class: &Tag) -> bool;
+    fn attribute(&self, name: &Tag) -> Option<&str>;
+    /// Index of the node among the element children of its parent
+    fn sibling_index(&self) -> usize;
+    /// Number of the element children of the node parent, including the node itself
+    fn sibling_count(&self) -> usize;
+    /// Number of the node children (elements or text)
+    fn children_count(&self) -> usize;
 
     fn next(&self) -> Option<Self>;
+    /// The closest preceding element sibling of the node
+    fn previous_sibling(&self) -> Option<Self>;
 
     fn fits(&self, selector: &SelectorEntry) -> Option<u8> {
         if selector.is_direct_child() {
@@ -353,18 +574,30 @@ pub trait EmlNode: Sized {
             } else {
                 None
             }
-        } else if selector.describes_node(self) {
-            match (self.next(), selector.next()) {
-                (None, None) => Some(1),
-                (Some(_node), None) => Some(1),
-                (None, Some(_slice)) => None,
-                (Some(next_node), Some(next_slice)) => {
-                    if let Some(weight) = next_node.fits(&next_slice) {
-                        Some(weight + 1)
-                    } else {
-                        None
-                    }
+        } else if selector.is_adjacent_sibling() {
+            let next_selector = selector.next()?;
+            let sibling = self.previous_sibling()?;
+            sibling.fits(&next_selector).map(|weight| weight + 1)
+        } else if selector.is_general_sibling() {
+            let next_selector = selector.next()?;
+            let mut sibling = self.previous_sibling();
+            while let Some(node) = sibling {
+                if let Some(weight) = node.fits(&next_selector) {
+                    return Some(weight + 1);
                 }
+                sibling = node.previous_sibling();
+            }
+            None
+        } else if selector.describes_node(self) {
+            match selector.next() {
+                None => Some(1),
+                // sibling combinators are resolved against the same node,
+                // the rest of separators are resolved against the parent
+                Some(next_slice) if next_slice.is_sibling() => self.fits(&next_slice),
+                Some(next_slice) => self
+                    .next()
+                    .and_then(|next_node| next_node.fits(&next_slice))
+                    .map(|weight| weight + 1),
             }
         } else {
             None
@@ -372,29 +605,146 @@ pub trait EmlNode: Sized {
     }
 }
 
+struct BranchNode<'e> {
+    element: &'e Element,
+    position: ElementPosition,
+    /// Preceding element siblings, the closest one first
+    previous: SmallVec<[(&'e Element, ElementPosition); 4]>,
+}
+
+impl<'e> BranchNode<'e> {
+    fn resolve(
+        entity: Entity,
+        element: &'e Element,
+        parent: impl Fn(Entity) -> Option<Entity>,
+        children: impl Fn(Entity) -> Option<&'e Children>,
+        get_element: impl Fn(Entity) -> Option<&'e Element>,
+    ) -> BranchNode<'e> {
+        let is_element = |e| get_element(e).map(|e| !e.is_virtual()).unwrap_or(false);
+        let siblings = parent(entity).and_then(&children);
+        let position = ElementPosition::resolve(entity, siblings, children(entity), is_element);
+        let mut previous: SmallVec<[(&'e Element, ElementPosition); 4]> = SmallVec::new();
+        for sibling in siblings.iter().flat_map(|s| s.iter().copied()) {
+            if sibling == entity {
+                break;
+            }
+            if let Some(sibling_element) = get_element(sibling).filter(|e| !e.is_virtual()) {
+                let sibling_position = ElementPosition {
+                    index: previous.len(),
+                    siblings: position.siblings,
+                    children: children(sibling).map(|c| c.len()).unwrap_or(0),
+                };
+                previous.insert(0, (sibling_element, sibling_position));
+            }
+        }
+        BranchNode {
+            element,
+            position,
+            previous,
+        }
+    }
+}
+
 #[derive(Default)]
-pub struct ElementsBranch<'e>(SmallVec<[&'e Element; 12]>);
+pub struct ElementsBranch<'e>(SmallVec<[BranchNode<'e>; 12]>);
 
 impl<'e> ElementsBranch<'e> {
     pub fn new() -> ElementsBranch<'e> {
         ElementsBranch::default()
     }
 
-    pub fn insert(&mut self, element: &'e Element) {
-        self.0.push(element);
+    /// Builds the branch from the `entity` up to the root element,
+    /// collecting positions and preceding siblings of every element.
+    /// Virtual elements (like text) are not included into the branch
+    /// when `skip_virtual` is set.
+    pub fn build(
+        entity: Entity,
+        skip_virtual: bool,
+        parent: impl Fn(Entity) -> Option<Entity>,
+        children: impl Fn(Entity) -> Option<&'e Children>,
+        element: impl Fn(Entity) -> Option<&'e Element>,
+    ) -> ElementsBranch<'e> {
+        let mut branch = ElementsBranch::new();
+        let mut tail = entity;
+        while let Some(tail_element) = element(tail) {
+            if !skip_virtual || !tail_element.is_virtual() {
+                let node = BranchNode::resolve(tail, tail_element, &parent, &children, &element);
+                branch.0.push(node);
+            }
+            if let Some(parent_entity) = parent(tail) {
+                tail = parent_entity;
+            } else {
+                break;
+            }
+        }
+        branch
+    }
+
+    /// Adds the `entity` as the new tail of the branch. Used when the branch
+    /// is walked from the root down to the leaves, so the ancestors are
+    /// resolved only once.
+    pub fn push_entity(
+        &mut self,
+        entity: Entity,
+        parent: impl Fn(Entity) -> Option<Entity>,
+        children: impl Fn(Entity) -> Option<&'e Children>,
+        element: impl Fn(Entity) -> Option<&'e Element>,
+    ) -> bool {
+        let Some(tail_element) = element(entity) else {
+            return false;
+        };
+        let node = BranchNode::resolve(entity, tail_element, &parent, &children, &element);
+        self.0.insert(0, node);
+        true
+    }
+
+    /// Removes the tail added by `push_entity`
+    pub fn pop_entity(&mut self) {
+        if !self.0.is_empty() {
+            self.0.remove(0);
+        }
+    }
+
+    pub fn insert(&mut self, element: &'e Element, position: ElementPosition) {
+        self.0.push(BranchNode {
+            element,
+            position,
+            previous: SmallVec::new(),
+        });
     }
 
-    pub fn append(&mut self, element: &'e Element) {
-        self.0.insert(0, element);
+    pub fn append(&mut self, element: &'e Element, position: ElementPosition) {
+        self.0.insert(
+            0,
+            BranchNode {
+                element,
+                position,
+                previous: SmallVec::new(),
+            },
+        );
     }
 
     pub fn pop_tail(&mut self) {
         self.0.pop();
     }
 
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns the node at `idx`, where `0` is the tail and `len() - 1` is the root
+    pub(crate) fn node(&self, idx: usize) -> ElementNode<'_, 'e> {
+        ElementNode {
+            idx,
+            sibling: 0,
+            branch: self,
+        }
+    }
+
     pub fn to_string(&self) -> String {
         let mut result = "".to_string();
         for (idx, node) in self.0.iter().enumerate().rev() {
+            let node = node.element;
             if node.is_virtual() {
                 continue;
             }
@@ -410,6 +760,9 @@ impl<'e> ElementsBranch<'e> {
             for state in node.state.iter() {
                 result.push_str(&format!(":{}", state));
             }
+            for (name, value) in node.attributes.iter() {
+                result.push_str(&format!("[{}=\"{}\"]", name, value));
+            }
             if idx != 0 {
                 result.push_str(" ");
             }
@@ -419,29 +772,71 @@ impl<'e> ElementsBranch<'e> {
 }
 pub struct ElementNode<'b, 'e> {
     idx: usize,
+    /// `0` for the branch element itself, `n` for its n-th preceding sibling
+    sibling: usize,
     branch: &'b ElementsBranch<'e>,
 }
 
+impl<'b, 'e> ElementNode<'b, 'e> {
+    fn data(&self) -> (&'e Element, &'b ElementPosition) {
+        let node = &self.branch.0[self.idx];
+        if self.sibling == 0 {
+            (node.element, &node.position)
+        } else {
+            let (element, position) = &node.previous[self.sibling - 1];
+            (*element, position)
+        }
+    }
+
+    pub(crate) fn element(&self) -> &'e Element {
+        self.data().0
+    }
+
+    fn position(&self) -> &'b ElementPosition {
+        self.data().1
+    }
+}
+
 impl<'b, 'e> EmlNode for ElementNode<'b, 'e> {
     fn id(&self) -> Option<Tag> {
-        self.branch.0[self.idx].id
+        self.element().id
     }
     fn has_tag(&self, tag: &Tag) -> bool {
-        self.branch.0[self.idx]
+        let element = self.element();
+        element
             .names
             .iter()
-            .chain(self.branch.0[self.idx].aliases.iter())
+            .chain(element.aliases.iter())
             .filter(|t| *t == tag)
             .next()
             .is_some()
     }
 
     fn has_class(&self, class: &Tag) -> bool {
-        self.branch.0[self.idx].classes.contains(class)
+        self.element().classes.contains(class)
     }
 
     fn has_state(&self, tag: &Tag) -> bool {
-        self.branch.0[self.idx].state.contains(tag)
+        self.element().state.contains(tag)
+    }
+
+    fn attribute(&self, name: &Tag) -> Option<&str> {
+        self.element()
+            .attributes
+            .get(name)
+            .map(|value| value.as_str())
+    }
+
+    fn sibling_index(&self) -> usize {
+        self.position().index
+    }
+
+    fn sibling_count(&self) -> usize {
+        self.position().siblings
+    }
+
+    fn children_count(&self) -> usize {
+        self.position().children
     }
 
     fn next(&self) -> Option<Self> {
@@ -450,7 +845,25 @@ impl<'b, 'e> EmlNode for ElementNode<'b, 'e> {
         if idx >= branch.0.len() {
             None
         } else {
-            Some(ElementNode { idx, branch })
+            Some(ElementNode {
+                idx,
+                sibling: 0,
+                branch,
+            })
+        }
+    }
+
+    fn previous_sibling(&self) -> Option<Self> {
+        let sibling = self.sibling + 1;
+        let branch = self.branch;
+        if sibling > branch.0[self.idx].previous.len() {
+            None
+        } else {
+            Some(ElementNode {
+                idx: self.idx,
+                sibling,
+                branch,
+            })
         }
     }
 }
@@ -461,6 +874,7 @@ impl<'b, 'e> EmlBranch for &'b ElementsBranch<'e> {
     fn tail(&self) -> Self::Node {
         ElementNode {
             idx: 0,
+            sibling: 0,
             branch: *self,
         }
     }
@@ -469,21 +883,18 @@ impl<'b, 'e> EmlBranch for &'b ElementsBranch<'e> {
 fn _example(
     entities: Query<Entity, Changed<Element>>,
     parents: Query<&Parent>,
+    children: Query<&Children>,
     elements: Query<&Element>,
 ) {
     for entity in entities.iter() {
         // build branch for each entity
-        let mut branch = smallvec![];
-        let mut tail = entity;
-        while let Ok(element) = elements.get(tail) {
-            branch.push(element);
-            if let Ok(parent) = parents.get(tail) {
-                tail = parent.get();
-            } else {
-                break;
-            }
-        }
-        let branch = ElementsBranch(branch);
+        let branch = ElementsBranch::build(
+            entity,
+            true,
+            |e| parents.get(e).ok().map(|p| p.get()),
+            |e| children.get(e).ok(),
+            |e| elements.get(e).ok(),
+        );
 
         // can now find all matching rules
         let selector: Selector = "div span".into();
@@ -496,74 +907,22 @@ fn _example(
 impl From<&str> for Selector {
     fn from(source: &str) -> Self {
         use cssparser::{Parser, ParserInput};
-        use tagstr::*;
-        const NEXT_TAG: u8 = 0;
-        const NEXT_CLASS: u8 = 1;
-        const NEXT_ATTR: u8 = 2;
-        let mut selector = Selector::default();
-        // selector.elements.push(SelectorElement::AnyChild);
         let mut input = ParserInput::new(source);
         let mut parser = Parser::new(&mut input);
-        let mut next = NEXT_TAG;
-        while let Ok(token) = parser.next_including_whitespace() {
-            use cssparser::Token::*;
-            match token {
-                Ident(v) => {
-                    match next {
-                        NEXT_TAG => selector
-                            .elements
-                            .insert(0, SelectorElement::Tag(v.to_string().as_tag())),
-                        NEXT_CLASS => selector
-                            .elements
-                            .insert(0, SelectorElement::Class(v.to_string().as_tag())),
-                        NEXT_ATTR => selector
-                            .elements
-                            .insert(0, SelectorElement::State(v.to_string().as_tag())),
-                        _ => panic!("Invalid NEXT_TAG"),
-                    };
-                    next = NEXT_TAG;
-                }
-                IDHash(v) => {
-                    if v.is_empty() {
-                        panic!("Invalid #id selector");
-                    } else {
-                        selector
-                            .elements
-                            .insert(0, SelectorElement::Id(v.to_string().as_tag()));
-                    }
-                }
-                WhiteSpace(_) => {
-                    if let Some(token) = selector.elements.first() {
-                        if token.is_direct_child() || token.is_any_child() {
-                            continue;
-                        }
-                    }
-                    selector.elements.insert(0, SelectorElement::AnyChild);
-                }
-                Colon => next = NEXT_ATTR,
-                Delim(c) if *c == '.' => next = NEXT_CLASS,
-                Delim(c) if *c == '*' => selector.elements.insert(0, SelectorElement::Any),
-                Delim(c) if *c == '>' => {
-                    if let Some(token) = selector.elements.first() {
-                        if token.is_any_child() {
-                            selector.elements[0] = SelectorElement::DirectChild;
-                            continue;
-                        }
-                    }
-                    selector.elements.insert(0, SelectorElement::DirectChild);
-                }
-                _ => panic!("Unexpected token: {token:?}"),
-            }
+        if parser.is_exhausted() {
+            return Selector::default();
+        }
+        match parse_selector(&mut parser) {
+            Ok(selector) => selector,
+            Err(e) => panic!("Invalid selector '{}': {:?}", source, e.kind),
         }
-
-        selector
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use bevy::utils::HashSet;
+    use bevy::utils::{HashMap, HashSet};
     use tagstr::*;
 
     struct TestBranch(Vec<TestNodeData>);
@@ -574,6 +933,7 @@ mod test {
         fn tail(&self) -> Self::Node {
             TestNode {
                 index: 0,
+                sibling: 0,
                 branch: self,
             }
         }
@@ -585,25 +945,53 @@ mod test {
         tag: Tag,
         classes: HashSet<Tag>,
         params: HashSet<Tag>,
+        attributes: HashMap<Tag, String>,
+        position: ElementPosition,
+        /// preceding siblings, the closest one first
+        previous: Vec<TestNodeData>,
     }
 
     struct TestNode<'a> {
         index: usize,
+        sibling: usize,
         branch: &'a TestBranch,
     }
 
+    impl<'a> TestNode<'a> {
+        fn data(&self) -> &'a TestNodeData {
+            let node = &self.branch.0[self.index];
+            if self.sibling == 0 {
+                node
+            } else {
+                &node.previous[self.sibling - 1]
+            }
+        }
+    }
+
     impl<'a> EmlNode for TestNode<'a> {
         fn id(&self) -> Option<Tag> {
-            self.branch.0[self.index].id
+            self.data().id
         }
         fn has_tag(&self, tag: &Tag) -> bool {
-            self.branch.0[self.index].tag == *tag
+            self.data().tag == *tag
         }
         fn has_state(&self, tag: &Tag) -> bool {
-            self.branch.0[self.index].params.contains(tag)
+            self.data().params.contains(tag)
         }
         fn has_class(&self, class: &Tag) -> bool {
-            self.branch.0[self.index].classes.contains(class)
+            self.data().classes.contains(class)
+        }
+        fn attribute(&self, name: &Tag) -> Option<&str> {
+            self.data().attributes.get(name).map(|v| v.as_str())
+        }
+        fn sibling_index(&self) -> usize {
+            self.data().position.index
+        }
+        fn sibling_count(&self) -> usize {
+            self.data().position.siblings
+        }
+        fn children_count(&self) -> usize {
+            self.data().position.children
         }
         fn next(&self) -> Option<Self> {
             let index = self.index + 1;
@@ -612,6 +1000,19 @@ mod test {
             } else {
                 Some(TestNode {
                     index,
+                    sibling: 0,
+                    branch: self.branch,
+                })
+            }
+        }
+        fn previous_sibling(&self) -> Option<Self> {
+            let sibling = self.sibling + 1;
+            if sibling > self.branch.0[self.index].previous.len() {
+                None
+            } else {
+                Some(TestNode {
+                    index: self.index,
+                    sibling,
                     branch: self.branch,
                 })
             }
@@ -626,7 +1027,10 @@ mod test {
             let void = |_| ();
             for element in selector.elements {
                 match element {
-                    SelectorElement::Any | SelectorElement::DirectChild => {
+                    SelectorElement::Any
+                    | SelectorElement::DirectChild
+                    | SelectorElement::AdjacentSibling
+                    | SelectorElement::GeneralSibling => {
                         continue;
                     }
                     SelectorElement::AnyChild => {
@@ -641,6 +1045,21 @@ mod test {
                     SelectorElement::Class(class) => void(node.classes.insert(class)),
                     SelectorElement::Id(id) => node.id = Some(id),
                     SelectorElement::Tag(tag) => node.tag = tag,
+                    SelectorElement::Structural(_)
+                    | SelectorElement::Not(_)
+                    | SelectorElement::Is(_) => continue,
+                    SelectorElement::Attribute(name, matcher) => {
+                        let value = match matcher {
+                            AttributeMatcher::Exists => "".to_string(),
+                            AttributeMatcher::Equals(v)
+                            | AttributeMatcher::Includes(v)
+                            | AttributeMatcher::DashMatch(v)
+                            | AttributeMatcher::Prefix(v)
+                            | AttributeMatcher::Suffix(v)
+                            | AttributeMatcher::Substring(v) => v,
+                        };
+                        node.attributes.insert(name, value);
+                    }
                 };
                 has_values = true;
             }
@@ -781,4 +1200,236 @@ mod test {
             );
         }
     }
+
+    #[test]
+    fn selector_attributes() {
+        let branch: TestBranch = "div[kind=panel] button[mode=toggle]".into();
+        let valid_selectors: &[&str] = &[
+            "[mode]",
+            "button[mode]",
+            "[mode=toggle]",
+            "[mode=\"toggle\"]",
+            "[mode^=tog]",
+            "[mode$=gle]",
+            "[mode*=ggl]",
+            "[kind=panel] [mode]",
+            "div[kind] > button[mode=toggle]",
+        ];
+        for src in valid_selectors {
+            let selector: Selector = (*src).into();
+            assert!(
+                selector.matches(&branch),
+                "Selector '{}' should be matched",
+                src
+            );
+        }
+        let invalid_selectors: &[&str] = &[
+            "[kind]",
+            "[mode=instant]",
+            "[mode^=gle]",
+            "[mode$=tog]",
+            "[disabled]",
+            "[mode] > [kind]",
+        ];
+        for src in invalid_selectors {
+            let selector: Selector = (*src).into();
+            assert!(
+                !selector.matches(&branch),
+                "Selector '{}' shouldn't be matched",
+                src
+            );
+        }
+    }
+
+    #[test]
+    fn selector_attribute_matchers() {
+        assert!(AttributeMatcher::Includes("b".into()).matches("a b c"));
+        assert!(!AttributeMatcher::Includes("b".into()).matches("abc"));
+        assert!(AttributeMatcher::DashMatch("en".into()).matches("en"));
+        assert!(AttributeMatcher::DashMatch("en".into()).matches("en-US"));
+        assert!(!AttributeMatcher::DashMatch("en".into()).matches("english"));
+    }
+
+    fn list_item(index: usize, siblings: usize, children: usize) -> TestBranch {
+        TestBranch(vec![
+            TestNodeData {
+                tag: "li".as_tag(),
+                position: ElementPosition {
+                    index,
+                    siblings,
+                    children,
+                },
+                ..Default::default()
+            },
+            TestNodeData {
+                tag: "ul".as_tag(),
+                position: ElementPosition {
+                    index: 0,
+                    siblings: 1,
+                    children: siblings,
+                },
+                ..Default::default()
+            },
+        ])
+    }
+
+    fn matches(selector: &str, branch: &TestBranch) -> bool {
+        let selector: Selector = selector.into();
+        selector.matches(branch)
+    }
+
+    #[test]
+    fn selector_structural_pseudo_classes() {
+        let first = list_item(0, 4, 1);
+        let second = list_item(1, 4, 0);
+        let third = list_item(2, 4, 1);
+        let last = list_item(3, 4, 1);
+        let only = list_item(0, 1, 1);
+
+        assert!(matches("li:first-child", &first));
+        assert!(!matches("li:first-child", &second));
+        assert!(matches("li:last-child", &last));
+        assert!(!matches("li:last-child", &third));
+        assert!(matches("li:only-child", &only));
+        assert!(!matches("li:only-child", &first));
+        assert!(matches("li:empty", &second));
+        assert!(!matches("li:empty", &first));
+        assert!(!matches("ul:empty li", &first));
+        assert!(matches("ul:only-child > li:first-child", &first));
+
+        // An+B
+        assert!(matches("li:nth-child(odd)", &first));
+        assert!(matches("li:nth-child(odd)", &third));
+        assert!(!matches("li:nth-child(odd)", &second));
+        assert!(matches("li:nth-child(even)", &second));
+        assert!(matches("li:nth-child(2n)", &last));
+        assert!(matches("li:nth-child(3)", &third));
+        assert!(!matches("li:nth-child(3)", &last));
+        assert!(matches("li:nth-child(-n+2)", &second));
+        assert!(!matches("li:nth-child(-n+2)", &third));
+        assert!(matches("li:nth-child(n+3)", &last));
+        assert!(!matches("li:nth-child(n+3)", &second));
+        assert!(matches("li:nth-last-child(1)", &last));
+        assert!(matches("li:nth-last-child(2n)", &third));
+        assert!(!matches("li:nth-last-child(2n)", &last));
+    }
+
+    #[test]
+    fn selector_structural_weight() {
+        let selector: Selector = "li:nth-child(2n+1)".into();
+        assert_eq!(selector.weight, SelectorWeight(11, 0));
+        assert!(matches!(
+            selector.elements[0],
+            SelectorElement::Structural(StructuralPseudoClass::NthChild(2, 1))
+        ));
+        assert_eq!(selector.to_string(), "li:nth-child(2n+1)");
+    }
+
+    #[test]
+    fn selector_negation_and_grouping() {
+        let branch: TestBranch = "div.red#id:pressed span.green span.red".into();
+        let valid_selectors: &[&str] = &[
+            "span:not(.green)",
+            "span:not(:pressed)",
+            ":not(div, .green)",
+            ":is(.green, .red)",
+            "#id :is(span, label)",
+            ":is(div) span",
+            ":is(.green .red)",
+            ":is(.green > .red)",
+            ":not(.red) > .red",
+            "span:not(:first-child:last-child)",
+        ];
+        for src in valid_selectors {
+            let selector: Selector = (*src).into();
+            assert!(
+                selector.matches(&branch),
+                "Selector '{}' should be matched",
+                src
+            );
+        }
+        let invalid_selectors: &[&str] = &[
+            "span:not(.red)",
+            ":not(span)",
+            ":is(div, .blue)",
+            ":is(.red .green)",
+            "span:not(div .red)",
+            ":is(.green) > :not(.red)",
+        ];
+        for src in invalid_selectors {
+            let selector: Selector = (*src).into();
+            assert!(
+                !selector.matches(&branch),
+                "Selector '{}' shouldn't be matched",
+                src
+            );
+        }
+    }
+
+    #[test]
+    fn selector_negation_and_grouping_weight() {
+        let cases: &[(&str, i32)] = &[
+            ("button:not(:pressed)", 11),
+            (":not(#id)", 100),
+            (":is(.a, #b) span", 101),
+            (":is(label, span)", 1),
+            (".panel :is(label, .title)", 20),
+        ];
+        for (src, weight) in cases {
+            let selector: Selector = (*src).into();
+            assert_eq!(selector.weight.0, *weight, "Weight of '{}'", src);
+        }
+    }
+
+    fn sibling(tag: &str, classes: &[&str], state: &[&str]) -> TestNodeData {
+        TestNodeData {
+            tag: tag.as_tag(),
+            classes: classes.iter().map(|c| c.as_tag()).collect(),
+            params: state.iter().map(|s| s.as_tag()).collect(),
+            ..Default::default()
+        }
+    }
+
+    /// `div.panel > (h1, button:pressed, span.icon, label)` with `label` as tail
+    fn label_after_siblings() -> TestBranch {
+        TestBranch(vec![
+            TestNodeData {
+                previous: vec![
+                    sibling("span", &["icon"], &[]),
+                    sibling("button", &[], &["pressed"]),
+                    sibling("h1", &[], &[]),
+                ],
+                ..sibling("label", &[], &[])
+            },
+            sibling("div", &["panel"], &[]),
+        ])
+    }
+
+    #[test]
+    fn selector_sibling_combinators() {
+        let branch = label_after_siblings();
+        assert!(matches("span + label", &branch));
+        assert!(matches(".icon+label", &branch));
+        assert!(!matches("button + label", &branch));
+        assert!(matches("button ~ label", &branch));
+        assert!(matches("h1 ~ label", &branch));
+        assert!(matches("h1 ~ button:pressed + span ~ label", &branch));
+        assert!(!matches("label ~ label", &branch));
+        assert!(!matches("div ~ label", &branch));
+        assert!(matches(".panel > span + label", &branch));
+        assert!(matches(".panel button ~ label", &branch));
+        assert!(matches("div > h1 ~ label", &branch));
+        assert!(!matches(".other > span + label", &branch));
+        assert!(!matches("h1 + label", &branch));
+    }
+
+    #[test]
+    fn selector_sibling_combinators_root_id() {
+        let selector: Selector = "#root > a + b".into();
+        assert_eq!(selector.get_root_id(), Some("root".as_tag()));
+        let selector: Selector = "#root ~ a b".into();
+        assert_eq!(selector.get_root_id(), None);
+        let selector: Selector = "#root + a".into();
+        assert_eq!(selector.get_root_id(), None);
+    }
 }
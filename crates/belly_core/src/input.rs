@@ -4,7 +4,7 @@ use bevy::{
     prelude::*,
     render::camera::RenderTarget,
     ui::{FocusPolicy, UiStack},
-    utils::HashSet,
+    utils::{HashMap, HashSet},
     window::{PrimaryWindow, WindowRef},
 };
 
@@ -181,6 +181,18 @@ pub struct NodeQuery {
     computed_visibility: Option<&'static ComputedVisibility>,
 }
 
+/// Checks if the `point` is inside of the node of the `size`. The point
+/// is moved to the space of the node, so the scaled and rotated nodes
+/// are hit where they are rendered.
+pub(crate) fn contains_point(size: Vec2, transform: &GlobalTransform, point: Vec2) -> bool {
+    let local = transform
+        .affine()
+        .inverse()
+        .transform_point3(point.extend(transform.translation().z));
+    let extents = size / 2.0;
+    (-extents.x..extents.x).contains(&local.x) && (-extents.y..extents.y).contains(&local.y)
+}
+
 // pointer_input_system is the rewriten bevy's ui_focus_system
 // it emit PointerEvent with associated entities and data.
 pub fn pointer_input_system(
@@ -247,20 +259,16 @@ pub fn pointer_input_system(
                     }
                 }
 
-                let position = node.global_transform.translation();
-                let ui_position = position.truncate();
-                let extents = node.node.size() / 2.0;
-                let mut min = ui_position - extents;
-                let mut max = ui_position + extents;
-                if let Some(clip) = node.calculated_clip {
-                    min = Vec2::max(min, clip.clip.min);
-                    max = Vec2::min(max, clip.clip.max);
-                }
                 // if the current cursor position is within the bounds of the node, consider it for
                 // emiting the event
                 let contains_cursor = if let Some(cursor_position) = cursor_position {
-                    (min.x..max.x).contains(&cursor_position.x)
-                        && (min.y..max.y).contains(&cursor_position.y)
+                    let clipped = node.calculated_clip.map_or(false, |clip| {
+                        let (min, max) = (clip.clip.min, clip.clip.max);
+                        !(min.x..max.x).contains(&cursor_position.x)
+                            || !(min.y..max.y).contains(&cursor_position.y)
+                    });
+                    !clipped
+                        && contains_point(node.node.size(), node.global_transform, cursor_position)
                 } else {
                     false
                 };
@@ -422,11 +430,31 @@ pub struct Focused(Option<Entity>);
 #[derive(Debug, Event)]
 pub struct RequestFocus(Entity);
 
+/// Returns `true` if the entity or any of its ancestors is hidden
+/// by the [`Visibility`] component (the `visibility: hidden` property).
+/// Unlike [`ComputedVisibility`] it reflects the changes made during
+/// the current frame.
+pub fn is_hidden(entity: Entity, visibility: &Query<(&Visibility, Option<&Parent>)>) -> bool {
+    let mut current = Some(entity);
+    while let Some(entity) = current {
+        let Ok((visibility, parent)) = visibility.get(entity) else {
+            return false;
+        };
+        match visibility {
+            Visibility::Hidden => return true,
+            Visibility::Visible => return false,
+            Visibility::Inherited => current = parent.map(|p| p.get()),
+        }
+    }
+    false
+}
+
 pub fn focus_system(
     mut focused: ResMut<Focused>,
     // mut elements: Query<(Entity, &mut Element)>,
     mut elements: Elements,
     interactable: Query<Entity, (With<Interaction>, With<Element>)>,
+    visibility: Query<(&Visibility, Option<&Parent>)>,
     mut signals: EventReader<PointerInput>,
     mut requests: EventReader<RequestFocus>,
 ) {
@@ -441,9 +469,16 @@ pub fn focus_system(
         }
     }
     for RequestFocus(entity) in requests.iter() {
+        if is_hidden(*entity, &visibility) {
+            continue;
+        }
         update_required = true;
         target_focus = Some(*entity);
     }
+    // hidden elements lose the focus
+    if !update_required && focused.0.map_or(false, |e| is_hidden(e, &visibility)) {
+        update_required = true;
+    }
 
     if update_required && target_focus != focused.0 {
         if let Some(was_focused) = focused.0 {
@@ -456,12 +491,67 @@ pub fn focus_system(
     }
 }
 
+/// The icon of the window cursor shown while the pointer is over the element
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ElementCursor(pub CursorIcon);
+
+impl Default for ElementCursor {
+    fn default() -> Self {
+        ElementCursor(CursorIcon::Default)
+    }
+}
+
+/// The window cursor managed by [`hover_system`]: the top-most hovered
+/// element, the window the pointer is over and the icons applied by
+/// belly per window along with the icons they replaced.
+#[derive(Default)]
+pub struct HoverCursor {
+    hovered: Option<Entity>,
+    window: Option<Entity>,
+    applied: HashMap<Entity, (CursorIcon, CursorIcon)>,
+}
+
+impl HoverCursor {
+    /// Applies the `icon` of the hovered element to the `window`. The elements
+    /// with the default cursor leave the window cursor untouched, the icon
+    /// replaced by belly is restored when such element gets hovered.
+    fn apply(&mut self, entity: Entity, window: &mut Window, icon: Option<CursorIcon>) {
+        let icon = icon.filter(|icon| *icon != CursorIcon::Default);
+        match (icon, self.applied.get(&entity).copied()) {
+            (Some(icon), applied) => {
+                let previous = match applied {
+                    Some((applied, previous)) if window.cursor.icon == applied => previous,
+                    _ => window.cursor.icon,
+                };
+                if window.cursor.icon != icon {
+                    window.cursor.icon = icon;
+                }
+                self.applied.insert(entity, (icon, previous));
+            }
+            (None, Some((applied, previous))) => {
+                self.applied.remove(&entity);
+                // the icon could be changed by the app in the meantime
+                if window.cursor.icon == applied {
+                    window.cursor.icon = previous;
+                }
+            }
+            (None, None) => {}
+        }
+    }
+}
+
 pub fn hover_system(
     mut events: EventReader<PointerInput>,
+    mut cursor_moved: EventReader<CursorMoved>,
     mut elements: Elements,
     mut hovered_entities: Local<HashSet<Entity>>,
+    mut cursor: Local<HoverCursor>,
+    cursors: Query<&ElementCursor>,
+    changed_cursors: Query<(), Changed<ElementCursor>>,
+    mut windows: Query<&mut Window>,
 ) {
     let mut any_motion = false;
+    let mut top_most = None;
     let new_hovered_entities: HashSet<_> = events
         .iter()
         .filter(|e| e.motion() || e.dragging())
@@ -470,8 +560,41 @@ pub fn hover_system(
             e
         })
         .flat_map(|e| e.entities.iter())
-        .map(|e| *e)
+        .map(|e| {
+            top_most.get_or_insert(*e);
+            *e
+        })
         .collect();
+
+    // the cursor of the top-most hovered element is applied to the window
+    // the pointer moves over, the cursor is updated as well when the icon
+    // of the hovered element changes without the pointer movement
+    let moved = cursor_moved.iter().last().map(|e| e.window);
+    if let Some(window) = moved {
+        if let Some(left) = cursor.window.filter(|w| *w != window) {
+            if let Ok(mut left_window) = windows.get_mut(left) {
+                cursor.apply(left, &mut left_window, None);
+            }
+        }
+        cursor.window = Some(window);
+    }
+    if moved.is_some() || any_motion {
+        cursor.hovered = top_most;
+    }
+    let icon_changed = cursor
+        .hovered
+        .map_or(false, |entity| changed_cursors.contains(entity));
+    if moved.is_some() || any_motion || icon_changed {
+        if let Some(window) = cursor.window {
+            if let Ok(mut target) = windows.get_mut(window) {
+                let icon = cursor
+                    .hovered
+                    .and_then(|entity| cursors.get(entity).ok())
+                    .map(|c| c.0);
+                cursor.apply(window, &mut target, icon);
+            }
+        }
+    }
     if !any_motion {
         return;
     }
@@ -534,13 +657,89 @@ pub fn active_system(
 pub fn tab_focus_system(
     keyboard: Res<Input<KeyCode>>,
     elements: Query<(Entity, &Element), With<Interaction>>,
+    visibility: Query<(&Visibility, Option<&Parent>)>,
     mut requests: EventWriter<RequestFocus>,
 ) {
     if !keyboard.just_pressed(KeyCode::Tab) {
         return;
     }
     for (entity, _) in elements.iter() {
+        if is_hidden(entity, &visibility) {
+            continue;
+        }
         requests.send(RequestFocus(entity));
         break;
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bevy::ecs::system::SystemState;
+
+    #[test]
+    fn inherit_hidden_visibility() {
+        let mut world = World::new();
+        let root = world.spawn(Visibility::Hidden).id();
+        let panel = world.spawn(Visibility::Inherited).set_parent(root).id();
+        let button = world.spawn(Visibility::Inherited).set_parent(panel).id();
+        let shown = world.spawn(Visibility::Visible).set_parent(panel).id();
+        let other = world.spawn(Visibility::Inherited).id();
+
+        let mut state: SystemState<Query<(&Visibility, Option<&Parent>)>> =
+            SystemState::new(&mut world);
+        let visibility = state.get(&world);
+        assert!(is_hidden(root, &visibility));
+        assert!(is_hidden(button, &visibility));
+        assert!(!is_hidden(shown, &visibility));
+        assert!(!is_hidden(other, &visibility));
+    }
+
+    #[test]
+    fn hit_transformed_node() {
+        let size = Vec2::new(100., 20.);
+        let plain = GlobalTransform::from_xyz(100., 100., 0.);
+        assert!(contains_point(size, &plain, Vec2::new(140., 105.)));
+        assert!(!contains_point(size, &plain, Vec2::new(100., 115.)));
+
+        let scaled = GlobalTransform::from(
+            Transform::from_xyz(100., 100., 0.).with_scale(Vec3::new(2., 2., 1.)),
+        );
+        assert!(contains_point(size, &scaled, Vec2::new(190., 115.)));
+        assert!(!contains_point(size, &scaled, Vec2::new(210., 100.)));
+
+        let rotated = GlobalTransform::from(
+            Transform::from_xyz(100., 100., 0.)
+                .with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_2)),
+        );
+        assert!(contains_point(size, &rotated, Vec2::new(100., 140.)));
+        assert!(!contains_point(size, &rotated, Vec2::new(140., 100.)));
+    }
+
+    #[test]
+    fn restore_replaced_cursor() {
+        let window_entity = Entity::from_raw(0);
+        let mut window = Window::default();
+        window.cursor.icon = CursorIcon::Crosshair;
+        let mut cursor = HoverCursor::default();
+
+        // the elements with the default cursor don't touch the window
+        cursor.apply(window_entity, &mut window, Some(CursorIcon::Default));
+        assert_eq!(window.cursor.icon, CursorIcon::Crosshair);
+        cursor.apply(window_entity, &mut window, None);
+        assert_eq!(window.cursor.icon, CursorIcon::Crosshair);
+
+        cursor.apply(window_entity, &mut window, Some(CursorIcon::Hand));
+        assert_eq!(window.cursor.icon, CursorIcon::Hand);
+        cursor.apply(window_entity, &mut window, Some(CursorIcon::Grab));
+        assert_eq!(window.cursor.icon, CursorIcon::Grab);
+        cursor.apply(window_entity, &mut window, None);
+        assert_eq!(window.cursor.icon, CursorIcon::Crosshair);
+
+        // the icon changed by the app is kept
+        cursor.apply(window_entity, &mut window, Some(CursorIcon::Hand));
+        window.cursor.icon = CursorIcon::Wait;
+        cursor.apply(window_entity, &mut window, Some(CursorIcon::Default));
+        assert_eq!(window.cursor.icon, CursorIcon::Wait);
+    }
+}
@@ -16,6 +16,7 @@ pub use tagstr::*;
 
 pub mod prelude {
     // funcs
+    pub use crate::ess::custom_property;
     pub use crate::ess::managed;
 
     // macro
@@ -28,6 +29,7 @@ pub mod prelude {
     pub use crate::eml::content::IntoContent;
     pub use crate::eml::Widget;
     pub use crate::ess::ColorFromHexExtension;
+    pub use crate::ess::ColorFunctionsExtension;
     pub use crate::relations::connect::ConnectCommandsExtension;
 
     // structs
@@ -102,6 +104,8 @@ pub enum ElementsError {
     /// An unexpected token was found on a style sheet rule.
     UnexpectedToken(String),
     EndOfInput,
+    /// A style sheet imports itself directly or through other style sheets.
+    ImportCycle(String),
 }
 
 impl std::error::Error for ElementsError {}
@@ -117,6 +121,7 @@ impl std::fmt::Display for ElementsError {
             ElementsError::InvalidSelector => write!(f, "Invalid selector"),
             ElementsError::UnexpectedToken(t) => write!(f, "Unexpected token: {}", t),
             ElementsError::EndOfInput => write!(f, "Unexpected end of input"),
+            ElementsError::ImportCycle(c) => write!(f, "Import cycle detected: {}", c),
         }
     }
 }
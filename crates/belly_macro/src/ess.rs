@@ -120,18 +120,60 @@ pub fn parse_ident(input: syn::parse::ParseStream) -> syn::Result<String> {
     Ok(ident)
 }
 
+/// Restores the selector source from tokens, keeping
+/// whitespaces only where they were in the original source
+fn selector_source(stream: proc_macro2::TokenStream) -> String {
+    let mut result = String::new();
+    let mut last: Option<Span> = None;
+    for tt in stream {
+        let span = tt.span();
+        if let Some(last) = last {
+            if last.has_space(&span) {
+                result.push(' ');
+            }
+        }
+        last = Some(span);
+        match tt {
+            TokenTree::Group(group) => {
+                let (open, close) = match group.delimiter() {
+                    Delimiter::Parenthesis => ("(", ")"),
+                    Delimiter::Bracket => ("[", "]"),
+                    Delimiter::Brace => ("{", "}"),
+                    Delimiter::None => ("", ""),
+                };
+                result.push_str(open);
+                result.push_str(selector_source(group.stream()).as_str());
+                result.push_str(close);
+            }
+            tt => result.push_str(tt.to_string().as_str()),
+        }
+    }
+    result
+}
+
 #[derive(Debug, Clone)]
 pub enum SelectorToken {
     Tag(String),
     Id(String),
     Class(String),
     State(String),
+    Attribute(String),
     AnyChild,
     DirectChild,
+    AdjacentSibling,
+    GeneralSibling,
     Any,
+    Parent,
 }
 
 impl SelectorToken {
+    pub fn is_combinator(&self) -> bool {
+        matches!(
+            self,
+            Self::DirectChild | Self::AdjacentSibling | Self::GeneralSibling
+        )
+    }
+
     pub fn add_str(&mut self, s: &str, span: &Span) -> Result<(), syn::Error> {
         let value = match self {
             Self::Id(i) | Self::Tag(i) | Self::Class(i) | Self::State(i) => i.clone() + s,
@@ -155,9 +197,13 @@ impl std::fmt::Display for SelectorToken {
             Self::Id(s) => write!(f, "#{s}"),
             Self::Class(s) => write!(f, ".{s}"),
             Self::State(s) => write!(f, ":{s}"),
+            Self::Attribute(s) => write!(f, "[{s}]"),
             Self::AnyChild => write!(f, " "),
             Self::DirectChild => write!(f, " > "),
+            Self::AdjacentSibling => write!(f, " + "),
+            Self::GeneralSibling => write!(f, " ~ "),
             Self::Any => write!(f, "*"),
+            Self::Parent => write!(f, "&"),
         }
     }
 }
@@ -168,12 +214,12 @@ pub struct Selector(Vec<SelectorToken>);
 impl Selector {
     pub fn push(&mut self, token: SelectorToken) {
         match (token, self.last()) {
-            (SelectorToken::DirectChild, None)
-            | (SelectorToken::AnyChild, None)
-            | (SelectorToken::AnyChild, Some(SelectorToken::AnyChild))
-            | (SelectorToken::AnyChild, Some(SelectorToken::DirectChild)) => {}
-            (SelectorToken::DirectChild, Some(SelectorToken::AnyChild)) => {
-                *self.0.last_mut().unwrap() = SelectorToken::DirectChild
+            (SelectorToken::AnyChild, None)
+            | (SelectorToken::AnyChild, Some(SelectorToken::AnyChild)) => {}
+            (SelectorToken::AnyChild, Some(last)) if last.is_combinator() => {}
+            (token, None) if token.is_combinator() => {}
+            (token, Some(SelectorToken::AnyChild)) if token.is_combinator() => {
+                *self.0.last_mut().unwrap() = token
             }
             (token, _) => self.0.push(token),
         }
@@ -188,7 +234,8 @@ impl std::fmt::Display for Selector {
                 .map(|t| t.to_string())
                 .collect::<Vec<_>>()
                 .join("")
-                .as_str(),
+                // nested selectors may start with combinator: `> span`
+                .trim_start(),
         )
     }
 }
@@ -204,7 +251,9 @@ pub enum StyleValueToken {
     Values(Vec<StyleValueToken>),
     Function(String, Vec<StyleValueToken>),
     Comma,
-    Slash
+    Slash,
+    Plus,
+    Asterisk,
 }
 
 impl StyleValueToken {
@@ -227,6 +276,8 @@ impl StyleValueToken {
             ),
             Self::Comma => format!(", "),
             Self::Slash => format!("/"),
+            Self::Plus => format!("+"),
+            Self::Asterisk => format!("*"),
         }
     }
 }
@@ -264,6 +315,25 @@ impl syn::parse::Parse for StyleValue {
             } else if input.peek(Token![/]) {
                 input.parse::<Token![/]>()?;
                 value.push(StyleValueToken::Slash);
+            } else if input.peek(Token![+]) {
+                input.parse::<Token![+]>()?;
+                value.push(StyleValueToken::Plus);
+            } else if input.peek(Token![*]) {
+                input.parse::<Token![*]>()?;
+                value.push(StyleValueToken::Asterisk);
+            } else if input.peek(syn::token::Paren) {
+                // parenthesized group of math expression: calc((100% - 10px) / 2)
+                let content;
+                syn::parenthesized!(content in input);
+                let mut group = content.parse::<StyleValue>()?;
+                value.push(StyleValueToken::Function(
+                    "".to_string(),
+                    vec![if group.len() == 1 {
+                        group.0.pop().unwrap()
+                    } else {
+                        StyleValueToken::Values(group.0)
+                    }],
+                ));
             } else if input.peek(Token![#]) {
                 input.parse::<Token![#]>()?;
                 let color = input.step(|cursor| {
@@ -397,8 +467,9 @@ impl syn::parse::Parse for StyleProperty {
 #[derive(Debug)]
 pub struct StyleRule {
     comments: Vec<String>,
-    selector: Selector,
+    selectors: Vec<Selector>,
     properties: Vec<StyleProperty>,
+    rules: Vec<StyleRule>,
 }
 
 impl std::fmt::Display for StyleRule {
@@ -407,7 +478,7 @@ impl std::fmt::Display for StyleRule {
             for comment in self.comments.iter() {
                 write!(f, "/**{comment}*/\n")?;
             }
-            write!(f, "{} {{\n", self.selector)?;
+            write!(f, "{} {{\n", self.selectors_source())?;
 
             for (idx, property) in self.properties.iter().enumerate() {
                 for (pidx, comment) in property.comments.iter().enumerate() {
@@ -416,15 +487,21 @@ impl std::fmt::Display for StyleRule {
                 }
                 write!(f, "  {}: {};\n", property.name, property.value)?;
             }
+            for rule in self.rules.iter() {
+                for line in format!("{rule:#}").lines() {
+                    write!(f, "  {line}\n")?;
+                }
+            }
             write!(f, "}}\n")
         } else {
             write!(
                 f,
                 "{} {{ {} }}",
-                self.selector.to_string(),
+                self.selectors_source(),
                 self.properties
                     .iter()
                     .map(|p| p.to_string())
+                    .chain(self.rules.iter().map(|r| r.to_string()))
                     .collect::<Vec<_>>()
                     .join(" ")
             )
@@ -432,12 +509,15 @@ impl std::fmt::Display for StyleRule {
     }
 }
 
-impl syn::parse::Parse for StyleRule {
-    fn parse(mut input: syn::parse::ParseStream) -> syn::Result<Self> {
+impl StyleRule {
+    /// Parses the rule, the `nested` rules may start with combinator
+    /// and refer the parent selector with `&`
+    fn parse_rule(mut input: syn::parse::ParseStream, nested: bool) -> syn::Result<Self> {
         let comments = parse_docs(&mut input)?;
-        // parse selector
-        let selector = input.step(|cursor| {
+        // parse comma-separated selectors
+        let selectors = input.step(|cursor| {
             let mut rest = *cursor;
+            let mut selectors = vec![];
             let mut selector = Selector(vec![]);
             let mut last = cursor.span();
             let mut token = None;
@@ -523,18 +603,76 @@ impl syn::parse::Parse for StyleRule {
                         selector.push(SelectorToken::Any);
                         token = None;
                     }
-                    TokenTree::Punct(p) if p.as_char() == '>' => {
+                    TokenTree::Punct(p) if p.as_char() == '&' => {
+                        if !nested {
+                            throw!(span, "Parent selector '&' is allowed only in nested rules");
+                        }
+                        if token.is_some() {
+                            selector.push(token.unwrap())
+                        }
+                        if has_space {
+                            selector.push(SelectorToken::AnyChild);
+                        }
+                        selector.push(SelectorToken::Parent);
+                        token = None;
+                    }
+                    TokenTree::Punct(p) if matches!(p.as_char(), '>' | '+' | '~') => {
                         if token.is_some() {
                             selector.push(token.unwrap())
                         }
-                        selector.push(SelectorToken::DirectChild);
+                        let combinator = match p.as_char() {
+                            '>' => SelectorToken::DirectChild,
+                            '+' => SelectorToken::AdjacentSibling,
+                            _ => SelectorToken::GeneralSibling,
+                        };
+                        if nested && selector.is_empty() {
+                            // relative to the parent selector: `> span`
+                            selector.0.push(combinator);
+                        } else {
+                            selector.push(combinator);
+                        }
+                        token = None;
+                    }
+                    TokenTree::Group(group) if group.delimiter() == Delimiter::Bracket => {
+                        if token.is_some() {
+                            selector.push(token.unwrap())
+                        }
+                        if has_space {
+                            selector.push(SelectorToken::AnyChild);
+                        }
+                        let attribute = selector_source(group.stream());
+                        selector.push(SelectorToken::Attribute(attribute));
+                        token = None;
+                    }
+                    TokenTree::Group(group)
+                        if group.delimiter() == Delimiter::Parenthesis
+                            && !has_space
+                            && matches!(token, Some(SelectorToken::State(_))) =>
+                    {
+                        // functional pseudo-classes: `:not(...)`, `:nth-child(...)`
+                        let args = format!("({})", selector_source(group.stream()));
+                        token.as_mut().unwrap().add_str(args.as_str(), &span)?;
+                    }
+                    TokenTree::Punct(p) if p.as_char() == ',' => {
+                        if token.is_some() {
+                            selector.push(token.unwrap())
+                        }
+                        if selector.0.is_empty() {
+                            throw!(span, "Expected selector before ','");
+                        }
+                        selectors.push(selector);
+                        selector = Selector(vec![]);
                         token = None;
                     }
                     TokenTree::Group(group) if group.delimiter() == Delimiter::Brace => {
                         if token.is_some() {
                             selector.push(token.unwrap());
                         }
-                        return Ok((selector, rest));
+                        if selector.0.is_empty() {
+                            throw!(span, "Expected selector");
+                        }
+                        selectors.push(selector);
+                        return Ok((selectors, rest));
                     }
                     e => throw!(span, "Unsupported selector: {e:?}"),
                 }
@@ -543,32 +681,54 @@ impl syn::parse::Parse for StyleRule {
             if token.is_some() {
                 selector.push(token.unwrap());
             }
-            Ok((selector, rest))
+            selectors.push(selector);
+            Ok((selectors, rest))
         })?;
 
         let mut properties = vec![];
-        // parse properties
+        let mut rules = vec![];
+        // parse properties & nested rules
         if input.is_empty() {
             throw!(input.span(), "Expected style properties block");
         } else {
             let content;
             braced!(content in input);
-            let props: Punctuated<StyleProperty, Token![;]> =
-                content.parse_terminated(StyleProperty::parse)?;
-            for prop in props {
-                properties.push(prop);
+            while !content.is_empty() {
+                let fork = content.fork();
+                if fork.parse::<StyleProperty>().is_ok()
+                    && (fork.is_empty() || fork.peek(Token![;]))
+                {
+                    properties.push(content.parse()?);
+                    if !content.is_empty() {
+                        content.parse::<Token![;]>()?;
+                    }
+                } else {
+                    rules.push(StyleRule::parse_rule(&content, true)?);
+                }
             }
-            // properties = props.iter().cloned().collect();
-            // props.
-            // println!("rest input: {props:?}");
         }
 
         Ok(StyleRule {
             comments,
-            selector,
+            selectors,
             properties,
+            rules,
         })
     }
+
+    fn selectors_source(&self) -> String {
+        self.selectors
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+impl syn::parse::Parse for StyleRule {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        StyleRule::parse_rule(input, false)
+    }
 }
 
 #[derive(Debug, Default)]
@@ -624,11 +784,23 @@ mod test {
             "tag.class > direct-child:state {  }",
             ":state any-child.class {  }",
             "tag-name.class-name :some-state #cool-id {  }",
+            "button[mode] {  }",
+            "button[mode=\"toggle\"] > span {  }",
+            "[data-kind^=prefix] child {  }",
+            "li:nth-child(2n+1) {  }",
+            "button:not(:pressed) > label {  }",
+            ".panel :is(label, span) {  }",
+            ":not(.a > .b) {  }",
+            "button:pressed + label {  }",
+            "h1 ~ p.note {  }",
+            ".panel > .a + .b ~ .c {  }",
+            "button, .panel > label {  }",
+            "a:hover, #menu span, * {  }",
         ];
         for src in selecors {
             let stream: TokenStream = src.parse().unwrap();
             let rule: StyleRule = syn::parse2(stream).unwrap();
-            println!("Selector: {:?}", rule.selector);
+            println!("Selectors: {:?}", rule.selectors);
             assert_eq!(rule.to_string().as_str(), *src);
         }
     }
@@ -656,6 +828,16 @@ mod test {
             r#"body { grid-template-columns: min-content flex(1); }"#,
             // test slash formating
             r#"body { grid-row: 2 / span 2; }"#,
+            // custom properties
+            r#"body { --accent: #ff7f00; color: var(--accent); }"#,
+            r#"body { padding: var(--gap, 5px) 10px; }"#,
+            // math functions
+            r#"body { width: calc(100% - 240px); }"#,
+            r#"body { width: clamp(200px, 30% + 10px, 50%); }"#,
+            r#"body { left: calc((100% - 20px) / 2 * 3); }"#,
+            // color functions
+            r#"body { color: rgb(255 127 0 / 50%); }"#,
+            r#"body { color: lighten(hsl(210deg, 50%, 40%), 10%); }"#,
         ];
         for src in rules {
             println!("Checking '{src}'");
@@ -694,6 +876,24 @@ span { some-bigger-prop: value; }"#;
         assert_eq!(stylesheet.to_string().as_str(), src);
     }
 
+    #[test]
+    fn test_nested_rules() {
+        let rules = &[
+            ".slot { color: white; &:hover { color: red; } .icon { width: 10px; } }",
+            ".slot { > span {  } + .slot, & ~ label { prop: value; } }",
+            ".inventory { .slot { .icon { prop: value; } } }",
+        ];
+        for src in rules {
+            println!("Checking '{src}'");
+            let stream: TokenStream = src.parse().unwrap();
+            let rule: StyleRule = syn::parse2(stream).unwrap();
+            println!("Rule: {:?}", rule);
+            assert_eq!(rule.to_string().as_str(), *src);
+        }
+        let stream: TokenStream = "&.slot { }".parse().unwrap();
+        assert!(syn::parse2::<StyleRule>(stream).is_err());
+    }
+
     #[test]
     fn test_colors() {
         let valid = &[
@@ -96,7 +96,6 @@ fn button(ctx: &mut WidgetContext) {
     } else {
         eml! {
             <span c:button interactable>
-                <span c:button-shadow s:position-type="absolute"/>
                 <span c:button-background>
                     <span c:button-foreground>
                         {content}
@@ -114,32 +113,29 @@ ess_define! {
         min-width: 40px;
         min-height: 40px;
         margin: 5px;
+        cursor: pointer;
     }
     button:hover > span > .button-foreground {
         background-color: white;
     }
     button:active > .button-background {
         margin: 1px -1px -1px 1px;
+        box-shadow: none;
     }
     button:pressed > .button-background {
         margin: 1px -1px -1px 1px;
+        box-shadow: none;
     }
     button:pressed > span > .button-foreground {
         background-color: #bfbfbf;
     }
-    .button-shadow {
-        background-color: #4f4f4fb8;
-        top: 1px;
-        left: 1px;
-        bottom: -1px;
-        right: -1px;
-    }
     .button-background {
         width: 100%;
         height: 100%;
         margin: -1px 1px 1px -1px;
         padding: 1px;
         background-color: #2f2f2f;
+        box-shadow: 2px 2px #4f4f4fb8;
     }
     .button-foreground {
         width: 100%;
@@ -29,6 +29,10 @@ impl Plugin for SliderPlugin {
       min-height: 16px;
       width: 16px;
       height: 16px;
+      cursor: grab;
+    }
+    slider .slider-grabber:active {
+      cursor: grabbing;
     }
 )]
 fn slider(ctx: &mut WidgetContext) {
@@ -69,6 +69,7 @@ ess_define! {
     TEXTINPUT_STYLES,
     .text-input {
         width: 200px;
+        cursor: text;
     }
     .text-input-border {
         background-color: #2f2f2f00;
@@ -0,0 +1,284 @@
+use crate::Stylebox;
+use bevy::{
+    asset::{AssetLoader, AssetPath, LoadContext, LoadedAsset},
+    math::Rect,
+    prelude::*,
+    reflect::{TypePath, TypeUuid},
+    utils::{BoxedFuture, HashMap},
+};
+
+/// The region of the atlas referenced either by its index or by its name
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum AtlasRegion {
+    Index(usize),
+    Name(String),
+}
+
+impl From<usize> for AtlasRegion {
+    fn from(index: usize) -> Self {
+        AtlasRegion::Index(index)
+    }
+}
+
+impl From<&str> for AtlasRegion {
+    fn from(name: &str) -> Self {
+        AtlasRegion::Name(name.to_string())
+    }
+}
+
+impl std::fmt::Display for AtlasRegion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AtlasRegion::Index(index) => write!(f, "{index}"),
+            AtlasRegion::Name(name) => write!(f, "\"{name}\""),
+        }
+    }
+}
+
+/// Specifies the atlas region to be used as a stylebox image instead of
+/// the whole `Stylebox.texture`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum StyleboxAtlas {
+    /// The region of the bevy `TextureAtlas` with the provided index
+    TextureAtlas(Handle<TextureAtlas>, usize),
+    /// The indexed or the named region of the `SpriteAtlas`
+    Sprite(Handle<SpriteAtlas>, AtlasRegion),
+}
+
+impl StyleboxAtlas {
+    /// Returns the atlas texture and the region of the atlas in pixels
+    /// or `None` if the atlas isn't loaded yet or doesn't have such region.
+    pub fn resolve(
+        &self,
+        texture_atlases: &Assets<TextureAtlas>,
+        sprite_atlases: &Assets<SpriteAtlas>,
+    ) -> Option<(Handle<Image>, Rect)> {
+        match self {
+            StyleboxAtlas::TextureAtlas(handle, index) => {
+                let atlas = texture_atlases.get(handle)?;
+                let rect = atlas.textures.get(*index)?;
+                Some((atlas.texture.clone_weak(), *rect))
+            }
+            StyleboxAtlas::Sprite(handle, region) => {
+                let atlas = sprite_atlases.get(handle)?;
+                Some((atlas.texture.clone_weak(), atlas.region(region)?))
+            }
+        }
+    }
+
+    pub(crate) fn is_loaded(
+        &self,
+        texture_atlases: &Assets<TextureAtlas>,
+        sprite_atlases: &Assets<SpriteAtlas>,
+    ) -> bool {
+        match self {
+            StyleboxAtlas::TextureAtlas(handle, _) => texture_atlases.contains(handle),
+            StyleboxAtlas::Sprite(handle, _) => sprite_atlases.contains(handle),
+        }
+    }
+}
+
+/// The texture with the named regions loaded from the `.atlas` file:
+/// ```text
+/// # the path to the texture relative to the atlas file
+/// texture: ui.png
+/// # the regions in `name: x y width height` format (in pixels)
+/// panel_blue: 0 0 100 100
+/// panel_green: 100 0 100 100
+/// ```
+/// The regions can be referenced by names or by indices in the order they are
+/// defined in the file, so the texture can be repacked without touching the
+/// styles using it.
+#[derive(TypeUuid, TypePath, Clone, Debug, Default)]
+#[uuid = "4c3bd4b1-0b3e-4d52-9c0e-5be0a6a3f7c1"]
+pub struct SpriteAtlas {
+    pub texture: Handle<Image>,
+    pub regions: Vec<Rect>,
+    pub names: HashMap<String, usize>,
+}
+
+impl SpriteAtlas {
+    /// Returns the index of the region with the provided `name`
+    pub fn index(&self, name: &str) -> Option<usize> {
+        self.names.get(name).copied()
+    }
+
+    /// Returns the rect of the `region` in pixels
+    pub fn region(&self, region: &AtlasRegion) -> Option<Rect> {
+        let index = match region {
+            AtlasRegion::Index(index) => *index,
+            AtlasRegion::Name(name) => self.index(name)?,
+        };
+        self.regions.get(index).copied()
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct AtlasDescription {
+    texture: String,
+    regions: Vec<(String, Rect)>,
+}
+
+fn parse_atlas(source: &str) -> Result<AtlasDescription, String> {
+    let mut texture = None;
+    let mut regions = vec![];
+    for (idx, line) in source.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':') else {
+            return Err(format!(
+                "line {}: expected `name: value`, got `{line}`",
+                idx + 1
+            ));
+        };
+        let (key, value) = (key.trim(), value.trim());
+        if key == "texture" {
+            texture = Some(value.to_string());
+            continue;
+        }
+        let numbers = value
+            .split_whitespace()
+            .map(|num| num.parse::<f32>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("line {}: {e}", idx + 1))?;
+        let [x, y, width, height] = numbers[..] else {
+            return Err(format!(
+                "line {}: expected `{key}: x y width height`, got `{line}`",
+                idx + 1
+            ));
+        };
+        if regions.iter().any(|(name, _)| name == key) {
+            return Err(format!("line {}: region `{key}` defined twice", idx + 1));
+        }
+        let rect = Rect::new(x, y, x + width, y + height);
+        regions.push((key.to_string(), rect));
+    }
+    let Some(texture) = texture else {
+        return Err("the `texture` is not specified".to_string());
+    };
+    Ok(AtlasDescription { texture, regions })
+}
+
+#[derive(Default)]
+pub struct SpriteAtlasLoader;
+
+impl AssetLoader for SpriteAtlasLoader {
+    fn extensions(&self) -> &[&str] {
+        &["atlas"]
+    }
+
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let source = std::str::from_utf8(bytes)?;
+            let path = load_context.path().to_path_buf();
+            let description = parse_atlas(source).map_err(|err| {
+                bevy::asset::Error::msg(format!("Unable to parse {}: {err}", path.display()))
+            })?;
+            let texture_path = path
+                .parent()
+                .map(|dir| dir.join(&description.texture))
+                .unwrap_or_else(|| description.texture.clone().into());
+            let texture_path = AssetPath::new(texture_path, None);
+            let mut atlas = SpriteAtlas {
+                texture: load_context.get_handle(texture_path.clone()),
+                ..default()
+            };
+            for (name, rect) in description.regions {
+                atlas.names.insert(name, atlas.regions.len());
+                atlas.regions.push(rect);
+            }
+            load_context.set_default_asset(LoadedAsset::new(atlas).with_dependency(texture_path));
+            Ok(())
+        })
+    }
+}
+
+/// Marks the styleboxes using the modified atlases as changed,
+/// so the regions are resolved again when the atlas is repacked.
+pub fn track_stylebox_atlases(
+    mut texture_atlas_events: EventReader<AssetEvent<TextureAtlas>>,
+    mut sprite_atlas_events: EventReader<AssetEvent<SpriteAtlas>>,
+    mut styleboxes: Query<&mut Stylebox>,
+) {
+    let mut texture_atlases = vec![];
+    let mut sprite_atlases = vec![];
+    for event in texture_atlas_events.iter() {
+        if let AssetEvent::Modified { handle } = event {
+            texture_atlases.push(handle.id());
+        }
+    }
+    for event in sprite_atlas_events.iter() {
+        if let AssetEvent::Modified { handle } = event {
+            sprite_atlases.push(handle.id());
+        }
+    }
+    if texture_atlases.is_empty() && sprite_atlases.is_empty() {
+        return;
+    }
+    for mut stylebox in styleboxes.iter_mut() {
+        let modified = match &stylebox.atlas {
+            Some(StyleboxAtlas::TextureAtlas(handle, _)) => texture_atlases.contains(&handle.id()),
+            Some(StyleboxAtlas::Sprite(handle, _)) => sprite_atlases.contains(&handle.id()),
+            None => false,
+        };
+        if modified {
+            stylebox.set_changed();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_atlas_description() {
+        let description = parse_atlas(
+            "
+            # ui frames
+            texture: frames/ui.png
+            panel_blue: 0 0 100 100
+            panel_green: 100 0 100 50.5
+            ",
+        )
+        .unwrap();
+        assert_eq!(description.texture, "frames/ui.png");
+        assert_eq!(
+            description.regions,
+            vec![
+                ("panel_blue".to_string(), Rect::new(0., 0., 100., 100.)),
+                ("panel_green".to_string(), Rect::new(100., 0., 200., 50.5)),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_invalid_atlas_description() {
+        assert!(parse_atlas("panel: 0 0 10 10").is_err());
+        assert!(parse_atlas("texture: ui.png\npanel: 0 0 10").is_err());
+        assert!(parse_atlas("texture: ui.png\npanel: 0 0 ten 10").is_err());
+        assert!(parse_atlas("texture: ui.png\npanel 0 0 10 10").is_err());
+        assert!(parse_atlas("texture: ui.png\na: 0 0 1 1\na: 1 1 1 1").is_err());
+    }
+
+    #[test]
+    fn sprite_atlas_regions() {
+        let mut atlas = SpriteAtlas::default();
+        atlas.regions.push(Rect::new(0., 0., 10., 10.));
+        atlas.regions.push(Rect::new(10., 0., 30., 10.));
+        atlas.names.insert("panel".to_string(), 1);
+        assert_eq!(atlas.region(&0.into()), Some(Rect::new(0., 0., 10., 10.)));
+        assert_eq!(
+            atlas.region(&"panel".into()),
+            Some(Rect::new(10., 0., 30., 10.))
+        );
+        assert_eq!(atlas.region(&2.into()), None);
+        assert_eq!(atlas.region(&"missing".into()), None);
+    }
+}
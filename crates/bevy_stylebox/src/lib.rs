@@ -1,8 +1,13 @@
 /// The `bevy_stylebox` is plugin for [bevy](https://bevyengine.org/) engine which
 /// allows you to fill UI node with sliced by 9 parts region of image. `Stylebox`
 /// doesn't add any additional UI components. It renders just like `UiImage`, but
-/// generates more vertices in the rendering system. Only `stretch` mode is
-/// supported for now for drawing edges, `repeat` & `round` coming soon.
+/// generates more vertices in the rendering system. The edges and the middle
+/// are stretched by default, or tiled with `repeat`, `round` & `space` modes.
+/// The image may be taken from the region of the `TextureAtlas` or the named
+/// region of the `SpriteAtlas` loaded from the `.atlas` file.
+mod atlas;
+
+pub use atlas::*;
 use bevy::{
     math::Rect,
     prelude::*,
@@ -27,10 +32,19 @@ const EPSILON: f32 = 0.000005;
 const TWO_EPSILONS: f32 = EPSILON + EPSILON;
 const ONE_MINUS_EPSILON: f32 = 1.0 - EPSILON;
 const ONE_MINUS_TWO_EPSILONS: f32 = ONE_MINUS_EPSILON - EPSILON;
+/// Tiling falls back to stretching for the tiny tiles
+const MIN_TILE_SIZE: f32 = 0.5;
+const MAX_TILES: f32 = 1024.;
 
 impl Plugin for StyleboxPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, compute_stylebox_configuration)
+        app.add_asset::<TextureAtlas>()
+            .add_asset::<SpriteAtlas>()
+            .init_asset_loader::<SpriteAtlasLoader>()
+            .add_systems(
+                Update,
+                (track_stylebox_atlases, compute_stylebox_configuration).chain(),
+            )
             .add_systems(PostUpdate, compute_stylebox_slices)
             .sub_app_mut(RenderApp)
             .add_systems(
@@ -66,6 +80,10 @@ pub struct StyleboxBundle {
 pub struct Stylebox {
     /// holds the handle to the image to be used as a stylebox
     pub texture: Handle<Image>,
+    /// specifies the atlas region to be used as a stylebox image. When provided,
+    /// the atlas texture is used instead of `texture` and `region` is
+    /// relative to the atlas region.
+    pub atlas: Option<StyleboxAtlas>,
     /// specifies how to slice the image region specified by texture & region
     /// The image is always sliced into nine sections: four corners, four edges and the middle.
     /// - when `Val::Px` specified, region sliced to the exact amount of pixels
@@ -89,16 +107,20 @@ pub struct Stylebox {
     pub region: UiRect,
     /// specifies what color the original image should be multiplied by
     pub modulate: Color,
+    /// specifies how the edges and the middle fill the space between the corners
+    pub repeat: StyleboxRepeat,
 }
 
 impl Default for Stylebox {
     fn default() -> Self {
         Stylebox {
             texture: Handle::<Image>::default(),
+            atlas: None,
             slice: UiRect::default(),
             width: UiRect::all(Val::Percent(100.)),
             modulate: Color::WHITE,
             region: UiRect::all(Val::Px(0.)),
+            repeat: StyleboxRepeat::default(),
         }
     }
 }
@@ -107,12 +129,52 @@ impl std::fmt::Display for Stylebox {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "Stylebox (\n  slice: {:?},\n  width: {:?},\n  region: {:?}\n)",
-            self.slice, self.width, self.region
+            "Stylebox (\n  slice: {:?},\n  width: {:?},\n  region: {:?},\n  repeat: {:?}\n)",
+            self.slice, self.width, self.region, self.repeat
         )
     }
 }
 
+/// Specifies how the edge (or the middle) slice fills the space, like the
+/// css `border-image-repeat` does.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RepeatMode {
+    /// The slice is stretched to fill the space
+    #[default]
+    Stretch,
+    /// The slice is tiled and the tiles are centered, the tiles at
+    /// the ends are clipped
+    Repeat,
+    /// The slice is tiled and the tiles are rescaled to fill the space
+    /// with the whole number of tiles
+    Round,
+    /// The slice is tiled with the whole number of tiles, the space
+    /// left is distributed around the tiles
+    Space,
+}
+
+/// The repeat modes of the stylebox slices:
+/// - `horizontal` is applied to the top and bottom edges and to the middle
+/// - `vertical` is applied to the left and right edges and to the middle
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StyleboxRepeat {
+    pub horizontal: RepeatMode,
+    pub vertical: RepeatMode,
+}
+
+impl StyleboxRepeat {
+    pub fn new(horizontal: RepeatMode, vertical: RepeatMode) -> StyleboxRepeat {
+        StyleboxRepeat {
+            horizontal,
+            vertical,
+        }
+    }
+
+    pub fn all(mode: RepeatMode) -> StyleboxRepeat {
+        StyleboxRepeat::new(mode, mode)
+    }
+}
+
 #[derive(Default, Debug)]
 struct UiRectF32 {
     left: f32,
@@ -143,27 +205,56 @@ pub struct ComputedStylebox {
     // edges width relative to slices edges
     width: UiRectF32,
     region: Rect,
+    repeat: StyleboxRepeat,
+    // the image resolved from `Stylebox.texture` or `Stylebox.atlas`
+    texture: Handle<Image>,
 }
 /// Calculates exact values for `Stylebox` when image size is available and stores it into `ComputedStylebox`.
+/// When `Stylebox.atlas` is provided, the atlas region is resolved first.
 pub fn compute_stylebox_configuration(
     mut commands: Commands,
     images: Res<Assets<Image>>,
+    texture_atlases: Res<Assets<TextureAtlas>>,
+    sprite_atlases: Res<Assets<SpriteAtlas>>,
     mut styleboxes: Query<
         (Entity, &Stylebox, Option<&mut ComputedStylebox>),
         Or<(Changed<Stylebox>, Without<ComputedStylebox>)>,
     >,
 ) {
     for (entity, stylebox, computed) in styleboxes.iter_mut() {
-        if stylebox.texture == Handle::<Image>::default() {
-            if computed.is_none() {
+        let resolved = match &stylebox.atlas {
+            None => Some((stylebox.texture.clone_weak(), None)),
+            Some(atlas) => match atlas.resolve(&texture_atlases, &sprite_atlases) {
+                Some((texture, rect)) => Some((texture, Some(rect))),
+                None if atlas.is_loaded(&texture_atlases, &sprite_atlases) => {
+                    warn!("Unable to resolve the stylebox atlas region: {atlas:?}");
+                    None
+                }
+                None => {
+                    // wait until the atlas is loaded
+                    if computed.is_some() {
+                        commands
+                            .entity(entity)
+                            .remove::<StyleboxSlices>()
+                            .remove::<ComputedStylebox>();
+                    }
+                    continue;
+                }
+            },
+        };
+        let resolved = resolved.filter(|(texture, _)| *texture != Handle::<Image>::default());
+        let Some((texture, atlas_rect)) = resolved else {
+            if let Some(mut computed) = computed {
+                *computed = ComputedStylebox::default();
+            } else {
                 commands
                     .entity(entity)
                     .insert(StyleboxSlices::default())
                     .insert(ComputedStylebox::default());
-                continue;
             }
-        }
-        match images.get(&stylebox.texture) {
+            continue;
+        };
+        match images.get(&texture) {
             None => {
                 if computed.is_some() {
                     commands
@@ -173,7 +264,11 @@ pub fn compute_stylebox_configuration(
                 }
             }
             Some(image) => {
-                let size = image.size();
+                let bounds = atlas_rect.unwrap_or(Rect {
+                    min: Vec2::ZERO,
+                    max: image.size(),
+                });
+                let size = bounds.size();
                 let region_left = match stylebox.region.left {
                     Val::Percent(percent) => size.x * percent * 0.01,
                     Val::Px(px) => px,
@@ -195,10 +290,10 @@ pub fn compute_stylebox_configuration(
                     _ => 0.,
                 };
                 let region = Rect {
-                    min: Vec2::new(region_left, region_top),
+                    min: bounds.min + Vec2::new(region_left, region_top),
                     max: Vec2::new(
-                        (size.x - region_right).max(region_left),
-                        (size.y - region_bottom).max(region_top),
+                        (bounds.max.x - region_right).max(bounds.min.x + region_left),
+                        (bounds.max.y - region_bottom).max(bounds.min.y + region_top),
                     ),
                 };
                 let size = region.size();
@@ -251,6 +346,8 @@ pub fn compute_stylebox_configuration(
                     computed.region = region;
                     computed.slice = slice;
                     computed.width = width;
+                    computed.repeat = stylebox.repeat;
+                    computed.texture = texture;
                 } else {
                     commands
                         .entity(entity)
@@ -259,6 +356,8 @@ pub fn compute_stylebox_configuration(
                             region,
                             slice,
                             width,
+                            repeat: stylebox.repeat,
+                            texture,
                         });
                 }
             }
@@ -333,36 +432,142 @@ pub fn compute_stylebox_slices(
         ];
         let img_height = &[top * rsize.y, (1. - bot - top) * rsize.y, bot * rsize.y];
 
-        for row in 0..3 {
-            for col in 0..3 {
-                if ui_width[row] < EPSILON || ui_height[col] < EPSILON {
+        // the tiles are scaled with the edges they belong to: the top and the
+        // bottom edges scale the horizontal tiles, the left and the right edges
+        // scale the vertical ones, the middle is scaled like the top & left edges
+        let factor = |ui: f32, img: f32| (ui > EPSILON && img > 1.).then(|| ui / img);
+        let top_factor = factor(h0, img_height[0]);
+        let bottom_factor = factor(h2, img_height[2]);
+        let left_factor = factor(w0, img_width[0]);
+        let right_factor = factor(w2, img_width[2]);
+        let tile_width = [
+            top_factor.or(bottom_factor),
+            top_factor.or(bottom_factor),
+            bottom_factor.or(top_factor),
+        ]
+        .map(|factor| img_width[1] * factor.unwrap_or(1.));
+        let tile_height = [
+            left_factor.or(right_factor),
+            left_factor.or(right_factor),
+            right_factor.or(left_factor),
+        ]
+        .map(|factor| img_height[1] * factor.unwrap_or(1.));
+
+        for col in 0..3 {
+            for row in 0..3 {
+                if ui_width[col] < EPSILON || ui_height[row] < EPSILON {
                     continue;
                 }
-                let uirect = Rect {
-                    min: Vec2::new(ui_x[row], ui_y[col]),
-                    max: Vec2::new(ui_x[row] + ui_width[row], ui_y[col] + ui_height[col]),
+                let columns = if col == 1 {
+                    tile_axis(stylebox.repeat.horizontal, w0, w1, tile_width[row])
+                } else {
+                    vec![Tile::whole(ui_x[col], ui_width[col])]
                 };
-
-                let imgrect = Rect {
-                    min: Vec2::new(img_x[row], img_y[col]),
-                    max: Vec2::new(img_x[row] + img_width[row], img_y[col] + img_height[col]),
+                let rows = if row == 1 {
+                    tile_axis(stylebox.repeat.vertical, h0, h1, tile_height[col])
+                } else {
+                    vec![Tile::whole(ui_y[row], ui_height[row])]
                 };
+                for x in columns.iter() {
+                    for y in rows.iter() {
+                        let uirect = Rect {
+                            min: Vec2::new(x.offset, y.offset),
+                            max: Vec2::new(x.offset + x.size, y.offset + y.size),
+                        };
+                        let imgrect = Rect {
+                            min: Vec2::new(
+                                img_x[col] + x.from * img_width[col],
+                                img_y[row] + y.from * img_height[row],
+                            ),
+                            max: Vec2::new(
+                                img_x[col] + x.to * img_width[col],
+                                img_y[row] + y.to * img_height[row],
+                            ),
+                        };
 
-                let center = 0.5 * (uirect.min + uirect.max);
-                let offset = center - size * 0.5;
-                let scale = uirect.size() / imgrect.size();
-                let mut tr = Mat4::IDENTITY;
-                tr *= Mat4::from_translation(offset.extend(0.));
-                tr *= Mat4::from_scale(scale.extend(1.));
-                transforms.items.push(StyleboxSlice {
-                    transform: tr,
-                    region: imgrect,
-                });
+                        let center = 0.5 * (uirect.min + uirect.max);
+                        let offset = center - size * 0.5;
+                        let scale = uirect.size() / imgrect.size();
+                        let mut tr = Mat4::IDENTITY;
+                        tr *= Mat4::from_translation(offset.extend(0.));
+                        tr *= Mat4::from_scale(scale.extend(1.));
+                        transforms.items.push(StyleboxSlice {
+                            transform: tr,
+                            region: imgrect,
+                        });
+                    }
+                }
             }
         }
     }
 }
 
+/// The single tile along the axis: the offset and the size in the node
+/// space and the part of the slice (`0..1`) it shows
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Tile {
+    offset: f32,
+    size: f32,
+    from: f32,
+    to: f32,
+}
+
+impl Tile {
+    fn whole(offset: f32, size: f32) -> Tile {
+        Tile {
+            offset,
+            size,
+            from: 0.,
+            to: 1.,
+        }
+    }
+}
+
+/// Splits the space of the `length` starting at `offset` into the tiles
+/// of the `tile` size according to the repeat `mode`
+fn tile_axis(mode: RepeatMode, offset: f32, length: f32, tile: f32) -> Vec<Tile> {
+    if mode == RepeatMode::Stretch || tile < MIN_TILE_SIZE || length / tile > MAX_TILES {
+        return vec![Tile::whole(offset, length)];
+    }
+    match mode {
+        RepeatMode::Stretch => unreachable!(),
+        RepeatMode::Round => {
+            let count = (length / tile).round().max(1.);
+            let size = length / count;
+            (0..count as usize)
+                .map(|idx| Tile::whole(offset + idx as f32 * size, size))
+                .collect()
+        }
+        RepeatMode::Space => {
+            let count = (length / tile).floor();
+            let gap = (length - count * tile) / (count + 1.);
+            (0..count as usize)
+                .map(|idx| Tile::whole(offset + gap + idx as f32 * (tile + gap), tile))
+                .collect()
+        }
+        RepeatMode::Repeat => {
+            // the middle tile is centered, so the first one may start before the offset
+            let center = (length - tile) * 0.5;
+            let mut start = center - (center / tile).ceil() * tile;
+            let mut tiles = vec![];
+            while start < length {
+                let from = start.max(0.);
+                let to = (start + tile).min(length);
+                if to - from > EPSILON {
+                    tiles.push(Tile {
+                        offset: offset + from,
+                        size: to - from,
+                        from: (from - start) / tile,
+                        to: (to - start) / tile,
+                    });
+                }
+                start += tile;
+            }
+            tiles
+        }
+    }
+}
+
 fn normalize_axis(left: f32, right: f32) -> (f32, f32) {
     let mut x0 = left;
     let mut x1 = 1. - right;
@@ -388,7 +593,7 @@ fn normalize_axis(left: f32, right: f32) -> (f32, f32) {
     (x0, 1. - x1)
 }
 
-/// Extracts stylebox vertices into render pipeline based on `Stylebox.texture`,
+/// Extracts stylebox vertices into render pipeline based on `ComputedStylebox` texture,
 /// `Stylebox.modulate` and `StyleboxSlices`
 pub fn extract_stylebox(
     mut extracted_uinodes: ResMut<ExtractedUiNodes>,
@@ -399,6 +604,7 @@ pub fn extract_stylebox(
             &Node,
             &GlobalTransform,
             &Stylebox,
+            &ComputedStylebox,
             &StyleboxSlices,
             &ComputedVisibility,
             Option<&CalculatedClip>,
@@ -406,13 +612,15 @@ pub fn extract_stylebox(
     >,
 ) {
     for (stack_index, entity) in ui_stack.uinodes.iter().enumerate() {
-        let Ok((uinode, transform, stylebox, slices, visibility, clip)) = uinode_query.get(*entity) else {
-            continue
+        let Ok((uinode, transform, stylebox, computed, slices, visibility, clip)) =
+            uinode_query.get(*entity)
+        else {
+            continue;
         };
         if !visibility.is_visible() {
             continue;
         }
-        let image = stylebox.texture.clone_weak();
+        let image = computed.texture.clone_weak();
         // Skip unloaded images
         if !images.contains(&image) {
             continue;
@@ -440,3 +648,40 @@ pub fn extract_stylebox(
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sizes(tiles: &[Tile]) -> Vec<(f32, f32)> {
+        tiles.iter().map(|t| (t.offset, t.size)).collect()
+    }
+
+    #[test]
+    fn tile_axis_modes() {
+        let stretch = tile_axis(RepeatMode::Stretch, 10., 100., 30.);
+        assert_eq!(stretch, vec![Tile::whole(10., 100.)]);
+
+        let round = tile_axis(RepeatMode::Round, 0., 100., 28.);
+        assert_eq!(
+            sizes(&round),
+            vec![(0., 25.), (25., 25.), (50., 25.), (75., 25.)]
+        );
+
+        let space = tile_axis(RepeatMode::Space, 0., 100., 30.);
+        assert_eq!(sizes(&space), vec![(2.5, 30.), (35., 30.), (67.5, 30.)]);
+
+        let repeat = tile_axis(RepeatMode::Repeat, 0., 100., 40.);
+        assert_eq!(sizes(&repeat), vec![(0., 30.), (30., 40.), (70., 30.)]);
+        assert_eq!((repeat[0].from, repeat[0].to), (0.25, 1.));
+        assert_eq!((repeat[2].from, repeat[2].to), (0., 0.75));
+    }
+
+    #[test]
+    fn tile_axis_fallback_to_stretch() {
+        let tiny = tile_axis(RepeatMode::Repeat, 0., 100., 0.);
+        assert_eq!(tiny, vec![Tile::whole(0., 100.)]);
+        let too_many = tile_axis(RepeatMode::Round, 0., 10000., 1.);
+        assert_eq!(too_many, vec![Tile::whole(0., 10000.)]);
+    }
+}